@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::{Handle, Texture, Timer, Vec2, Vec3};
+
+pub(crate) struct SnekHead {
+    pub(crate) direction: Direction,
+    /// Turns pressed since the last tick, oldest first, taken one per tick.
+    pub(crate) turns: VecDeque<Direction>,
+    pub(crate) ticks_since_turn: u32,
+}
+
+pub(crate) struct SnekSegment;
+
+/// Where a segment is drawn under [`Wobble`](crate::Wobble), and how fast that is moving.
+pub(crate) struct SegmentSpring {
+    pub(crate) position: Vec3,
+    pub(crate) velocity: Vec3,
+}
+
+pub(crate) struct Food;
+
+/// Food spawned together under [`FoodSpawn::Cluster`](crate::FoodSpawn::Cluster), by spawn.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) struct FoodCluster(pub(crate) u32);
+
+/// Points left behind by the snake under [`CoinDrops`](crate::CoinDrops), until they
+/// expire. The head picks them up by passing over them.
+pub(crate) struct Coin {
+    pub(crate) value: u32,
+    pub(crate) lifetime: Timer,
+}
+
+/// The [`RunTime`](crate::resources::RunTime) at which a piece of food appeared.
+pub(crate) struct SpawnedAt(pub(crate) Duration);
+
+/// What eating a piece of food does besides the usual growth.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum FoodKind {
+    Normal,
+    /// Starts a food magnet.
+    Magnet,
+    /// Grants a wall break charge.
+    WallBreak,
+    /// Shrinks the snake instead of growing it.
+    Poison,
+    /// Swaps the arrow keys around for a while, see [`ReverseConfig`](crate::ReverseConfig).
+    Reverse,
+    /// Lets the snake pass through walls and itself, see
+    /// [`InvincibleConfig`](crate::InvincibleConfig).
+    Invincible,
+    /// Makes normal food worth more for a while, see
+    /// [`DoubleScoreConfig`](crate::DoubleScoreConfig).
+    DoubleScore,
+    /// Lets the snake cross its own body for a while, see [`PhaseConfig`](crate::PhaseConfig).
+    Phase,
+    /// Cuts the snake in half and leaves the back half behind as a chain of walls, see
+    /// [`SplitConfig`](crate::SplitConfig).
+    Split,
+    /// Blows up every other food nearby for extra points, see
+    /// [`ExplosiveConfig`](crate::ExplosiveConfig).
+    Explosive,
+    /// Golden food that grows the snake by several segments but is only out for a while,
+    /// see [`BonusFoodConfig`](crate::BonusFoodConfig).
+    Bonus,
+    /// Slows the snake down for a while, see [`SlowConfig`](crate::SlowConfig).
+    Slow,
+    /// Grows the snake by `growth` segments in one bite instead of one.
+    Mega {
+        growth: u32,
+    },
+}
+
+pub(crate) struct WallBreakText;
+
+pub(crate) struct RunTimeText;
+
+pub(crate) struct LivesText;
+
+pub(crate) struct ScoreText;
+
+pub(crate) struct LengthText;
+
+/// A line of the title menu, shown until the game is started.
+pub(crate) struct MenuText(pub(crate) &'static str);
+
+/// A line of the theme shop under the title menu.
+#[derive(Copy, Clone)]
+pub(crate) enum ShopText {
+    /// Every theme with its price, or whether it is unlocked or picked.
+    Themes,
+    /// What the last key press in the shop did.
+    Notice,
+}
+
+pub(crate) struct LevelText;
+
+pub(crate) struct WrapText;
+
+pub(crate) struct TutorialText;
+
+pub(crate) struct QuitText;
+
+pub(crate) struct GameOverText;
+
+pub(crate) struct PauseText;
+
+pub(crate) struct BoostBar;
+
+pub(crate) struct StarvationBar;
+
+/// Points floating up from where the head was when they were scored.
+pub(crate) struct ScorePopup {
+    pub(crate) origin: Vec3,
+    pub(crate) timer: Timer,
+}
+
+pub(crate) struct BeatMarker;
+
+pub(crate) struct Ghost;
+
+/// A sprite of the death replay, standing in for the hidden board.
+pub(crate) struct ReplaySprite;
+
+/// How a hazard moves across the grid on every movement tick.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MovePattern {
+    /// Step by `(dx, dy)`, reversing each component when it would leave the arena.
+    Bounce { dx: i32, dy: i32 },
+}
+
+impl MovePattern {
+    pub fn horizontal() -> Self {
+        Self::Bounce { dx: 1, dy: 0 }
+    }
+
+    pub fn vertical() -> Self {
+        Self::Bounce { dx: 0, dy: 1 }
+    }
+}
+
+/// A static obstacle that kills the snake on contact.
+pub(crate) struct Wall;
+
+/// A tail segment fired by [`ShootTail`](crate::ShootTail), flying one cell per movement
+/// tick until it hits a wall or an enemy or leaves the arena.
+pub(crate) struct Projectile {
+    pub(crate) direction: Direction,
+}
+
+/// A roaming hazard that kills the snake on contact.
+pub(crate) struct Enemy {
+    pub(crate) pattern: MovePattern,
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    /// The cell next to this one in direction `dir`.
+    pub(crate) fn step(self, dir: Direction) -> Position {
+        let (dx, dy) = dir.delta();
+        Position {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+}
+
+/// Draw order of a sprite; entities without a layer are drawn at `Layer::DEFAULT`.
+#[derive(Copy, Clone)]
+pub(crate) struct Layer(pub(crate) f32);
+
+impl Layer {
+    /// The 2d camera sees nothing below 0, so everything else has to stack above this.
+    pub(crate) const BACKDROP: Layer = Layer(0.0);
+    pub(crate) const BACKGROUND: Layer = Layer(1.0);
+    pub(crate) const GRID: Layer = Layer(1.5);
+    pub(crate) const PAINT: Layer = Layer(2.0);
+    pub(crate) const MARKINGS: Layer = Layer(5.0);
+    pub(crate) const FOG: Layer = Layer(6.0);
+    pub(crate) const DEFAULT: Layer = Layer(10.0);
+    pub(crate) const OVERLAY: Layer = Layer(20.0);
+}
+
+/// A cell colored in by [`PaintMode`](crate::PaintMode).
+pub(crate) struct PaintTile;
+
+/// A bit of an explosion flying out of its cell at `heading` cells per second until it
+/// burns out.
+pub(crate) struct Spark {
+    pub(crate) heading: Vec2,
+    pub(crate) timer: Timer,
+}
+
+/// A strip along the arena edge the snake would leave through when heading in this direction.
+pub(crate) struct Border(pub(crate) Direction);
+
+/// The line between cells `index - 1` and `index`, across the arena if `vertical` is false
+/// and up it otherwise, shown under [`GridLines`](crate::GridLines).
+pub(crate) struct GridLine {
+    pub(crate) vertical: bool,
+    pub(crate) index: u32,
+}
+
+/// The `index`th dash along the border on `side`, shown under [`WrapSeams`](crate::WrapSeams).
+pub(crate) struct SeamDash {
+    pub(crate) side: Direction,
+    pub(crate) index: u32,
+}
+
+pub(crate) struct Size {
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+impl Size {
+    pub fn square(x: f32) -> Self {
+        Self {
+            width: x,
+            height: x,
+        }
+    }
+}
+
+pub(crate) struct MainCamera;
+
+pub(crate) struct Tongue;
+
+pub(crate) struct Aura;
+
+pub(crate) struct SegmentArrow;
+
+/// Darkens its cell while it is outside the [`FogOfWar`](crate::FogOfWar) radius.
+pub(crate) struct FogTile;
+
+/// The highlight on the cell [`MoveHint`](crate::MoveHint) suggests.
+pub(crate) struct HintMarker;
+
+/// A cell of the [`WrapPeek`](crate::WrapPeek) inset, by its offset from the inset's center.
+pub(crate) struct PeekCell {
+    pub(crate) dx: i32,
+    pub(crate) dy: i32,
+}
+
+/// The sprite showing [`BackgroundImage`](crate::BackgroundImage), sized by
+/// `background_layout`.
+pub(crate) struct Backdrop(pub(crate) Handle<Texture>);
+
+/// Arrow drawn on a one-way cell; the cell itself is looked up in `OneWayCells`.
+pub(crate) struct OneWayMarker;
+
+/// One cell of the [`Portals`](crate::Portals) pair; a head moving in comes out on `link`.
+pub(crate) struct Portal {
+    pub(crate) link: Position,
+}
+
+/// A wall placed by [`GateCells`](crate::GateCells), closed until `opens_after` foods are eaten.
+pub(crate) struct Gate {
+    pub(crate) opens_after: u32,
+}
+
+/// Any part of player two's snake in [`TwoPlayer`](crate::TwoPlayer).
+pub(crate) struct Rival;
+
+/// The head of player two's snake, with the turn it takes on the next tick.
+pub(crate) struct RivalHead {
+    pub(crate) direction: Direction,
+    pub(crate) turn: Option<Direction>,
+}
+
+/// A wall placed by [`ObstacleCells`](crate::ObstacleCells), there for the whole game.
+pub(crate) struct Obstacle;
+
+/// A key from [`KeysAndDoors`](crate::KeysAndDoors), picked up by running over it.
+pub(crate) struct Key;
+
+/// A wall from [`KeysAndDoors`](crate::KeysAndDoors), closed until `requires` keys are held.
+pub(crate) struct Door {
+    pub(crate) requires: u32,
+}
+
+/// An opened gate or door shrinking away; it no longer blocks anything.
+pub(crate) struct GateOpening(pub(crate) Timer);
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Direction {
+    Left,
+    Up,
+    Right,
+    Down,
+}
+
+impl Direction {
+    pub(crate) const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+    ];
+
+    pub(crate) fn opposite(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Up => Self::Down,
+            Self::Right => Self::Left,
+            Self::Down => Self::Up,
+        }
+    }
+
+    /// The `(dx, dy)` of one cell's step this way.
+    pub(crate) fn delta(self) -> (i32, i32) {
+        match self {
+            Self::Left => (-1, 0),
+            Self::Up => (0, 1),
+            Self::Right => (1, 0),
+            Self::Down => (0, -1),
+        }
+    }
+
+    /// Rotation around the z axis that turns a right-facing sprite this way.
+    pub(crate) fn angle(self) -> f32 {
+        match self {
+            Self::Right => 0.0,
+            Self::Up => std::f32::consts::FRAC_PI_2,
+            Self::Left => std::f32::consts::PI,
+            Self::Down => -std::f32::consts::FRAC_PI_2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_go_one_cell_along_the_direction() {
+        let pos = Position { x: 3, y: 3 };
+        assert_eq!(pos.step(Direction::Left), Position { x: 2, y: 3 });
+        assert_eq!(pos.step(Direction::Up), Position { x: 3, y: 4 });
+        assert_eq!(pos.step(Direction::Right), Position { x: 4, y: 3 });
+        assert_eq!(pos.step(Direction::Down), Position { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn stepping_back_the_opposite_way_returns_to_the_start() {
+        let pos = Position { x: 0, y: 0 };
+        for &dir in &Direction::ALL {
+            assert_ne!(dir.opposite(), dir);
+            assert_eq!(dir.opposite().opposite(), dir);
+            assert_eq!(pos.step(dir).step(dir.opposite()), pos);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::components::Position;
+use crate::difficulty::{apply_difficulty, apply_preset, Difficulty, DifficultyPreset};
+use crate::resources::{
+    days_since_epoch, ArenaSize, FoodSeed, GateCell, GateCells, ObstacleCells, Portals,
+};
+
+/// Everything a daily challenge fixes for the day, the same for every player.
+///
+/// It all follows from the day number alone, so two players on the same UTC date get the
+/// same rules, the same gates and the same food.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyChallenge {
+    /// Whole days since the UNIX epoch, in UTC.
+    pub day: u64,
+    pub preset: DifficultyPreset,
+    pub gates: Vec<GateCell>,
+}
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        Self::for_day(days_since_epoch())
+    }
+
+    pub fn for_day(day: u64) -> Self {
+        // Kept apart from the food seed, which is the plain day number.
+        let mut rng = StdRng::seed_from_u64(day ^ 0xDA11_C4A1_1E46_E5ED);
+        let preset = match rng.gen_range(0, 4) {
+            0 => DifficultyPreset::Zen,
+            3 => DifficultyPreset::Insane,
+            _ => DifficultyPreset::Classic,
+        };
+        let arena = ArenaSize::default();
+        let mut gates = Vec::new();
+        for _ in 0..rng.gen_range(1, 3) {
+            let length = rng.gen_range(3, 6);
+            let opens_after = rng.gen_range(3, 10);
+            let horizontal = rng.gen::<bool>();
+            let (span_x, span_y) = if horizontal { (length, 1) } else { (1, length) };
+            let x = rng.gen_range(0, arena.width as i32 - span_x + 1);
+            let y = rng.gen_range(0, arena.height as i32 - span_y + 1);
+            for i in 0..length {
+                let position = if horizontal {
+                    Position { x: x + i, y }
+                } else {
+                    Position { x, y: y + i }
+                };
+                gates.push(GateCell {
+                    position,
+                    opens_after,
+                });
+            }
+        }
+        Self { day, preset, gates }
+    }
+
+    /// The food seed everyone plays the day with, the same one `--daily` always used.
+    pub fn seed(&self) -> FoodSeed {
+        FoodSeed(Some(self.day))
+    }
+
+    /// A line to paste elsewhere: the day, its rules and a bar with a square per ten points.
+    pub(crate) fn share_line(&self, score: u32, length: usize) -> String {
+        let filled = (score / 10).min(10) as usize;
+        let bar: String = "🟩".repeat(filled) + &"⬛".repeat(10 - filled);
+        format!(
+            "Snek daily #{} ({:?}) {} {} points, length {}",
+            self.day, self.preset, bar, score, length
+        )
+    }
+}
+
+/// The challenge being played, if any; a result line is printed whenever one of its runs ends.
+#[derive(Default)]
+pub(crate) struct ActiveDaily(pub(crate) Option<DailyChallenge>);
+
+/// Overwrites the rules, gates and food seed with those of `challenge`, on the default arena
+/// size so that its gates fit, without obstacles or portals and at the default [`Difficulty`].
+///
+/// Call it after adding [`SnekPlugin`](crate::SnekPlugin), like [`apply_preset`], and before
+/// the app runs.
+pub fn apply_daily(resources: &mut Resources, challenge: DailyChallenge) {
+    apply_difficulty(resources, Difficulty::default());
+    apply_preset(resources, challenge.preset);
+    resources.insert(challenge.seed());
+    resources.insert(ArenaSize::default());
+    resources.insert(ObstacleCells::default());
+    resources.insert(Portals::default());
+    resources.insert(GateCells(challenge.gates.clone()));
+    resources.insert(ActiveDaily(Some(challenge)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_daily, DailyChallenge};
+    use crate::resources::{ArenaSize, FoodSeed, GateCell, GateCells};
+    use crate::testing::quiet_app;
+
+    #[test]
+    fn the_same_day_always_gives_the_same_challenge() {
+        let day = 20_000;
+        assert_eq!(DailyChallenge::for_day(day), DailyChallenge::for_day(day));
+        assert_eq!(DailyChallenge::for_day(day).seed(), FoodSeed(Some(day)));
+
+        let applied = || {
+            let app = quiet_app(|resources| apply_daily(resources, DailyChallenge::for_day(day)));
+            let resources = &app.resources;
+            let seed = *resources.get::<FoodSeed>().unwrap();
+            let arena = *resources.get::<ArenaSize>().unwrap();
+            let gates: Vec<GateCell> = resources.get::<GateCells>().unwrap().0.clone();
+            (seed, arena, gates)
+        };
+        let first = applied();
+        assert!(!first.2.is_empty());
+        assert_eq!(applied(), first);
+
+        // Other days do play differently.
+        assert!((day + 1..day + 8).any(|other| {
+            DailyChallenge::for_day(other).gates != DailyChallenge::for_day(day).gates
+        }));
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::resources::{
+    ArenaShrink, BaseSpeed, FoodSpawnTimer, InvincibleConfig, PoisonConfig, SelfCollision,
+    SpeedBasis, SpeedRamp, WallMode,
+};
+
+/// How fast the snake starts out and how often food shows up, applied with
+/// [`apply_difficulty`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Difficulty {
+    /// A move every 350ms and food every 0.8s; the pace tops out at a move every 150ms.
+    Easy,
+    /// A move every 250ms and food every second, as with no difficulty picked.
+    #[default]
+    Medium,
+    /// A move every 150ms and food every 1.5s; the pace can climb to a move every 50ms.
+    Hard,
+}
+
+/// Sets the starting speed, the food spawn interval and the [`SpeedRamp`] floor for
+/// `difficulty`, leaving the rest of the ramp alone.
+///
+/// Call it after adding [`SnekPlugin`](crate::SnekPlugin) and before [`apply_preset`], whose
+/// ramp replaces the floor set here.
+pub fn apply_difficulty(resources: &mut Resources, difficulty: Difficulty) {
+    let (base, food, floor) = match difficulty {
+        Difficulty::Easy => (350, 800, 150),
+        Difficulty::Medium => (250, 1000, 80),
+        Difficulty::Hard => (150, 1500, 50),
+    };
+    resources.insert(BaseSpeed(Duration::from_millis(base)));
+    resources.insert(FoodSpawnTimer(Timer::new(
+        Duration::from_millis(food),
+        true,
+    )));
+    if let Some(mut ramp) = resources.get_mut::<SpeedRamp>() {
+        ramp.floor = Duration::from_millis(floor);
+    }
+}
+
+/// A curated mix of rules, applied with [`apply_preset`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DifficultyPreset {
+    /// Edges and the snake's own body are harmless, poison is gone and the snake moves half
+    /// again as slowly, a pace that never picks up.
+    Zen,
+    /// The game as it plays with no settings touched.
+    #[default]
+    Classic,
+    /// The snake speeds up with every point, poison shows up a lot more often and the arena
+    /// shrinks every 20 seconds.
+    Insane,
+}
+
+/// Overwrites every resource `preset` has an opinion on.
+///
+/// Call it after adding [`SnekPlugin`](crate::SnekPlugin), which inserts the defaults, and
+/// before the app runs.
+pub fn apply_preset(resources: &mut Resources, preset: DifficultyPreset) {
+    let classic_poison = PoisonConfig::default();
+    match preset {
+        DifficultyPreset::Zen => {
+            // Slower than whatever difficulty was picked.
+            let base = resources
+                .get::<BaseSpeed>()
+                .map_or(BaseSpeed::default().0, |base| base.0);
+            resources.insert(BaseSpeed(base * 3 / 2));
+            resources.insert(ArenaShrink::default());
+            resources.insert(WallMode::RandomTeleport);
+            resources.insert(SelfCollision::Cut);
+            resources.insert(SpeedBasis::default());
+            resources.insert(SpeedRamp {
+                step: Duration::from_millis(0),
+                ..SpeedRamp::default()
+            });
+            resources.insert(PoisonConfig {
+                spawn_chance: 0.,
+                ..classic_poison
+            });
+            // Invincibility would change nothing.
+            resources.insert(InvincibleConfig {
+                spawn_chance: 0.,
+                ..Default::default()
+            });
+        }
+        DifficultyPreset::Classic => {
+            resources.insert(WallMode::default());
+            resources.insert(SelfCollision::default());
+            resources.insert(SpeedBasis::default());
+            resources.insert(SpeedRamp::default());
+            resources.insert(classic_poison);
+            resources.insert(InvincibleConfig::default());
+            resources.insert(ArenaShrink::default());
+        }
+        DifficultyPreset::Insane => {
+            resources.insert(WallMode::Solid);
+            resources.insert(SelfCollision::Die);
+            resources.insert(SpeedBasis::Score);
+            resources.insert(SpeedRamp {
+                step: Duration::from_millis(10),
+                floor: Duration::from_millis(60),
+            });
+            resources.insert(PoisonConfig {
+                spawn_chance: 0.3,
+                ..classic_poison
+            });
+            resources.insert(InvincibleConfig::default());
+            resources.insert(ArenaShrink(Some(Duration::from_secs(20))));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{apply_preset, DifficultyPreset};
+    use crate::components::{FoodKind, Position};
+    use crate::events::GameOverEvent;
+    use crate::resources::{ArenaSize, BaseSpeed, RunTime, SelfCollision, WallMode};
+    use crate::testing::{food, place_food, quiet_app, run_tick_counting};
+    use crate::{MIN_ARENA_HEIGHT, MIN_ARENA_WIDTH};
+
+    #[test]
+    fn zen_turns_off_wall_and_body_deaths() {
+        let mut app = quiet_app(|resources| apply_preset(resources, DifficultyPreset::Zen));
+        assert_ne!(*app.resources.get::<WallMode>().unwrap(), WallMode::Solid);
+        assert_ne!(
+            *app.resources.get::<SelfCollision>().unwrap(),
+            SelfCollision::Die
+        );
+        // Straight up, into the edge that ends a classic game on the 7th move.
+        let game_overs: usize = (0..10)
+            .map(|_| run_tick_counting::<GameOverEvent>(&mut app))
+            .sum();
+        assert_eq!(game_overs, 0);
+    }
+
+    #[test]
+    fn zen_slows_the_snake_down() {
+        let app = quiet_app(|resources| apply_preset(resources, DifficultyPreset::Zen));
+        assert!(app.resources.get::<BaseSpeed>().unwrap().0 > BaseSpeed::default().0);
+    }
+
+    #[test]
+    fn insane_shrinks_the_arena_until_the_restart() {
+        let mut app = quiet_app(|resources| apply_preset(resources, DifficultyPreset::Insane));
+        place_food(&mut app, FoodKind::Normal, Position { x: 9, y: 9 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 5, y: 5 });
+        app.resources.get_mut::<RunTime>().unwrap().0 = Duration::from_secs(41);
+        app.update();
+        assert_eq!(
+            *app.resources.get::<ArenaSize>().unwrap(),
+            ArenaSize {
+                width: 8,
+                height: 8
+            }
+        );
+        assert_eq!(
+            food(&app),
+            vec![(Position { x: 5, y: 5 }, FoodKind::Normal)]
+        );
+
+        app.resources.get_mut::<RunTime>().unwrap().0 = Duration::from_secs(1000);
+        app.update();
+        assert_eq!(
+            *app.resources.get::<ArenaSize>().unwrap(),
+            ArenaSize {
+                width: MIN_ARENA_WIDTH,
+                height: MIN_ARENA_HEIGHT
+            }
+        );
+
+        app.resources.get_mut::<RunTime>().unwrap().0 = Duration::default();
+        app.update();
+        assert_eq!(
+            *app.resources.get::<ArenaSize>().unwrap(),
+            ArenaSize::default()
+        );
+    }
+}
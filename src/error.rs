@@ -0,0 +1,24 @@
+use std::fmt;
+
+/// Errors raised while setting up the game or restoring saved state.
+#[derive(Debug)]
+pub enum SnekError {
+    /// An asset could not be loaded.
+    AssetLoad(String),
+    /// There is no primary window to lay the arena out in.
+    WindowMissing,
+    /// A saved or configuration file could not be read or parsed.
+    ConfigParse(String),
+}
+
+impl fmt::Display for SnekError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AssetLoad(reason) => write!(f, "failed to load asset: {}", reason),
+            Self::WindowMissing => write!(f, "no primary window"),
+            Self::ConfigParse(reason) => write!(f, "failed to parse config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SnekError {}
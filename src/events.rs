@@ -0,0 +1,26 @@
+/// A meal that grows the snake by `segments`.
+pub(crate) struct GrowthEvent {
+    pub(crate) segments: u32,
+}
+
+/// One of the snakes; there is only ever a second one in [`TwoPlayer`](crate::TwoPlayer).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Player {
+    One,
+    Two,
+}
+
+/// A lethal collision of `0`'s snake.
+pub(crate) struct GameOverEvent(pub(crate) Player);
+
+/// Points scored by eating a piece of food.
+pub(crate) struct ScoreGained(pub(crate) u32);
+
+/// A lethal collision that cost a life but did not end the game.
+pub(crate) struct LifeLost;
+
+/// A lethal collision undone by [`RewindOnDeath`](crate::RewindOnDeath) instead.
+pub(crate) struct Rewound;
+
+/// Send this event to tear down the board and start over from a fresh game.
+pub struct ResetGame;
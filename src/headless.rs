@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::text::FontLoader;
+use bevy::window::WindowPlugin;
+
+use crate::resources::{
+    BestRun, FixedStep, GameState, SaveFiles, TutorialShown, UnlockedThemes, Wallet,
+};
+use crate::SnekPlugin;
+
+/// How long each headless frame counts as, see [`FixedStep`].
+const HEADLESS_FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Builds the game with [`MinimalPlugins`], without a window, a GPU or an audio device, for
+/// tests and tools to drive frame by frame.
+///
+/// Nothing is drawn or heard, but every system still runs. Sprites and sounds go to asset
+/// stores nothing reads, and layout finds no window to lay anything out in. The title menu
+/// and the tutorial are skipped, so the first snake is there from the start.
+///
+/// Nothing is read from or saved to the `snek_*` files either, see [`SaveFiles`], and every
+/// frame counts as a sixtieth of a second whatever the clock says, see [`FixedStep`]. The
+/// same inputs on the same frames so always play out the same.
+///
+/// Change resources through `resources_mut()`, take the `app`, call `initialize()` on it
+/// once to run the startup systems and then `update()` once per frame. Steer by pressing
+/// keys on the `Input<KeyCode>` resource between updates. A frame only moves the snake once
+/// a tick's worth of frames has passed.
+pub fn build_headless_app() -> AppBuilder {
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(bevy::transform::TransformPlugin)
+        .add_plugin(bevy::diagnostic::DiagnosticsPlugin)
+        .add_plugin(bevy::input::InputPlugin)
+        .add_plugin(WindowPlugin {
+            add_primary_window: false,
+            exit_on_close: false,
+        })
+        .add_plugin(bevy::asset::AssetPlugin)
+        .add_asset::<Texture>()
+        .add_asset::<ColorMaterial>()
+        .add_asset::<Font>()
+        .init_asset_loader::<FontLoader>()
+        .add_asset::<AudioSource>()
+        .init_resource::<Audio>()
+        .add_plugin(SnekPlugin)
+        .add_resource(GameState::Running)
+        .add_resource(FixedStep(Some(HEADLESS_FRAME)))
+        .add_resource(SaveFiles(false))
+        .add_resource(BestRun::default())
+        .add_resource(Wallet::default())
+        .add_resource(UnlockedThemes::default())
+        .add_resource(TutorialShown(true));
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Position, SnekHead};
+    use crate::testing::{run_tick, start};
+
+    fn moves(ticks: usize) -> Vec<(usize, Position)> {
+        let mut app = start(build_headless_app());
+        (0..ticks)
+            .map(|_| {
+                let frames = run_tick(&mut app);
+                let head = *app
+                    .world
+                    .query::<(&SnekHead, &Position)>()
+                    .map(|(_, pos)| pos)
+                    .next()
+                    .unwrap();
+                (frames, head)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_run_moves_on_the_same_frames() {
+        let first = moves(4);
+        assert_eq!(first, moves(4));
+        assert_eq!(first[1].1.y, first[0].1.y + 1);
+    }
+}
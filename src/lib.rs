@@ -0,0 +1,450 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod components;
+mod daily;
+mod difficulty;
+mod error;
+mod events;
+mod headless;
+mod resources;
+mod systems;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use components::{Direction, FoodKind, MovePattern, Position};
+pub use daily::{apply_daily, DailyChallenge};
+pub use difficulty::{apply_difficulty, apply_preset, Difficulty, DifficultyPreset};
+pub use error::SnekError;
+pub use events::ResetGame;
+pub use headless::build_headless_app;
+pub use resources::{
+    AgeColor, AgeScale, ArenaShrink, ArenaSize, BackgroundImage, BaseSpeed, BodySpacing,
+    BonusFoodConfig, BoostConfig, BoostMode, CameraFollow, CameraMode, CellPadding, CellShape,
+    Checkerboard, CheckerboardShades, CoinDropConfig, CoinDrops, ConfirmQuit, DeathReplay,
+    DeathReplayConfig, DoorCell, DoubleScoreConfig, EdgeWarning, EnabledFoodKinds, EnemySpawns,
+    ExplosiveConfig, FixedStep, FogOfWar, FoodChain, FoodChainConfig, FoodDecay, FoodHoming,
+    FoodSeed, FoodSpawn, FoodValueDecay, FrameSettings, GameSnapshot, GameStats, GateCell,
+    GateCells, GridLines, GrowthMode, HeadStyle, InputAssist, InvincibleConfig, KeyBindings,
+    KeysAndDoors, LaunchOptions, Lives, LivesLeft, LoadedReplay, MagnetConfig, MaxFood,
+    MegaFoodConfig, MoveHint, MusicBpm, MusicTrack, ObstacleCells, OneWay, OneWayCells, PaintMode,
+    PaintPalette, Paused, PhaseConfig, PoisonConfig, Portals, Projection, RadarConfig, ReplayLog,
+    ReverseConfig, RewindConfig, RewindOnDeath, RhythmMode, RotateArena, SaveFiles,
+    ScatterInterval, ScorePopupConfig, ScorePopups, Scoring, SegmentArrows, SelfCollision,
+    ShootTail, SlowConfig, SoundEffects, SpeedBasis, SpeedColor, SpeedRamp, SplitConfig,
+    StarvationInterval, StatsFile, StickDeadzone, Theme, TickCallback, TickHook, TiePolicy,
+    TimeAttack, TongueConfig, TronMode, TurnCooldown, TutorialShown, TwoPlayer, WallBreakCharges,
+    WallBreakConfig, WallMode, Wobble, WobbleSpring, WrapPeek, WrapSeamStyle, WrapSeams,
+    WrapUnlockLength,
+};
+
+use bevy::prelude::*;
+use bevy::render::pass::ClearColor;
+
+use daily::ActiveDaily;
+use events::{GameOverEvent, GrowthEvent, LifeLost, Rewound, ScoreGained};
+use resources::{
+    AudioAssets, BestRun, Boost, Canvas, DeathPlayback, DoubleScore, FoodRng, FoodSpawnTimer,
+    FoodsEaten, GameOverScreen, GameState, GamepadInput, Invincible, Keys, LastTailPosition, Level,
+    MagnetEffect, PendingGrowth, PhaseSelf, PingReveal, PlayerPause, QuitPrompt, RadarPings,
+    RecentTicks, ReplayRecorder, ReversedControls, RewindHistory, RivalSegments, RunRecorder,
+    RunTime, Score, SettingsDirty, SlowEffect, SnekMoveTimer, SnekPath, SnekSegments, Starvation,
+    TimeUp, Tutorial, UnlockedThemes, Wallet, Winner,
+};
+use systems::*;
+
+/// Default size of the arena, see [`ArenaSize`].
+pub(crate) const ARENA_WIDTH: u32 = 10;
+pub(crate) const ARENA_HEIGHT: u32 = 10;
+
+// The starting snake is spawned at (3, 3) heading up and needs at least a cell ahead of it.
+pub(crate) const MIN_ARENA_WIDTH: u32 = 4;
+pub(crate) const MIN_ARENA_HEIGHT: u32 = 5;
+
+const _: () = assert!(
+    ARENA_WIDTH >= MIN_ARENA_WIDTH && ARENA_HEIGHT >= MIN_ARENA_HEIGHT,
+    "the arena is too small to fit the starting snake"
+);
+
+pub(crate) const BORDER_THICKNESS: f32 = 4.0;
+pub(crate) const GRID_LINE_THICKNESS: f32 = 1.0;
+
+/// Longest frame, in seconds, that gameplay timers advance by; see `gameplay_delta`.
+pub(crate) const MAX_FRAME_DELTA: f32 = 0.1;
+
+/// How far toward grey [`AgeColor`] fades the snake by the end of its [`AgeScale`].
+pub(crate) const AGE_COLOR_FADE: f32 = 0.7;
+
+/// Move interval, in seconds, at which [`SpeedColor`] reaches its full tint.
+pub(crate) const SPEED_COLOR_FASTEST: f32 = 0.08;
+
+pub(crate) const WALLET_PATH: &str = "snek_wallet.txt";
+
+/// Themes bought with the coins in [`WALLET_PATH`].
+pub(crate) const UNLOCKS_PATH: &str = "snek_unlocks.txt";
+
+pub(crate) const HUD_FONT_PATH: &str = "fonts/DejaVuSansMono-Bold.ttf";
+
+/// Window title outside of a run; during one, the score and length are added after it.
+pub const WINDOW_TITLE: &str = "Snek!";
+
+/// Fraction of a tick, either side of it, in which turns count under [`RhythmMode`].
+pub(crate) const BEAT_WINDOW: f32 = 0.25;
+
+/// Turns the snake keeps queued up between two ticks.
+pub(crate) const TURN_QUEUE_LEN: usize = 3;
+
+/// Sparks thrown by exploding food, how long they last and how many cells a second they fly.
+pub(crate) const SPARK_COUNT: u32 = 8;
+pub(crate) const SPARK_SECS: f32 = 0.4;
+pub(crate) const SPARK_SPEED: f32 = 4.0;
+
+/// Size, in pixels, of a HUD meter bar when full.
+pub(crate) const HUD_BAR_WIDTH: f32 = 200.0;
+pub(crate) const HUD_BAR_HEIGHT: f32 = 10.0;
+
+/// Cells the [`WrapPeek`] inset shows either side of its center, and their size in pixels.
+pub(crate) const PEEK_RADIUS: i32 = 2;
+pub(crate) const PEEK_CELL_SIZE: f32 = 12.0;
+
+pub(crate) const EAT_STAGE: &str = "eat";
+pub(crate) const GROW_STAGE: &str = "grow";
+
+pub(crate) const BEST_RUN_PATH: &str = "snek_best_run.txt";
+
+pub(crate) const REPLAY_PATH: &str = "snek_last_replay.txt";
+
+pub(crate) const SETTINGS_PATH: &str = "snek_settings.txt";
+
+/// Read at startup for the [`KeyBindings`] of player one.
+pub(crate) const KEYS_PATH: &str = "snek_keys.toml";
+
+pub(crate) const TUTORIAL_PATH: &str = "snek_tutorial_shown.txt";
+
+pub struct SnekPlugin;
+
+impl Plugin for SnekPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(SnekMoveTimer::default())
+            .add_resource(FoodSpawnTimer::default())
+            .add_resource(SnekSegments::default())
+            .add_resource(BodySpacing::default())
+            .add_resource(GrowthMode::default())
+            .add_resource(PendingGrowth::default())
+            .add_resource(SnekPath::default())
+            .add_resource(LastTailPosition::default())
+            .add_resource(Score::default())
+            .add_resource(Lives::default())
+            .add_resource(LivesLeft::default())
+            .add_resource(RunTime::default())
+            .add_resource(StarvationInterval::default())
+            .add_resource(Starvation::default())
+            .add_resource(TimeAttack::default())
+            .add_resource(TimeUp::default())
+            .add_resource(Paused::default())
+            .add_resource(ConfirmQuit::default())
+            .add_resource(QuitPrompt::default())
+            .add_resource(PlayerPause::default())
+            .add_resource(GameOverScreen::default())
+            .add_resource(GameState::default())
+            .add_resource(TurnCooldown::default())
+            .add_resource(ArenaSize::default())
+            .add_resource(ArenaShrink::default())
+            .add_resource(TiePolicy::default())
+            .add_resource(KeyBindings::default())
+            .add_resource(InputAssist::default())
+            .add_resource(StickDeadzone::default())
+            .add_resource(MaxFood::default())
+            .add_resource(FixedStep::default())
+            .add_resource(SaveFiles::default())
+            .add_resource(GameStats::default())
+            .add_resource(StatsFile::default())
+            .add_resource(GamepadInput::default())
+            .add_resource(WallMode::default())
+            .add_resource(SelfCollision::default())
+            .add_resource(TronMode::default())
+            .add_resource(TwoPlayer::default())
+            .add_resource(ReplayRecorder::default())
+            .add_resource(LoadedReplay::default())
+            .add_resource(RivalSegments::default())
+            .add_resource(Winner::default())
+            .add_resource(ShootTail::default())
+            .add_resource(PaintMode::default())
+            .add_resource(PaintPalette::default())
+            .add_resource(Canvas::default())
+            .add_resource(WrapUnlockLength::default())
+            .add_resource(OneWayCells::default())
+            .add_resource(Portals::default())
+            .add_resource(GateCells::default())
+            .add_resource(ObstacleCells::default())
+            .add_resource(FoodsEaten::default())
+            .add_resource(KeysAndDoors::default())
+            .add_resource(Keys::default())
+            .add_resource(Scoring::default())
+            .add_resource(FoodDecay::default())
+            .add_resource(FoodChain::default())
+            .add_resource(DeathReplay::default())
+            .add_resource(RewindOnDeath::default())
+            .add_resource(RewindHistory::default())
+            .add_resource(RecentTicks::default())
+            .add_resource(DeathPlayback::default())
+            .add_resource(Level::default())
+            .add_resource(CellPadding::default())
+            .add_resource(HeadStyle::default())
+            .add_resource(TongueConfig::default())
+            .add_resource(SegmentArrows::default())
+            .add_resource(CellShape::default())
+            .add_resource(Theme::default())
+            .add_resource(ClearColor(Theme::default().colors().background))
+            .add_resource(Projection::default())
+            .add_resource(CameraMode::default())
+            .add_resource(CameraFollow::default())
+            .add_resource(RotateArena::default())
+            .add_resource(EdgeWarning::default())
+            .add_resource(Wobble::default())
+            .add_resource(WobbleSpring::default())
+            .add_resource(WrapPeek::default())
+            .add_resource(WrapSeams::default())
+            .add_resource(MoveHint::default())
+            .add_resource(FogOfWar::default())
+            .add_resource(RadarConfig::default())
+            .add_resource(RadarPings::default())
+            .add_resource(PingReveal::default())
+            .add_resource(Checkerboard::default())
+            .add_resource(CheckerboardShades::default())
+            .add_resource(GridLines::default())
+            .add_resource(ScatterInterval::default())
+            .add_resource(CoinDrops::default())
+            .add_resource(FoodHoming::default())
+            .add_resource(WallBreakConfig::default())
+            .add_resource(WallBreakCharges::default())
+            .add_resource(PoisonConfig::default())
+            .add_resource(SplitConfig::default())
+            .add_resource(ExplosiveConfig::default())
+            .add_resource(BonusFoodConfig::default())
+            .add_resource(MegaFoodConfig::default())
+            .add_resource(SpeedColor::default())
+            .add_resource(AgeColor::default())
+            .add_resource(AgeScale::default())
+            .add_resource(SpeedBasis::default())
+            .add_resource(SpeedRamp::default())
+            .add_resource(BaseSpeed::default())
+            .add_resource(FoodSpawn::default())
+            .add_resource(FoodSeed::default())
+            .add_resource(ActiveDaily::default())
+            .add_resource(FoodRng::default())
+            .add_resource(EnabledFoodKinds::default())
+            .add_resource(MagnetConfig::default())
+            .add_resource(MagnetEffect::default())
+            .add_resource(ReverseConfig::default())
+            .add_resource(ReversedControls::default())
+            .add_resource(ScorePopups::default())
+            .add_resource(BoostMode::default())
+            .add_resource(Boost::default())
+            .add_resource(DoubleScoreConfig::default())
+            .add_resource(DoubleScore::default())
+            .add_resource(PhaseConfig::default())
+            .add_resource(PhaseSelf::default())
+            .add_resource(InvincibleConfig::default())
+            .add_resource(Invincible::default())
+            .add_resource(SlowConfig::default())
+            .add_resource(SlowEffect::default())
+            .add_resource(EnemySpawns::default())
+            .add_resource(RunRecorder::default())
+            .add_resource(BestRun::load(BEST_RUN_PATH))
+            .add_resource(Wallet::load(WALLET_PATH))
+            .add_resource(UnlockedThemes::load(UNLOCKS_PATH))
+            .add_resource(TutorialShown::load(TUTORIAL_PATH))
+            .add_resource(Tutorial::default())
+            .add_resource(TickHook::default())
+            .add_resource(FrameSettings::default())
+            .add_resource(SettingsDirty::default())
+            .add_resource(BackgroundImage::default())
+            .add_resource(MusicTrack::default())
+            .add_resource(SoundEffects::default())
+            .add_resource(AudioAssets::default())
+            .add_resource(RhythmMode::default())
+            .add_resource(MusicBpm::default())
+            // Everything else at startup lays itself out on the arena, so check it first.
+            .add_startup_system(validate_arena.system())
+            .add_startup_system(setup.system())
+            .add_startup_system(spawn_background.system())
+            .add_startup_system(load_sounds.system())
+            .add_startup_system(spawn_checkerboard.system())
+            .add_startup_system(spawn_grid_lines.system())
+            .add_startup_system(spawn_tutorial.system())
+            .add_startup_system(spawn_menu_text.system())
+            .add_startup_system(spawn_quit_prompt.system())
+            .add_startup_system(spawn_pause_text.system())
+            .add_startup_system(spawn_game_over_text.system())
+            .add_startup_system(validate_obstacles.system())
+            .add_startup_system(validate_gates.system())
+            .add_startup_system(validate_keys_and_doors.system())
+            .add_startup_stage("game_setup")
+            .add_startup_system_to_stage("game_setup", game_setup.system())
+            .add_startup_system_to_stage("game_setup", spawn_borders.system())
+            .add_startup_system_to_stage("game_setup", spawn_one_way_cells.system())
+            .add_startup_system_to_stage("game_setup", spawn_portals.system())
+            .add_startup_system_to_stage("game_setup", spawn_fog.system())
+            .add_startup_system_to_stage("game_setup", spawn_wrap_peek.system())
+            .add_startup_system_to_stage("game_setup", spawn_wrap_seams.system())
+            .add_startup_system_to_stage("game_setup", spawn_move_hint.system())
+            // Each tick the head moves first, then eats whatever it landed on, then grows.
+            // The stages keep that order explicit and flush despawns/spawns in between.
+            .add_stage_after(stage::UPDATE, EAT_STAGE)
+            .add_stage_after(EAT_STAGE, GROW_STAGE)
+            .add_system(boost_meter.system())
+            .add_system(snek_timer.system())
+            .add_system(run_timer.system())
+            .add_system(shrink_arena.system())
+            .add_system(magnet_timer.system())
+            .add_system(reversed_controls_timer.system())
+            .add_system(invincible_timer.system())
+            .add_system(slow_timer.system())
+            .add_system(double_score_timer.system())
+            .add_system(phase_timer.system())
+            .add_system(coin_lifetime.system())
+            .add_system(bonus_food_lifetime.system())
+            .add_system(theme.system())
+            .add_system(speed_color.system())
+            .add_system(gamepad_input.system())
+            .add_system(snek_movement.system())
+            .add_system(rival_movement.system())
+            .add_system(shoot_tail.system())
+            .add_system(projectile_flight.system())
+            .add_system(tron_trail.system())
+            .add_system(paint_trail.system())
+            .add_system(enemy_spawner.system())
+            .add_system(enemy_movement.system())
+            .add_system(tick_scoring.system())
+            .add_system(record_run.system())
+            .add_system(ghost_movement.system())
+            .add_system(food_spawner.system())
+            .add_system(position_translation.system())
+            // Must come after `position_translation`, which it pulls the segments back from.
+            .add_system(wobble.system())
+            // Also after `position_translation`, whose cell it throws the sparks out from.
+            .add_system(sparks.system())
+            .add_system(head_orientation.system())
+            .add_system(invincible_flash.system())
+            .add_system(recenter_on_resize.system())
+            .add_system(camera_follow.system())
+            .add_system(rotate_arena.system())
+            .add_system(background_layout.system())
+            .add_system(border_layout.system())
+            .add_system(grid_layout.system())
+            .add_system(edge_warning.system())
+            .add_system(wrap_peek.system())
+            .add_system(wrap_seams.system())
+            .add_system(move_hint.system())
+            .add_system(radar_ping.system())
+            .add_system(fog_of_war.system())
+            .add_system(size_scaling.system())
+            .add_system(tongue.system())
+            .add_system(double_score_aura.system())
+            .add_system(segment_arrows.system())
+            .add_system(cell_shape.system())
+            .add_system(texture_smoothing.system())
+            .add_system(save_settings.system())
+            .add_system(window_title.system())
+            .add_system(wall_break_text.system())
+            .add_system(run_time_text.system())
+            .add_system(lives_text.system())
+            .add_system(score_text.system())
+            .add_system(level_text.system())
+            .add_system(wrap_text.system())
+            .add_system(boost_bar.system())
+            .add_system(starvation_bar.system())
+            .add_system(score_popups.system())
+            .add_system(beat_marker.system())
+            .add_system(music.system())
+            .add_system_to_stage(GROW_STAGE, sound_effects.system())
+            .add_system(quit.system())
+            .add_system(pause.system())
+            .add_system_to_stage(EAT_STAGE, magnet_pull.system())
+            .add_system_to_stage(EAT_STAGE, food_homing.system())
+            .add_system_to_stage(EAT_STAGE, snek_eating.system())
+            .add_system_to_stage(EAT_STAGE, rival_eating.system())
+            .add_system_to_stage(EAT_STAGE, coin_drop.system())
+            .add_system_to_stage(EAT_STAGE, keys_and_doors.system())
+            .add_system_to_stage(GROW_STAGE, snek_growth.system())
+            .add_system_to_stage(GROW_STAGE, starvation.system())
+            .add_system_to_stage(GROW_STAGE, speed_control.system())
+            .add_system_to_stage(GROW_STAGE, speed_ramp.system())
+            .add_system_to_stage(GROW_STAGE, award_coins.system())
+            .add_system_to_stage(GROW_STAGE, food_scatter.system())
+            .add_system_to_stage(GROW_STAGE, save_best_run.system())
+            .add_system_to_stage(GROW_STAGE, save_replay.system())
+            .add_system_to_stage(GROW_STAGE, level_objective.system())
+            .add_system_to_stage(GROW_STAGE, gates.system())
+            .add_system_to_stage(GROW_STAGE, obstacles.system())
+            .add_system_to_stage(GROW_STAGE, time_attack.system())
+            .add_system_to_stage(GROW_STAGE, record_recent_ticks.system())
+            .add_system_to_stage(GROW_STAGE, game_over.system())
+            .add_system_to_stage(GROW_STAGE, record_rewind_points.system())
+            .add_system_to_stage(GROW_STAGE, rewind_board.system())
+            .add_system_to_stage(GROW_STAGE, death_replay.system())
+            .add_system_to_stage(GROW_STAGE, game_stats.system())
+            .add_system_to_stage(GROW_STAGE, respawn_snake.system())
+            .add_system_to_stage(GROW_STAGE, restart_hotkey.system())
+            .add_system_to_stage(GROW_STAGE, game_over_screen.system())
+            .add_system_to_stage(GROW_STAGE, tutorial.system())
+            .add_system_to_stage(GROW_STAGE, menu.system())
+            .add_system_to_stage(GROW_STAGE, shop.system())
+            // Must come before `reset_game`, which clears the score it reports.
+            .add_system_to_stage(GROW_STAGE, daily_result.system())
+            .add_system_to_stage(GROW_STAGE, reset_game.system())
+            .add_system_to_stage(GROW_STAGE, rival_spawn.system())
+            // After growth and resets, so it never lags a frame behind either.
+            .add_system_to_stage(GROW_STAGE, length_display.system())
+            .add_system_to_stage(stage::PRE_UPDATE, fixed_step.system())
+            .add_system_to_stage(stage::POST_UPDATE, tick_hook.system())
+            .add_system_to_stage(stage::LAST, frame_limiter.system())
+            .add_event::<GrowthEvent>()
+            .add_event::<GameOverEvent>()
+            .add_event::<ScoreGained>()
+            .add_event::<LifeLost>()
+            .add_event::<Rewound>()
+            .add_event::<ResetGame>();
+        #[cfg(feature = "dev")]
+        app.add_resource(TickAdjust::default())
+            .add_resource(InputLatency::default())
+            .add_resource(HeatmapDebug::default())
+            .add_resource(DebugOverlay::default())
+            .add_plugin(bevy::diagnostic::FrameTimeDiagnosticsPlugin)
+            .add_startup_system(spawn_tick_rate_text.system())
+            .add_startup_system(spawn_heatmap.system())
+            .add_system_to_stage(EAT_STAGE, input_latency.system())
+            .add_system_to_stage(GROW_STAGE, tick_rate_keys.system())
+            .add_system(tick_rate_text.system())
+            .add_system(latency_text.system())
+            .add_system(debug_overlay.system())
+            .add_system(heatmap.system());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::{FoodKind, Position};
+    use crate::events::GrowthEvent;
+    use crate::resources::Score;
+    use crate::testing::{body, food, head, place_food, quiet_app, run_tick, run_tick_counting};
+
+    /// Moving, eating and growing all happen on the tick the head reaches the food.
+    #[test]
+    fn food_ahead_is_eaten_on_the_tick_the_head_gets_there() {
+        let mut app = quiet_app(|_| ());
+        let ahead = Position { x: 3, y: 4 };
+        place_food(&mut app, FoodKind::Normal, ahead);
+        let score = app.resources.get::<Score>().unwrap().0;
+
+        assert_eq!(run_tick_counting::<GrowthEvent>(&mut app), 1);
+        assert_eq!(head(&app).0, ahead);
+        assert!(food(&app).is_empty());
+        assert_eq!(app.resources.get::<Score>().unwrap().0, score + 1);
+
+        // The new segment shows up behind the tail on the same tick and stays put.
+        assert_eq!(body(&app).len(), 2);
+        run_tick(&mut app);
+        assert_eq!(body(&app), vec![ahead, Position { x: 3, y: 3 }]);
+    }
+}
@@ -0,0 +1,2665 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::components::{Direction, FoodKind, MovePattern, Position};
+use crate::daily::DailyChallenge;
+use crate::difficulty::{Difficulty, DifficultyPreset};
+use crate::error::SnekError;
+use crate::events::Player;
+use crate::systems::{rival_start, START_POSITION};
+use crate::{
+    ARENA_HEIGHT, ARENA_WIDTH, KEYS_PATH, MIN_ARENA_HEIGHT, MIN_ARENA_WIDTH, SETTINGS_PATH,
+    UNLOCKS_PATH,
+};
+
+/// Cells across and up the arena, picked at launch with `--width` and `--height`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ArenaSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ArenaSize {
+    fn default() -> Self {
+        Self {
+            width: ARENA_WIDTH,
+            height: ARENA_HEIGHT,
+        }
+    }
+}
+
+impl ArenaSize {
+    pub(crate) fn contains(&self, pos: Position) -> bool {
+        (0..self.width as i32).contains(&pos.x) && (0..self.height as i32).contains(&pos.y)
+    }
+
+    /// The cell `pos` lands on after coming back in through the opposite edge.
+    pub(crate) fn wrap(&self, pos: Position) -> Position {
+        Position {
+            x: pos.x.rem_euclid(self.width as i32),
+            y: pos.y.rem_euclid(self.height as i32),
+        }
+    }
+
+    /// Every cell of the arena, column by column.
+    pub(crate) fn cells(self) -> impl Iterator<Item = Position> {
+        (0..self.width as i32)
+            .flat_map(move |x| (0..self.height as i32).map(move |y| Position { x, y }))
+    }
+
+    pub(crate) fn area(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// The arena with `steps` columns taken off the right and rows off the top, never
+    /// smaller than [`MIN_ARENA_WIDTH`] by [`MIN_ARENA_HEIGHT`].
+    pub(crate) fn shrunk(self, steps: u32) -> Self {
+        Self {
+            width: self.width.saturating_sub(steps).max(MIN_ARENA_WIDTH),
+            height: self.height.saturating_sub(steps).max(MIN_ARENA_HEIGHT),
+        }
+    }
+}
+
+/// How often the arena closes in by a column and a row during a run, if at all; a restart
+/// brings it back to full size. Part of [`DifficultyPreset::Insane`](crate::DifficultyPreset).
+#[derive(Default)]
+pub struct ArenaShrink(pub Option<Duration>);
+
+/// What happens when the head runs off the edge of the arena.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum WallMode {
+    /// The snake dies.
+    #[default]
+    Solid,
+    /// The head reappears on a random free cell, still heading the same way.
+    RandomTeleport,
+    /// The head bounces off the edge and heads back the way it came. If part of the body
+    /// moves into the cell it bounces into, the snake dies instead.
+    Reflect,
+    /// The head comes back in through the opposite edge.
+    Wrap,
+}
+
+/// When set, the head wraps around the arena edges instead of dying there once the snake,
+/// head included, is at least this long.
+#[derive(Default)]
+pub struct WrapUnlockLength(pub Option<u32>);
+
+impl WrapUnlockLength {
+    pub(crate) fn unlocked(&self, segments: &SnekSegments) -> bool {
+        self.0
+            .is_some_and(|length| segments.0.len() as u32 + 1 >= length)
+    }
+}
+
+/// Whether to show, while the head closes in on an edge it will wrap through, a small inset
+/// of the cells waiting on the other side.
+#[derive(Default)]
+pub struct WrapPeek(pub bool);
+
+/// Tron-style trail: every cell the tail leaves becomes a wall, so the snake only ever grows.
+///
+/// The trail lasts until a life is lost or the game is reset.
+#[derive(Default)]
+pub struct TronMode(pub bool);
+
+/// Local two-player mode, picked with `--two-player on`: a second snake steered with these
+/// keys competes for the same food.
+///
+/// Whoever runs into a wall, themselves or the other snake loses the round, and running into
+/// each other head on is a draw. Give player one other keys than these, like
+/// [`KeyBindings::arrows`] next to [`KeyBindings::wasd`]. Player two's snake keeps to the
+/// basics: every kind of food just makes it one cell longer, and only player one scores.
+#[derive(Default)]
+pub struct TwoPlayer(pub Option<KeyBindings>);
+
+/// Player two's snake, head first, and where its tail last moved out of.
+#[derive(Default)]
+pub(crate) struct RivalSegments {
+    pub(crate) entities: Vec<Entity>,
+    pub(crate) last_tail: Option<Position>,
+}
+
+/// Who won the last [`TwoPlayer`] round; `None` for a draw.
+#[derive(Default)]
+pub(crate) struct Winner(pub(crate) Option<Player>);
+
+/// Sandbox where every cell the head visits stays colored in, cycling through [`PaintPalette`].
+///
+/// The snake can't die, goes through the edges and no food spawns; `C` clears the canvas.
+#[derive(Default)]
+pub struct PaintMode(pub bool);
+
+/// The colors [`PaintMode`] paints with, one after the other.
+pub struct PaintPalette(pub Vec<Color>);
+
+impl Default for PaintPalette {
+    fn default() -> Self {
+        Self(vec![
+            Color::rgb(0.8, 0.2, 0.2),
+            Color::rgb(0.85, 0.55, 0.15),
+            Color::rgb(0.85, 0.8, 0.2),
+            Color::rgb(0.25, 0.7, 0.3),
+            Color::rgb(0.2, 0.5, 0.85),
+            Color::rgb(0.55, 0.3, 0.8),
+        ])
+    }
+}
+
+/// Cells painted so far in [`PaintMode`].
+#[derive(Default)]
+pub(crate) struct Canvas(pub(crate) HashSet<Position>);
+
+/// What happens when the head runs into the snake's own body.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SelfCollision {
+    /// The snake dies.
+    #[default]
+    Die,
+    /// The snake loses the segment it ran into and everything behind it.
+    Cut,
+}
+
+/// How opposite arrow keys held at the same time, like Left and Right, are settled.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TiePolicy {
+    /// Drop both keys; any other arrow key still counts.
+    Ignore,
+    /// Don't turn while the conflict lasts.
+    #[default]
+    KeepCurrent,
+    /// The key that went down first wins.
+    FirstPressed,
+}
+
+/// The keys that steer, pause and restart the snake, any of them per action.
+///
+/// Both the arrow keys and WASD steer by default, `Space` pauses and `R` restarts; swap the
+/// lists or write them in a file for [`KeyBindings::load`] to rebind them. Player two of
+/// [`TwoPlayer`] only steers with theirs.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    pub left: Vec<KeyCode>,
+    pub up: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    pub pause: Vec<KeyCode>,
+    pub restart: Vec<KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            left: vec![KeyCode::Left, KeyCode::A],
+            up: vec![KeyCode::Up, KeyCode::W],
+            right: vec![KeyCode::Right, KeyCode::D],
+            down: vec![KeyCode::Down, KeyCode::S],
+            pause: vec![KeyCode::Space],
+            restart: vec![KeyCode::R],
+        }
+    }
+}
+
+/// Keys that can be named in a [`KeyBindings`] file, by their `KeyCode` name.
+const BINDABLE_KEYS: [KeyCode; 92] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Pause,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::Apostrophe,
+    KeyCode::Backslash,
+    KeyCode::Comma,
+    KeyCode::Equals,
+    KeyCode::Grave,
+    KeyCode::LAlt,
+    KeyCode::LBracket,
+    KeyCode::LControl,
+    KeyCode::LShift,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::RAlt,
+    KeyCode::RBracket,
+    KeyCode::RControl,
+    KeyCode::RShift,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+];
+
+/// Keys the game already answers to outside the bindings, with what they do there.
+const RESERVED_KEYS: [(KeyCode, &str); 16] = [
+    (KeyCode::Escape, "quit"),
+    (KeyCode::Return, "restart from the game over screen"),
+    (KeyCode::F1, "the tutorial"),
+    (KeyCode::C, "clear the canvas"),
+    (KeyCode::F, "smoothing"),
+    (KeyCode::H, "the hint"),
+    (KeyCode::T, "the theme"),
+    (KeyCode::X, "shoot"),
+    (KeyCode::Tab, "ping"),
+    (KeyCode::LBracket, "slow down"),
+    (KeyCode::RBracket, "speed up"),
+    (KeyCode::LShift, "boost"),
+    (KeyCode::RShift, "boost"),
+    (KeyCode::Key1, "the shop"),
+    (KeyCode::Key2, "the shop"),
+    (KeyCode::Key3, "the shop"),
+];
+
+/// The layout of a [`KeyBindings`] file; each action is a key name or a list of them.
+#[derive(Deserialize)]
+struct KeyFile {
+    left: Option<toml::Value>,
+    up: Option<toml::Value>,
+    right: Option<toml::Value>,
+    down: Option<toml::Value>,
+    pause: Option<toml::Value>,
+    restart: Option<toml::Value>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// The keys named by one action in a [`KeyFile`].
+fn parse_keys(value: &toml::Value) -> Result<Vec<KeyCode>, String> {
+    let names = match value {
+        toml::Value::String(name) => vec![name.as_str()],
+        toml::Value::Array(names) => names
+            .iter()
+            .map(|name| name.as_str().ok_or("expected a key name"))
+            .collect::<Result<Vec<_>, &str>>()?,
+        _ => return Err("expected a key name or a list of them".to_string()),
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            let key = BINDABLE_KEYS
+                .iter()
+                .copied()
+                .find(|code| format!("{:?}", code).eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("unknown key {}", name))?;
+            match RESERVED_KEYS.iter().find(|(reserved, _)| *reserved == key) {
+                Some((_, what)) => Err(format!("{} is taken by {}", name, what)),
+                None => Ok(key),
+            }
+        })
+        .collect()
+}
+
+impl KeyBindings {
+    pub fn arrows() -> Self {
+        Self {
+            left: vec![KeyCode::Left],
+            up: vec![KeyCode::Up],
+            right: vec![KeyCode::Right],
+            down: vec![KeyCode::Down],
+            ..Self::default()
+        }
+    }
+
+    pub fn wasd() -> Self {
+        Self {
+            left: vec![KeyCode::A],
+            up: vec![KeyCode::W],
+            right: vec![KeyCode::D],
+            down: vec![KeyCode::S],
+            ..Self::default()
+        }
+    }
+
+    /// Reads bindings from the TOML file at `path`, like `left = "J"` or `pause = ["P",
+    /// "Space"]`.
+    ///
+    /// The actions are `left`, `up`, `right`, `down`, `pause` and `restart`, and keys go by
+    /// their `KeyCode` name. An action left out keeps its default keys, and so does one that
+    /// doesn't parse or names a key the game already uses for something else, with a
+    /// warning; a missing or malformed file means the defaults all round.
+    pub fn load(path: &str) -> Self {
+        let mut bindings = Self::default();
+        let contents = match read_save(path) {
+            Ok(Some(contents)) => contents,
+            Ok(None) => return bindings,
+            Err(e) => {
+                eprintln!("{}, using the default keys", e);
+                return bindings;
+            }
+        };
+        let file: KeyFile = match toml::from_str(&contents) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("{}: {}, using the default keys", path, e);
+                return bindings;
+            }
+        };
+        for action in file.unknown.keys() {
+            eprintln!("{}: unknown action {}, ignoring it", path, action);
+        }
+        let actions = vec![
+            ("left", file.left, &mut bindings.left),
+            ("up", file.up, &mut bindings.up),
+            ("right", file.right, &mut bindings.right),
+            ("down", file.down, &mut bindings.down),
+            ("pause", file.pause, &mut bindings.pause),
+            ("restart", file.restart, &mut bindings.restart),
+        ];
+        for (action, value, slot) in actions {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            match parse_keys(&value) {
+                Ok(keys) => *slot = keys,
+                Err(problem) => eprintln!(
+                    "{}: {} in {} = {}, keeping the default keys",
+                    path, problem, action, value
+                ),
+            }
+        }
+        bindings
+    }
+
+    /// These bindings with the steering keys of `other` left out, for sharing the keyboard
+    /// with a second player.
+    ///
+    /// The keys `other` steers with stay theirs: bound here as well in a file, they are
+    /// dropped with a warning. An action left without keys goes back to its arrow key.
+    pub fn without_keys_of(&self, other: &KeyBindings) -> Self {
+        let taken: Vec<KeyCode> = other.keys().map(|(key, _)| key).collect();
+        let (defaults, arrows) = (Self::default(), Self::arrows());
+        let mut bindings = self.clone();
+        let actions = vec![
+            ("left", &mut bindings.left, defaults.left, arrows.left),
+            ("up", &mut bindings.up, defaults.up, arrows.up),
+            ("right", &mut bindings.right, defaults.right, arrows.right),
+            ("down", &mut bindings.down, defaults.down, arrows.down),
+            ("pause", &mut bindings.pause, defaults.pause, arrows.pause),
+            (
+                "restart",
+                &mut bindings.restart,
+                defaults.restart,
+                arrows.restart,
+            ),
+        ];
+        for (action, slot, default, arrow) in actions {
+            // The default keys include WASD; only keys picked on purpose are worth a warning.
+            for key in slot
+                .iter()
+                .filter(|key| taken.contains(key) && !default.contains(key))
+            {
+                eprintln!(
+                    "{:?} for {} is taken by player two, leaving it to them",
+                    key, action
+                );
+            }
+            slot.retain(|key| !taken.contains(key));
+            if slot.is_empty() {
+                *slot = arrow;
+            }
+        }
+        bindings
+    }
+
+    /// Every bound key with the direction it steers, in the order Left, Up, Right, Down.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = (KeyCode, Direction)> + '_ {
+        self.left
+            .iter()
+            .map(|key| (*key, Direction::Left))
+            .chain(self.up.iter().map(|key| (*key, Direction::Up)))
+            .chain(self.right.iter().map(|key| (*key, Direction::Right)))
+            .chain(self.down.iter().map(|key| (*key, Direction::Down)))
+    }
+}
+
+/// How far a gamepad's left stick has to be pushed, from 0 to 1, before it steers; less is
+/// taken for drift.
+pub struct StickDeadzone(pub f32);
+
+impl Default for StickDeadzone {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+/// What the connected gamepads hold this frame, on the D-pad or the left stick.
+#[derive(Default)]
+pub(crate) struct GamepadInput {
+    pub(crate) pads: Vec<Gamepad>,
+    /// In the order Left, Up, Right, Down, like [`KeyBindings::keys`].
+    pub(crate) held: Vec<Direction>,
+    /// Whether `Start` went down on any of them this frame.
+    pub(crate) start_pressed: bool,
+}
+
+/// Makes turning more forgiving by letting the latest key pressed before a tick win.
+///
+/// Without assist quick turns queue up, a few deep, and are taken one per tick. With it, a
+/// late correction replaces the last queued key, and pressing the heading before it cancels
+/// that turn.
+/// The trade-off is that a stray press right before the tick overrides a deliberate one.
+#[derive(Default)]
+pub struct InputAssist(pub bool);
+
+/// A cell the head may only enter while heading `allowed`.
+#[derive(Debug, Copy, Clone)]
+pub struct OneWay {
+    pub position: Position,
+    pub allowed: Direction,
+}
+
+/// One-way cells of the arena; entering one against its arrow ends the game.
+#[derive(Default)]
+pub struct OneWayCells(pub Vec<OneWay>);
+
+impl OneWayCells {
+    pub(crate) fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.0.iter().map(|cell| cell.position)
+    }
+
+    pub(crate) fn allowed(&self, position: Position) -> Option<Direction> {
+        self.0
+            .iter()
+            .find(|cell| cell.position == position)
+            .map(|cell| cell.allowed)
+    }
+}
+
+/// A wall that opens for good once `opens_after` foods have been eaten in the current game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GateCell {
+    pub position: Position,
+    pub opens_after: u32,
+}
+
+/// Gates of the arena. They must not cut any free cell off from where the snake starts, or
+/// they are all left out.
+#[derive(Default)]
+pub struct GateCells(pub Vec<GateCell>);
+
+/// Walls inside the arena that never open, picked with `--obstacles on`. Like
+/// [`GateCells`], they must leave every free cell reachable from where the snake starts, or
+/// they are all left out.
+#[derive(Default)]
+pub struct ObstacleCells(pub Vec<Position>);
+
+impl ObstacleCells {
+    /// Single posts on every third cell each way, off the columns the snakes start out up.
+    pub fn posts(arena: ArenaSize) -> Self {
+        let rival = rival_start(arena);
+        Self(
+            arena
+                .cells()
+                .filter(|pos| pos.x % 3 == 1 && pos.y % 3 == 1)
+                .filter(|pos| pos.x != START_POSITION.x && pos.x != rival.x)
+                .filter(|pos| {
+                    Portals::across(arena)
+                        .positions()
+                        .all(|portal| portal != *pos)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Two linked cells, picked with `--portals on`. A head entering either comes out of the other
+/// heading the same way, and the body follows it through.
+#[derive(Default)]
+pub struct Portals(pub Option<(Position, Position)>);
+
+impl Portals {
+    /// A pair facing each other across the middle row, next to the side edges; left out when
+    /// the arena is too narrow to keep them off the columns the snakes start on.
+    pub fn across(arena: ArenaSize) -> Self {
+        let y = arena.height as i32 / 2;
+        let pair = (
+            Position { x: 1, y },
+            Position {
+                x: arena.width as i32 - 2,
+                y,
+            },
+        );
+        let starts = [START_POSITION.x, rival_start(arena).x];
+        if pair.0 == pair.1 || starts.contains(&pair.0.x) || starts.contains(&pair.1.x) {
+            return Self::default();
+        }
+        Self(Some(pair))
+    }
+
+    pub(crate) fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.0
+            .iter()
+            .flat_map(|(a, b)| std::iter::once(*a).chain(std::iter::once(*b)))
+    }
+}
+
+/// Foods eaten since the game started, counting toward opening [`GateCells`].
+#[derive(Default)]
+pub(crate) struct FoodsEaten(pub(crate) u32);
+
+/// A wall that opens for good once `requires` keys have been picked up in the current game.
+#[derive(Debug, Copy, Clone)]
+pub struct DoorCell {
+    pub position: Position,
+    pub requires: u32,
+}
+
+/// Keys lying around the arena and the doors they open.
+///
+/// Every door has to open eventually: if the keys reachable from the start, and those behind
+/// the doors they open in turn, never add up to what a door requires, the whole layout is
+/// left out.
+#[derive(Default)]
+pub struct KeysAndDoors {
+    pub keys: Vec<Position>,
+    pub doors: Vec<DoorCell>,
+}
+
+/// Keys picked up since the game started.
+#[derive(Default)]
+pub(crate) struct Keys(pub(crate) u32);
+
+/// When set, `X` fires the tail segment ahead of the head, destroying the first wall or
+/// enemy it hits. The last segment can't be fired.
+#[derive(Default)]
+pub struct ShootTail(pub bool);
+
+/// Number of movement ticks after a turn during which further turns are ignored.
+#[derive(Default)]
+pub struct TurnCooldown(pub u32);
+
+#[derive(Default)]
+pub(crate) struct SnekSegments(pub(crate) Vec<Entity>);
+
+/// Which end of the snake gets longer after eating.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum GrowthMode {
+    /// A segment appears right away on the cell the tail just left.
+    #[default]
+    Tail,
+    /// The head moves on as usual next tick while the tail stays put.
+    Head,
+}
+
+/// Segments owed under [`GrowthMode::Head`], paid out on the next movement tick.
+#[derive(Default)]
+pub(crate) struct PendingGrowth(pub(crate) u32);
+
+/// Segments sit on every this many cells of the head's path; 1 keeps the body contiguous.
+pub struct BodySpacing(pub u32);
+
+impl Default for BodySpacing {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Cells the head went through, most recent first, as far back as the tail reaches.
+#[derive(Default)]
+pub(crate) struct SnekPath(pub(crate) VecDeque<Position>);
+
+/// Time between moves at the start of every run, before [`SpeedRamp`] speeds the snake up.
+pub struct BaseSpeed(pub Duration);
+
+impl Default for BaseSpeed {
+    fn default() -> Self {
+        Self(Duration::from_millis(250))
+    }
+}
+
+pub(crate) struct SnekMoveTimer(pub(crate) Timer);
+
+impl Default for SnekMoveTimer {
+    fn default() -> Self {
+        Self(Timer::new(BaseSpeed::default().0, true))
+    }
+}
+
+impl Deref for SnekMoveTimer {
+    type Target = Timer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SnekMoveTimer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// When set, the board always holds exactly one food of each of these kinds, and eaten food
+/// is replaced right away.
+///
+/// This takes over from the timed spawns and their per-kind chances. `FoodKind::Normal`
+/// alone gives the classic single piece of food.
+#[derive(Default)]
+pub struct EnabledFoodKinds(pub Option<HashSet<FoodKind>>);
+
+/// Where new food is placed.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum FoodSpawn {
+    /// Anywhere on the board.
+    #[default]
+    Uniform,
+    /// Close to the previously spawned food, leaving a trail to follow.
+    NearPrevious { radius: u32 },
+    /// On the cell the tail just left, so the snake has to turn back for it.
+    AtTail,
+    /// A connected group of `size` foods at once. Eating every one of them scores `bonus`
+    /// on top. Only the first of the group can be a special kind.
+    Cluster { size: u32, bonus: u32 },
+}
+
+/// How many foods the timed spawns leave on the board at once; a cluster is let in as long as
+/// the board is below it.
+pub struct MaxFood(pub u32);
+
+impl Default for MaxFood {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// How much poison food hurts.
+pub struct PoisonConfig {
+    /// Number of tail segments removed when poison is eaten.
+    pub shrink: usize,
+    /// Points taken off the score.
+    pub penalty: u32,
+    /// Chance that a spawned food is poison.
+    pub spawn_chance: f32,
+}
+
+impl Default for PoisonConfig {
+    fn default() -> Self {
+        Self {
+            shrink: 2,
+            penalty: 2,
+            spawn_chance: 0.1,
+        }
+    }
+}
+
+/// How big, and how common, [`FoodKind::Mega`] food is.
+pub struct MegaFoodConfig {
+    /// Segments grown by eating one.
+    pub growth: u32,
+    /// Chance that a spawned food is mega food.
+    pub spawn_chance: f32,
+}
+
+impl Default for MegaFoodConfig {
+    fn default() -> Self {
+        Self {
+            growth: 5,
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// What [`FoodKind::Bonus`] food grows, how long it stays out and how common it is.
+pub struct BonusFoodConfig {
+    /// Segments grown by eating one.
+    pub growth: u32,
+    /// It disappears once it has been out this long uneaten, not counting time paused.
+    pub lifetime: Duration,
+    /// Chance that a spawned food is bonus food.
+    pub spawn_chance: f32,
+}
+
+impl Default for BonusFoodConfig {
+    fn default() -> Self {
+        Self {
+            growth: 3,
+            lifetime: Duration::from_secs(8),
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// How often wall break food shows up.
+pub struct WallBreakConfig {
+    /// Chance that a spawned food grants a wall break charge.
+    pub spawn_chance: f32,
+}
+
+impl Default for WallBreakConfig {
+    fn default() -> Self {
+        Self { spawn_chance: 0.05 }
+    }
+}
+
+/// Number of walls the head can still smash through instead of dying.
+#[derive(Default)]
+pub struct WallBreakCharges(pub u32);
+
+/// When set, every uneaten food jumps to a new free cell every this many movement ticks.
+#[derive(Default)]
+pub struct ScatterInterval(pub Option<u32>);
+
+/// When set, uneaten food creeps a cell toward the head every this many movement ticks.
+#[derive(Default)]
+pub struct FoodHoming(pub Option<u32>);
+
+/// Tuning of the food magnet powerup.
+pub struct MagnetConfig {
+    /// Foods within this many cells (Manhattan distance) of the head are pulled in.
+    pub radius: u32,
+    pub duration: Duration,
+    /// Chance that a spawned food is a magnet.
+    pub spawn_chance: f32,
+}
+
+impl Default for MagnetConfig {
+    fn default() -> Self {
+        Self {
+            radius: 3,
+            duration: Duration::from_secs(5),
+            spawn_chance: 0.1,
+        }
+    }
+}
+
+/// Time left on an active food magnet.
+pub(crate) struct MagnetEffect(pub(crate) Timer);
+
+impl MagnetEffect {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+}
+
+impl Default for MagnetEffect {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Tuning of the food that swaps the arrow keys around.
+pub struct ReverseConfig {
+    pub duration: Duration,
+    /// Chance that a spawned food reverses the controls.
+    pub spawn_chance: f32,
+}
+
+impl Default for ReverseConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(4),
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// Time left during which Left/Right and Up/Down are swapped.
+pub(crate) struct ReversedControls(pub(crate) Timer);
+
+impl ReversedControls {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+}
+
+impl Default for ReversedControls {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// When set, the points from each meal float up from the head and fade out.
+#[derive(Default)]
+pub struct ScorePopups(pub Option<ScorePopupConfig>);
+
+pub struct ScorePopupConfig {
+    /// How far, in pixels, the points rise before they are gone.
+    pub rise: f32,
+    pub duration: Duration,
+}
+
+impl Default for ScorePopupConfig {
+    fn default() -> Self {
+        Self {
+            rise: 40.0,
+            duration: Duration::from_millis(800),
+        }
+    }
+}
+
+/// When set, holding Shift speeds the snake up for as long as its boost meter lasts.
+#[derive(Default)]
+pub struct BoostMode(pub Option<BoostConfig>);
+
+/// Tuning of the boost held on Shift. The meter runs from empty at 0 to full at 1.
+pub struct BoostConfig {
+    /// How many times faster the snake moves while boosting.
+    pub speedup: f32,
+    /// Meter used up per second of boosting.
+    pub drain: f32,
+    /// Meter refilled per second with Shift released.
+    pub regen: f32,
+}
+
+impl Default for BoostConfig {
+    fn default() -> Self {
+        Self {
+            speedup: 2.0,
+            drain: 0.5,
+            regen: 0.2,
+        }
+    }
+}
+
+/// What is left of the boost meter, and whether the snake is boosting right now.
+pub(crate) struct Boost {
+    pub(crate) meter: f32,
+    pub(crate) active: bool,
+}
+
+impl Default for Boost {
+    fn default() -> Self {
+        Self {
+            meter: 1.0,
+            active: false,
+        }
+    }
+}
+
+/// When set, the snake now and then drops a coin in the cell its tail just left.
+#[derive(Default)]
+pub struct CoinDrops(pub Option<CoinDropConfig>);
+
+/// Tuning of the coins dropped under [`CoinDrops`].
+pub struct CoinDropConfig {
+    /// Chance, per movement tick, that a coin is dropped.
+    pub chance: f32,
+    /// Points for picking one up.
+    pub value: u32,
+    /// How long a coin lies around before it is gone.
+    pub lifetime: Duration,
+}
+
+impl Default for CoinDropConfig {
+    fn default() -> Self {
+        Self {
+            chance: 0.05,
+            value: 3,
+            lifetime: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Tuning of the food that makes normal food worth more for a while.
+pub struct DoubleScoreConfig {
+    pub duration: Duration,
+    /// What the score of each normal food eaten meanwhile is multiplied by.
+    pub multiplier: u32,
+    /// Chance that a spawned food is a score multiplier.
+    pub spawn_chance: f32,
+}
+
+impl Default for DoubleScoreConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(8),
+            multiplier: 2,
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// Time left during which normal food scores [`DoubleScoreConfig::multiplier`] times over.
+pub(crate) struct DoubleScore(pub(crate) Timer);
+
+impl DoubleScore {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+}
+
+impl Default for DoubleScore {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Tuning of the food that lets the snake pass through its own body, but not walls or edges.
+pub struct PhaseConfig {
+    pub duration: Duration,
+    /// Chance that a spawned food lets the snake phase.
+    pub spawn_chance: f32,
+}
+
+impl Default for PhaseConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(3),
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// How far [`FoodKind::Explosive`] food reaches, what it pays and how common it is.
+///
+/// It only has other food to clear when more than one can be out at a time, as with
+/// [`FoodSpawn::Cluster`] or [`EnabledFoodKinds`].
+pub struct ExplosiveConfig {
+    /// Other food at most this many cells away is cleared along with it.
+    pub radius: u32,
+    /// Points for each food cleared.
+    pub bonus: u32,
+    /// Chance that a spawned food is explosive.
+    pub spawn_chance: f32,
+}
+
+impl Default for ExplosiveConfig {
+    fn default() -> Self {
+        Self {
+            radius: 3,
+            bonus: 2,
+            spawn_chance: 0.03,
+        }
+    }
+}
+
+/// How often the food that splits the snake shows up.
+pub struct SplitConfig {
+    /// Chance that a spawned food splits the snake.
+    pub spawn_chance: f32,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        Self { spawn_chance: 0.02 }
+    }
+}
+
+/// Time left during which the head passes through the body instead of hitting it.
+pub(crate) struct PhaseSelf(pub(crate) Timer);
+
+impl PhaseSelf {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+}
+
+impl Default for PhaseSelf {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Tuning of the food that makes the snake invincible.
+pub struct InvincibleConfig {
+    pub duration: Duration,
+    /// Chance that a spawned food grants invincibility.
+    pub spawn_chance: f32,
+}
+
+impl Default for InvincibleConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(5),
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// Tuning of the blue food that slows the snake down.
+pub struct SlowConfig {
+    pub duration: Duration,
+    /// Share of its usual speed the snake keeps while slowed.
+    pub speed: f32,
+    /// Chance that a spawned food slows the snake down.
+    pub spawn_chance: f32,
+}
+
+impl Default for SlowConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(4),
+            speed: 0.5,
+            spawn_chance: 0.05,
+        }
+    }
+}
+
+/// Time left during which the snake moves at [`SlowConfig::speed`].
+pub(crate) struct SlowEffect(pub(crate) Timer);
+
+impl SlowEffect {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+
+    /// Slows the snake for `duration` on top of whatever time is left.
+    pub(crate) fn extend(&mut self, duration: Duration) {
+        let left = if self.is_active() {
+            Duration::from_secs_f32(self.0.duration - self.0.elapsed)
+        } else {
+            Duration::default()
+        };
+        self.0 = Timer::new(left + duration, false);
+    }
+}
+
+impl Default for SlowEffect {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Time left during which edges, walls and the snake's own body are harmless.
+pub(crate) struct Invincible(pub(crate) Timer);
+
+impl Invincible {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+}
+
+impl Default for Invincible {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Paces regular food spawns; restarted along with the game so every run gets the same cadence.
+pub(crate) struct FoodSpawnTimer(pub(crate) Timer);
+
+impl Default for FoodSpawnTimer {
+    fn default() -> Self {
+        Self(Timer::new(Duration::from_millis(1000), true))
+    }
+}
+
+pub(crate) struct Materials {
+    pub(crate) head_color: Color,
+    pub(crate) head_material: Handle<ColorMaterial>,
+    pub(crate) segment_color: Color,
+    pub(crate) segment_material: Handle<ColorMaterial>,
+    pub(crate) food_material: Handle<ColorMaterial>,
+    pub(crate) ghost_material: Handle<ColorMaterial>,
+    pub(crate) enemy_material: Handle<ColorMaterial>,
+    pub(crate) magnet_food_material: Handle<ColorMaterial>,
+    pub(crate) border_material: Handle<ColorMaterial>,
+    pub(crate) border_warning_material: Handle<ColorMaterial>,
+    pub(crate) wall_break_food_material: Handle<ColorMaterial>,
+    pub(crate) poison_food_material: Handle<ColorMaterial>,
+    pub(crate) reverse_food_material: Handle<ColorMaterial>,
+    pub(crate) invincible_food_material: Handle<ColorMaterial>,
+    pub(crate) double_score_food_material: Handle<ColorMaterial>,
+    pub(crate) phase_food_material: Handle<ColorMaterial>,
+    pub(crate) split_food_material: Handle<ColorMaterial>,
+    pub(crate) explosive_food_material: Handle<ColorMaterial>,
+    pub(crate) bonus_food_material: Handle<ColorMaterial>,
+    pub(crate) slow_food_material: Handle<ColorMaterial>,
+    pub(crate) mega_food_material: Handle<ColorMaterial>,
+    pub(crate) wall_material: Handle<ColorMaterial>,
+    pub(crate) fog_material: Handle<ColorMaterial>,
+    pub(crate) tongue_material: Handle<ColorMaterial>,
+    pub(crate) aura_material: Handle<ColorMaterial>,
+    pub(crate) segment_arrow_material: Handle<ColorMaterial>,
+    pub(crate) one_way_material: Handle<ColorMaterial>,
+    pub(crate) portal_material: Handle<ColorMaterial>,
+    pub(crate) coin_material: Handle<ColorMaterial>,
+    pub(crate) peek_material: Handle<ColorMaterial>,
+    pub(crate) hint_material: Handle<ColorMaterial>,
+    pub(crate) gate_material: Handle<ColorMaterial>,
+    pub(crate) key_material: Handle<ColorMaterial>,
+    pub(crate) door_material: Handle<ColorMaterial>,
+    pub(crate) rival_head_material: Handle<ColorMaterial>,
+    pub(crate) rival_segment_material: Handle<ColorMaterial>,
+    pub(crate) circle_texture: Handle<Texture>,
+}
+
+impl Materials {
+    pub(crate) fn food(&self, kind: FoodKind) -> &Handle<ColorMaterial> {
+        match kind {
+            FoodKind::Normal => &self.food_material,
+            FoodKind::Magnet => &self.magnet_food_material,
+            FoodKind::WallBreak => &self.wall_break_food_material,
+            FoodKind::Poison => &self.poison_food_material,
+            FoodKind::Reverse => &self.reverse_food_material,
+            FoodKind::Invincible => &self.invincible_food_material,
+            FoodKind::DoubleScore => &self.double_score_food_material,
+            FoodKind::Phase => &self.phase_food_material,
+            FoodKind::Split => &self.split_food_material,
+            FoodKind::Explosive => &self.explosive_food_material,
+            FoodKind::Bonus => &self.bonus_food_material,
+            FoodKind::Slow => &self.slow_food_material,
+            FoodKind::Mega { .. } => &self.mega_food_material,
+        }
+    }
+
+    /// The materials whose outline follows [`CellShape`].
+    pub(crate) fn shaped(&self) -> [&Handle<ColorMaterial>; 15] {
+        [
+            &self.head_material,
+            &self.segment_material,
+            &self.food_material,
+            &self.magnet_food_material,
+            &self.wall_break_food_material,
+            &self.poison_food_material,
+            &self.reverse_food_material,
+            &self.invincible_food_material,
+            &self.double_score_food_material,
+            &self.phase_food_material,
+            &self.split_food_material,
+            &self.explosive_food_material,
+            &self.bonus_food_material,
+            &self.slow_food_material,
+            &self.mega_food_material,
+        ]
+    }
+}
+
+/// Fraction of a cell covered by each kind of sprite.
+pub struct CellPadding {
+    pub head: f32,
+    pub segment: f32,
+    pub food: f32,
+}
+
+impl Default for CellPadding {
+    fn default() -> Self {
+        Self {
+            head: 0.8,
+            segment: 0.65,
+            food: 0.8,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct LastTailPosition(pub(crate) Option<Position>);
+
+#[derive(Default)]
+pub(crate) struct Score(pub(crate) u32);
+
+/// While set, no gameplay timer advances, so no time builds up to be released on resume.
+#[derive(Default)]
+pub struct Paused(pub bool);
+
+/// When set, every frame counts as this long, whatever the clock says, so a run steps the
+/// same way each time it is played. The headless app uses a sixtieth of a second.
+#[derive(Default)]
+pub struct FixedStep(pub Option<Duration>);
+
+/// Whether the best run, wallet, last replay, tutorial marker and settings are saved to their
+/// `snek_*` files in the working directory. The headless app turns it off, so tests and
+/// tools leave no files behind.
+pub struct SaveFiles(pub bool);
+
+impl Default for SaveFiles {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Whether Escape asks before quitting; with this off, it quits right away.
+pub struct ConfirmQuit(pub bool);
+
+impl Default for ConfirmQuit {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Whether the player paused the game with the pause key, rather than something else pausing it.
+#[derive(Default)]
+pub(crate) struct PlayerPause(pub(crate) bool);
+
+/// The "Quit?" prompt, and whether the game was already paused when it opened.
+#[derive(Default)]
+pub(crate) struct QuitPrompt {
+    pub(crate) open: bool,
+    pub(crate) was_paused: bool,
+}
+
+/// Number of lethal collisions a game takes before it is over.
+pub struct Lives(pub u32);
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Lives remaining in the current game, counting the one being played.
+pub struct LivesLeft(pub u32);
+
+impl Default for LivesLeft {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// How long the current run has lasted, not counting time spent paused.
+#[derive(Default)]
+pub(crate) struct RunTime(pub(crate) Duration);
+
+/// A summary of the game so far, printed as JSON once the game is over and kept until it is
+/// reset.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct GameStats {
+    /// Cells of the snake, head included.
+    pub length: usize,
+    pub score: u32,
+    /// Time played, not counting time spent paused; in seconds in JSON.
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Duration,
+    /// Meals that grew the snake.
+    pub foods_eaten: u32,
+}
+
+impl GameStats {
+    /// The stats as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("stats are plain numbers")
+    }
+}
+
+fn serialize_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// A file every finished game appends its [`GameStats`] to, one line of JSON each, picked
+/// with `--stats-file <file>`.
+#[derive(Default)]
+pub struct StatsFile(pub Option<String>);
+
+/// When set, the snake loses its tail every time this long passes without eating, and dies
+/// once it is down to its last segment.
+#[derive(Default)]
+pub struct StarvationInterval(pub Option<Duration>);
+
+/// Time since the snake last ate, or last lost its tail to hunger.
+#[derive(Default)]
+pub(crate) struct Starvation(pub(crate) Timer);
+
+/// When set, a run ends once it has lasted this long, and the clock counts down to it.
+///
+/// Lives lost along the way don't stop the clock. Once time is up the game pauses on the
+/// final score until it is reset.
+#[derive(Default)]
+pub struct TimeAttack(pub Option<Duration>);
+
+/// Whether the game is still on its title menu or has been started from it.
+///
+/// The board stays empty and paused on the menu; the first snake only spawns once `Enter`
+/// starts the game.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum GameState {
+    #[default]
+    Menu,
+    Running,
+}
+
+/// Set once the last life is lost, until the game is reset; the board stays frozen under a
+/// "GAME OVER" notice in the meantime.
+#[derive(Default)]
+pub(crate) struct GameOverScreen(pub(crate) bool);
+
+/// Set once a [`TimeAttack`] run is out of time, until the game is reset.
+#[derive(Default)]
+pub(crate) struct TimeUp(pub(crate) bool);
+
+/// What makes the snake speed up under a [`SpeedRamp`].
+///
+/// Defaults to `Length`, so that by default the snake speeds up as it grows, whatever the
+/// food it ate was worth; it used to default to `Score`, back when the ramp had no step by
+/// default.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SpeedBasis {
+    /// Every point scored.
+    Score,
+    /// Every segment grown beyond the first, regardless of score.
+    #[default]
+    Length,
+}
+
+/// How much every segment grown takes off the move interval by default, and the shortest
+/// interval that gets it down to; see [`SpeedRamp`].
+pub(crate) const GROWTH_SPEED_STEP: Duration = Duration::from_millis(10);
+pub(crate) const FASTEST_GROWN_SPEED: Duration = Duration::from_millis(80);
+
+/// How much the move interval shrinks per step of the [`SpeedBasis`].
+///
+/// By default the snake gets 10ms faster with every segment it grows, down to a move every
+/// 80ms. A step of zero keeps it at its starting speed.
+pub struct SpeedRamp {
+    pub step: Duration,
+    /// The move interval never drops below this.
+    pub floor: Duration,
+}
+
+impl Default for SpeedRamp {
+    fn default() -> Self {
+        Self {
+            step: GROWTH_SPEED_STEP,
+            floor: FASTEST_GROWN_SPEED,
+        }
+    }
+}
+
+/// When set, the snake is tinted toward red the faster it moves.
+#[derive(Default)]
+pub struct SpeedColor(pub bool);
+
+/// When set, the snake fades toward grey as the run goes on, over [`AgeScale`].
+///
+/// The fade is the base the other tints work from: [`SpeedColor`] still reddens an old snake,
+/// and phasing through itself still turns it see-through.
+#[derive(Default)]
+pub struct AgeColor(pub bool);
+
+/// How long a run lasts before [`AgeColor`] has faded the snake all the way.
+pub struct AgeScale(pub Duration);
+
+impl Default for AgeScale {
+    fn default() -> Self {
+        Self(Duration::from_secs(180))
+    }
+}
+
+/// Points for a piece of food, falling from `max` to `min` while it sits uneaten.
+pub struct FoodValueDecay {
+    pub max: u32,
+    pub min: u32,
+    /// Time it takes for the value to drop all the way to `min`.
+    pub window: Duration,
+}
+
+impl Default for FoodValueDecay {
+    fn default() -> Self {
+        Self {
+            max: 5,
+            min: 1,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+impl FoodValueDecay {
+    /// The points a food is worth after sitting uneaten for `age`.
+    pub fn value(&self, age: Duration) -> u32 {
+        let t = if self.window == Duration::from_secs(0) {
+            1.0
+        } else {
+            (age.as_secs_f32() / self.window.as_secs_f32()).min(1.0)
+        };
+        let (max, min) = (self.max as f32, self.min as f32);
+        (max + (min - max) * t).round() as u32
+    }
+}
+
+/// When set, eating food scores its decayed value instead of [`Scoring::per_food`].
+#[derive(Default)]
+pub struct FoodDecay(pub Option<FoodValueDecay>);
+
+/// Levels of the food chain objective: eat `foods` pieces before the time limit runs out.
+pub struct FoodChainConfig {
+    pub foods: u32,
+    /// Time limit of the first level.
+    pub time_limit: Duration,
+    /// How much shorter the limit gets with every level cleared.
+    pub step: Duration,
+    /// The limit never drops below this.
+    pub min_time_limit: Duration,
+}
+
+impl Default for FoodChainConfig {
+    fn default() -> Self {
+        Self {
+            foods: 5,
+            time_limit: Duration::from_secs(30),
+            step: Duration::from_secs(3),
+            min_time_limit: Duration::from_secs(10),
+        }
+    }
+}
+
+impl FoodChainConfig {
+    /// The time limit of level `number`, counting from 1.
+    pub fn time_limit(&self, number: u32) -> Duration {
+        self.time_limit
+            .checked_sub(self.step * number.saturating_sub(1))
+            .unwrap_or_default()
+            .max(self.min_time_limit)
+    }
+}
+
+/// When set, the game is played as a series of food chain levels instead of endlessly.
+#[derive(Default)]
+pub struct FoodChain(pub Option<FoodChainConfig>);
+
+/// Progress through the current food chain level.
+pub(crate) struct Level {
+    pub(crate) number: u32,
+    pub(crate) foods_remaining: u32,
+    pub(crate) time_limit: Timer,
+}
+
+impl Level {
+    pub(crate) fn new(number: u32, config: &FoodChainConfig) -> Self {
+        Self {
+            number,
+            foods_remaining: config.foods,
+            time_limit: Timer::new(config.time_limit(number), false),
+        }
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self::new(1, &FoodChainConfig::default())
+    }
+}
+
+/// Points awarded for eating food and for every movement tick survived.
+pub struct Scoring {
+    pub per_food: u32,
+    pub per_tick: u32,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            per_food: 1,
+            per_tick: 0,
+        }
+    }
+}
+
+/// How the last ticks before a death are played back.
+pub struct DeathReplayConfig {
+    /// Number of movement ticks to replay.
+    pub ticks: usize,
+    /// How many times slower than the snake was moving the replay runs.
+    pub slowdown: f32,
+}
+
+impl Default for DeathReplayConfig {
+    fn default() -> Self {
+        Self {
+            ticks: 10,
+            slowdown: 4.0,
+        }
+    }
+}
+
+/// When set, every death is replayed in slow motion before the game goes on.
+#[derive(Default)]
+pub struct DeathReplay(pub Option<DeathReplayConfig>);
+
+/// How far back [`RewindOnDeath`] goes, and how often.
+pub struct RewindConfig {
+    /// Number of movement ticks to go back.
+    pub ticks: usize,
+    /// Rewinds allowed in a game before deaths count again; `None` for no limit.
+    pub per_game: Option<u32>,
+}
+
+impl Default for RewindConfig {
+    fn default() -> Self {
+        Self {
+            // About two seconds at the starting speed.
+            ticks: 8,
+            per_game: None,
+        }
+    }
+}
+
+/// When set, dying puts the snake and food back where they were a few ticks earlier instead
+/// of costing a life, keeping the score. It takes over from [`DeathReplay`].
+#[derive(Default)]
+pub struct RewindOnDeath(pub Option<RewindConfig>);
+
+/// Everything needed to put the board back to how it was after one movement tick.
+pub(crate) struct RewindPoint {
+    pub(crate) head: Position,
+    pub(crate) direction: Direction,
+    pub(crate) segments: Vec<Position>,
+    pub(crate) path: VecDeque<Position>,
+    pub(crate) food: Vec<(Position, FoodKind)>,
+}
+
+/// The board after each of the last few ticks the snake survived, oldest first, and the
+/// rewinds used up this game.
+#[derive(Default)]
+pub(crate) struct RewindHistory {
+    pub(crate) points: VecDeque<RewindPoint>,
+    pub(crate) used: u32,
+}
+
+/// The board after each of the last few movement ticks, oldest first.
+#[derive(Default)]
+pub(crate) struct RecentTicks(pub(crate) VecDeque<GameSnapshot>);
+
+pub(crate) struct Playback {
+    pub(crate) frames: VecDeque<GameSnapshot>,
+    pub(crate) timer: Timer,
+}
+
+/// The death replay in progress, if any; the game stays paused until it is done.
+#[derive(Default)]
+pub(crate) struct DeathPlayback(pub(crate) Option<Playback>);
+
+/// A read-only view of the board, taken once per movement tick.
+#[derive(Debug, Clone, Default)]
+pub struct GameSnapshot {
+    pub head: Option<Position>,
+    pub segments: Vec<Position>,
+    pub food: Vec<Position>,
+    pub score: u32,
+}
+
+pub type TickCallback = Box<dyn FnMut(&GameSnapshot) + Send + Sync>;
+
+/// Optional callback invoked with a [`GameSnapshot`] on every movement tick.
+///
+/// Insert it after adding [`SnekPlugin`](crate::SnekPlugin) to observe the game from the outside.
+#[derive(Default)]
+pub struct TickHook(pub Option<TickCallback>);
+
+/// Reads a save file, treating a missing file as `None` rather than an error.
+pub(crate) fn read_save(path: &str) -> Result<Option<String>, SnekError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(SnekError::ConfigParse(format!("{}: {}", path, e))),
+    }
+}
+
+/// Frame pacing and sprite filtering, picked with `--vsync on|off`, `--fps-cap <fps>` and
+/// `--smooth on|off` on the command line, see [`LaunchOptions`].
+///
+/// Insert it after adding [`SnekPlugin`](crate::SnekPlugin), which starts out uncapped.
+pub struct FrameSettings {
+    pub vsync: bool,
+    /// Upper bound on frames per second, on top of whatever vsync does.
+    pub fps_cap: Option<u32>,
+    /// Sample sprite textures linearly instead of keeping their pixels crisp.
+    pub smooth: bool,
+}
+
+impl FrameSettings {
+    /// Writes the settings that can change while playing, as the flags that set them.
+    pub(crate) fn save(&self, path: &str) -> std::io::Result<()> {
+        let smooth = if self.smooth { "on" } else { "off" };
+        std::fs::write(path, format!("--smooth {}\n", smooth))
+    }
+}
+
+/// Set whenever a setting changes while playing, until `save_settings` has written it out.
+#[derive(Default)]
+pub(crate) struct SettingsDirty(pub(crate) bool);
+
+impl Default for FrameSettings {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            fps_cap: None,
+            smooth: false,
+        }
+    }
+}
+
+/// An image stretched to cover the window behind the arena, in place of the clear color.
+///
+/// The path is relative to the assets folder, like `--background images/grass.png`. If the
+/// image fails to load the clear color shows instead.
+#[derive(Default)]
+pub struct BackgroundImage(pub Option<String>);
+
+/// Seed for the order and placement of food, shared by everyone who wants the same board.
+///
+/// Picked with `--food-seed <n>`, or with `--daily` for a seed that changes once a day. Only
+/// food draws from it, so the n-th food lands on the same cell however each player moves,
+/// unless that cell is blocked for them. `None` picks a fresh seed every game.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FoodSeed(pub Option<u64>);
+
+impl FoodSeed {
+    /// The seed for today: the number of whole days since the UNIX epoch, in UTC.
+    pub fn daily() -> Self {
+        Self(Some(days_since_epoch()))
+    }
+}
+
+/// Whole days since the UNIX epoch, in UTC.
+pub(crate) fn days_since_epoch() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_secs() / (24 * 60 * 60)
+}
+
+/// The random stream food is placed from, kept apart from everything else that rolls dice.
+///
+/// Each spawn gets its own generator derived from the seed and the spawn count, so how many
+/// draws one spawn takes, say retrying around the snake, never shifts the ones after it.
+pub(crate) struct FoodRng {
+    seed: u64,
+    spawned: u64,
+}
+
+impl FoodRng {
+    pub(crate) fn new(seed: FoodSeed) -> Self {
+        Self {
+            seed: seed.0.unwrap_or_else(random),
+            spawned: 0,
+        }
+    }
+
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub(crate) fn next_spawn(&mut self) -> StdRng {
+        let rng = StdRng::seed_from_u64(
+            self.seed
+                .wrapping_add(self.spawned.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+        );
+        self.spawned += 1;
+        rng
+    }
+
+    /// A generator for a roll the snake takes on movement tick `tick`, from the same seed but
+    /// without touching the food's draws.
+    pub(crate) fn on_tick(&self, tick: u64) -> StdRng {
+        StdRng::seed_from_u64(
+            !self
+                .seed
+                .wrapping_add(tick.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+        )
+    }
+}
+
+impl Default for FoodRng {
+    fn default() -> Self {
+        Self::new(FoodSeed::default())
+    }
+}
+
+/// Sounds played on eating and on dying, picked with `--eat-sound` and `--death-sound`.
+///
+/// Paths are relative to the assets folder, like [`MusicTrack`]'s. Either one left out, or
+/// failing to load, is simply not played.
+#[derive(Default)]
+pub struct SoundEffects {
+    pub eat: Option<String>,
+    pub death: Option<String>,
+}
+
+/// The loaded [`SoundEffects`].
+#[derive(Default)]
+pub(crate) struct AudioAssets {
+    pub(crate) eat: Option<Handle<AudioSource>>,
+    pub(crate) death: Option<Handle<AudioSource>>,
+}
+
+/// A track restarted every `ticks_per_loop` movement ticks, so its beat follows the snake.
+///
+/// The path is relative to the assets folder, like `--music sounds/loop.mp3`. Cut the track
+/// to exactly that many ticks at the starting speed; as the snake speeds up, the loop
+/// restarts sooner. If the track fails to load the game stays silent.
+pub struct MusicTrack {
+    pub path: Option<String>,
+    pub ticks_per_loop: u32,
+}
+
+impl Default for MusicTrack {
+    fn default() -> Self {
+        Self {
+            path: None,
+            ticks_per_loop: 16,
+        }
+    }
+}
+
+/// When set, turns only register close to a movement tick, and a marker lights up on the
+/// beat to show when.
+#[derive(Default)]
+pub struct RhythmMode(pub bool);
+
+/// When set, the snake moves once per beat at this many beats per minute, in place of the
+/// [`SpeedRamp`], and the beat marker pulses on every move.
+#[derive(Default)]
+pub struct MusicBpm(pub Option<f32>);
+
+/// Everything that can be picked on the command line.
+///
+/// Insert its parts after adding [`SnekPlugin`](crate::SnekPlugin), which starts out with
+/// the defaults.
+#[derive(Default)]
+pub struct LaunchOptions {
+    pub frame: FrameSettings,
+    pub background: BackgroundImage,
+    pub food_seed: FoodSeed,
+    pub music: MusicTrack,
+    pub sounds: SoundEffects,
+    /// Picked with `--daily`; its food seed is in `food_seed` as well, and the rest goes in
+    /// with [`apply_daily`](crate::apply_daily).
+    pub daily: Option<DailyChallenge>,
+    pub arena: ArenaSize,
+    /// Picked with `--obstacles on`, to go in as [`ObstacleCells::posts`] of `arena`.
+    pub obstacles: bool,
+    /// Picked with `--portals on`, to go in as [`Portals::across`] `arena`.
+    pub portals: bool,
+    /// Picked with `--replay <file>`, to go in as [`LoadedReplay`] with its seed as the
+    /// [`FoodSeed`].
+    pub replay: Option<ReplayLog>,
+    pub theme: Theme,
+    /// Picked with `--two-player on`, to go in as [`TwoPlayer`] on [`KeyBindings::wasd`],
+    /// with player one on the rest of `keys`, see [`KeyBindings::without_keys_of`].
+    pub two_player: bool,
+    /// Picked with `--difficulty easy|medium|hard`, to go in with
+    /// [`apply_difficulty`](crate::apply_difficulty).
+    pub difficulty: Difficulty,
+    /// Picked with `--preset zen|classic|insane`, to go in with
+    /// [`apply_preset`](crate::apply_preset) after the difficulty.
+    pub preset: DifficultyPreset,
+    /// Loaded from `snek_keys.toml` by [`LaunchOptions::from_saved_and_args`].
+    pub keys: KeyBindings,
+    /// Picked with `--stats-file <file>`.
+    pub stats_file: StatsFile,
+}
+
+impl LaunchOptions {
+    /// Parses the settings saved while playing last time, then `args` on top of them, and
+    /// loads the key bindings.
+    ///
+    /// A saved file that doesn't parse is logged and ignored.
+    pub fn from_saved_and_args(args: impl Iterator<Item = String>) -> Result<Self, SnekError> {
+        let saved: Vec<String> = match read_save(SETTINGS_PATH) {
+            Ok(contents) => contents
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            Err(e) => {
+                eprintln!("{}, starting with the defaults", e);
+                Vec::new()
+            }
+        };
+        let saved = match Self::from_args(saved.clone().into_iter()) {
+            Ok(_) => saved,
+            Err(e) => {
+                eprintln!("{}: {}, starting with the defaults", SETTINGS_PATH, e);
+                Vec::new()
+            }
+        };
+        let mut options = Self::from_args(saved.into_iter().chain(args))?;
+        options.keys = KeyBindings::load(KEYS_PATH);
+        if !UnlockedThemes::load(UNLOCKS_PATH).contains(options.theme) {
+            eprintln!(
+                "the {} theme is not unlocked yet, buy it in the menu; using {}",
+                options.theme.name(),
+                Theme::default().name()
+            );
+            options.theme = Theme::default();
+        }
+        Ok(options)
+    }
+
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, SnekError> {
+        let mut options = Self::default();
+        let settings = &mut options.frame;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| SnekError::ConfigParse(format!("{} needs a value", arg)))
+            };
+            match arg.as_str() {
+                "--vsync" => settings.vsync = parse_switch(&arg, &value()?)?,
+                "--smooth" => settings.smooth = parse_switch(&arg, &value()?)?,
+                "--replay" => options.replay = Some(ReplayLog::load(&value()?)?),
+                "--theme" => {
+                    let value = value()?;
+                    options.theme = Theme::from_name(&value).ok_or_else(|| {
+                        SnekError::ConfigParse(format!(
+                            "--theme expects classic, neon or mono, got {}",
+                            value
+                        ))
+                    })?;
+                }
+                "--two-player" => options.two_player = parse_switch(&arg, &value()?)?,
+                "--obstacles" => options.obstacles = parse_switch(&arg, &value()?)?,
+                "--portals" => options.portals = parse_switch(&arg, &value()?)?,
+                "--background" => options.background.0 = Some(value()?),
+                "--music" => options.music.path = Some(value()?),
+                "--stats-file" => options.stats_file.0 = Some(value()?),
+                "--eat-sound" => options.sounds.eat = Some(value()?),
+                "--death-sound" => options.sounds.death = Some(value()?),
+                "--daily" => {
+                    let challenge = DailyChallenge::today();
+                    options.food_seed = challenge.seed();
+                    options.daily = Some(challenge);
+                }
+                "--food-seed" => {
+                    let value = value()?;
+                    let seed = value.parse().map_err(|_| {
+                        SnekError::ConfigParse(format!(
+                            "--food-seed expects a number, got {}",
+                            value
+                        ))
+                    })?;
+                    options.food_seed = FoodSeed(Some(seed));
+                }
+                "--width" => {
+                    options.arena.width = parse_arena_side(&arg, &value()?, MIN_ARENA_WIDTH)?
+                }
+                "--height" => {
+                    options.arena.height = parse_arena_side(&arg, &value()?, MIN_ARENA_HEIGHT)?
+                }
+                "--difficulty" => {
+                    let value = value()?;
+                    options.difficulty = match value.as_str() {
+                        "easy" => Difficulty::Easy,
+                        "medium" => Difficulty::Medium,
+                        "hard" => Difficulty::Hard,
+                        _ => {
+                            return Err(SnekError::ConfigParse(format!(
+                                "--difficulty expects easy, medium or hard, got {}",
+                                value
+                            )))
+                        }
+                    };
+                }
+                "--preset" => {
+                    let value = value()?;
+                    options.preset = match value.as_str() {
+                        "zen" => DifficultyPreset::Zen,
+                        "classic" => DifficultyPreset::Classic,
+                        "insane" => DifficultyPreset::Insane,
+                        _ => {
+                            return Err(SnekError::ConfigParse(format!(
+                                "--preset expects zen, classic or insane, got {}",
+                                value
+                            )))
+                        }
+                    };
+                }
+                "--fps-cap" => {
+                    let value = value()?;
+                    let fps = value.parse().ok().filter(|fps| *fps > 0).ok_or_else(|| {
+                        SnekError::ConfigParse(format!(
+                            "--fps-cap expects a positive number, got {}",
+                            value
+                        ))
+                    })?;
+                    settings.fps_cap = Some(fps);
+                }
+                _ => return Err(SnekError::ConfigParse(format!("unknown argument {}", arg))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Parses a side of the arena, which has to leave room for the starting snake.
+fn parse_arena_side(arg: &str, value: &str, min: u32) -> Result<u32, SnekError> {
+    value
+        .parse()
+        .ok()
+        .filter(|side| *side >= min)
+        .ok_or_else(|| {
+            SnekError::ConfigParse(format!(
+                "{} expects a number of cells no less than {}, got {}",
+                arg, min, value
+            ))
+        })
+}
+
+fn parse_switch(arg: &str, value: &str) -> Result<bool, SnekError> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        other => Err(SnekError::ConfigParse(format!(
+            "{} expects on or off, got {}",
+            arg, other
+        ))),
+    }
+}
+
+/// Loads a save file with `parse`, logging any error and falling back to the default.
+pub(crate) fn load_or_default<T: Default>(path: &str, parse: impl FnOnce(&str) -> Option<T>) -> T {
+    let loaded = read_save(path).and_then(|contents| match contents {
+        Some(contents) => parse(&contents)
+            .map(Some)
+            .ok_or_else(|| SnekError::ConfigParse(format!("{}: corrupt contents", path))),
+        None => Ok(None),
+    });
+    match loaded {
+        Ok(value) => value.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{}, starting fresh", e);
+            T::default()
+        }
+    }
+}
+
+/// Head positions of the current run, one per movement tick.
+#[derive(Default)]
+pub(crate) struct RunRecorder(pub(crate) Vec<Position>);
+
+/// The highest scoring run so far, replayed as a ghost on later runs.
+#[derive(Default)]
+pub(crate) struct BestRun {
+    pub(crate) score: u32,
+    pub(crate) path: Vec<Position>,
+    pub(crate) tick: usize,
+}
+
+impl BestRun {
+    /// Reads the best run from `path`, starting fresh if the file is missing or corrupt.
+    pub(crate) fn load(path: &str) -> Self {
+        load_or_default(path, Self::parse)
+    }
+
+    pub(crate) fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents.lines();
+        let score = lines.next()?.trim().parse().ok()?;
+        let path = lines
+            .map(|line| {
+                let mut coords = line.trim().split(',');
+                let x = coords.next()?.parse().ok()?;
+                let y = coords.next()?.parse().ok()?;
+                Some(Position { x, y })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            score,
+            path,
+            tick: 0,
+        })
+    }
+
+    pub(crate) fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut contents = format!("{}\n", self.score);
+        for pos in &self.path {
+            contents.push_str(&format!("{},{}\n", pos.x, pos.y));
+        }
+        std::fs::write(path, contents)
+    }
+}
+
+/// A game's food seed, every turn the snake took in it by the movement tick it was taken on,
+/// counting from 1, and the ticks food spawned on its timer after.
+///
+/// Played back with `--replay <file>`, it steers the snake through the same game again,
+/// spawning food after the same ticks however long the frames take. Only food and the turns
+/// are fixed by it: with enemies or anything else random turned on, the game can take another
+/// course.
+#[derive(Debug, Default, Clone)]
+pub struct ReplayLog {
+    pub seed: u64,
+    pub turns: Vec<(u64, Direction)>,
+    pub food: Vec<u64>,
+}
+
+impl ReplayLog {
+    /// Reads a log saved at the end of a game, failing if it is missing or corrupt.
+    pub fn load(path: &str) -> Result<Self, SnekError> {
+        read_save(path)?
+            .and_then(|contents| Self::parse(&contents))
+            .ok_or_else(|| SnekError::ConfigParse(format!("{}: not a replay", path)))
+    }
+
+    pub(crate) fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents.lines();
+        let mut log = Self {
+            seed: lines.next()?.trim().parse().ok()?,
+            ..Self::default()
+        };
+        for line in lines {
+            let mut fields = line.trim().split(',');
+            let tick = fields.next()?.parse().ok()?;
+            let dir = match fields.next()? {
+                "left" => Direction::Left,
+                "up" => Direction::Up,
+                "right" => Direction::Right,
+                "down" => Direction::Down,
+                "food" => {
+                    log.food.push(tick);
+                    continue;
+                }
+                _ => return None,
+            };
+            log.turns.push((tick, dir));
+        }
+        Some(log)
+    }
+
+    pub(crate) fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut contents = format!("{}\n", self.seed);
+        for (tick, dir) in &self.turns {
+            let dir = match dir {
+                Direction::Left => "left",
+                Direction::Up => "up",
+                Direction::Right => "right",
+                Direction::Down => "down",
+            };
+            contents.push_str(&format!("{},{}\n", tick, dir));
+        }
+        for tick in &self.food {
+            contents.push_str(&format!("{},food\n", tick));
+        }
+        std::fs::write(path, contents)
+    }
+
+    /// The turn taken on `tick`, if any.
+    pub(crate) fn turn_at(&self, tick: u64) -> Option<Direction> {
+        self.turns
+            .iter()
+            .find(|(turn_tick, _)| *turn_tick == tick)
+            .map(|(_, dir)| *dir)
+    }
+}
+
+/// The turns and timed food of the current game so far, saved as a [`ReplayLog`] when it is
+/// over.
+#[derive(Default)]
+pub(crate) struct ReplayRecorder {
+    pub(crate) tick: u64,
+    pub(crate) turns: Vec<(u64, Direction)>,
+    pub(crate) food: Vec<u64>,
+}
+
+/// The replay picked with `--replay <file>`, steering the snake in place of the keyboard.
+#[derive(Default)]
+pub struct LoadedReplay(pub Option<ReplayLog>);
+
+/// Coins earned across all runs, one per food eaten.
+#[derive(Default)]
+pub(crate) struct Wallet(pub(crate) u32);
+
+impl Wallet {
+    /// Reads the wallet from `path`, starting empty if the file is missing or corrupt.
+    pub(crate) fn load(path: &str) -> Self {
+        load_or_default(path, |contents| contents.trim().parse().ok().map(Self))
+    }
+
+    pub(crate) fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, format!("{}\n", self.0))
+    }
+}
+
+/// Themes bought in the menu's shop, saved next to the [`Wallet`]. [`Theme::Classic`] is
+/// always unlocked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnlockedThemes(pub(crate) Vec<Theme>);
+
+impl Default for UnlockedThemes {
+    fn default() -> Self {
+        Self(vec![Theme::Classic])
+    }
+}
+
+/// Why the shop turned a purchase down.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PurchaseError {
+    AlreadyUnlocked,
+    TooFewCoins { price: u32, coins: u32 },
+}
+
+impl UnlockedThemes {
+    /// Reads the unlocks from `path`, one theme name per line, keeping only the classic
+    /// theme if the file is missing or corrupt.
+    pub(crate) fn load(path: &str) -> Self {
+        load_or_default(path, |contents| {
+            let mut themes = contents
+                .lines()
+                .map(|line| Theme::from_name(line.trim()))
+                .collect::<Option<Vec<_>>>()?;
+            if !themes.contains(&Theme::Classic) {
+                themes.insert(0, Theme::Classic);
+            }
+            Some(Self(themes))
+        })
+    }
+
+    pub(crate) fn save(&self, path: &str) -> std::io::Result<()> {
+        let lines: String = self
+            .0
+            .iter()
+            .map(|theme| format!("{}\n", theme.name()))
+            .collect();
+        std::fs::write(path, lines)
+    }
+
+    pub(crate) fn contains(&self, theme: Theme) -> bool {
+        self.0.contains(&theme)
+    }
+
+    /// Pays the theme's price out of `wallet` and unlocks it, leaving both untouched if
+    /// it is already unlocked or the wallet holds too few coins.
+    pub(crate) fn buy(&mut self, theme: Theme, wallet: &mut Wallet) -> Result<(), PurchaseError> {
+        if self.contains(theme) {
+            return Err(PurchaseError::AlreadyUnlocked);
+        }
+        let price = theme.price();
+        if wallet.0 < price {
+            return Err(PurchaseError::TooFewCoins {
+                price,
+                coins: wallet.0,
+            });
+        }
+        wallet.0 -= price;
+        self.0.push(theme);
+        Ok(())
+    }
+
+    /// The unlocked theme that `T` switches to after `theme`.
+    pub(crate) fn next_after(&self, theme: Theme) -> Theme {
+        let mut next = theme.next();
+        while !self.contains(next) {
+            next = next.next();
+        }
+        next
+    }
+}
+
+/// Whether the tutorial was already played through, so later launches start right away.
+///
+/// Saved as a marker file once the player leaves the tutorial for the first time.
+#[derive(Default)]
+pub struct TutorialShown(pub bool);
+
+impl TutorialShown {
+    /// Reads the marker from `path`; a missing file means the tutorial is still due.
+    pub(crate) fn load(path: &str) -> Self {
+        load_or_default(path, |_| Some(Self(true)))
+    }
+
+    pub(crate) fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, "shown\n")
+    }
+}
+
+/// Whether the practice round of the tutorial is running.
+#[derive(Default)]
+pub(crate) struct Tutorial(pub(crate) bool);
+
+/// The patterns of the enemies spawned at the start of every game, one enemy per entry.
+#[derive(Default)]
+pub struct EnemySpawns(pub Vec<MovePattern>);
+
+/// Whether body segments spring after their cells instead of snapping to them, so the snake
+/// wiggles as it turns. Only the picture lags: collisions still use the cells.
+#[derive(Default)]
+pub struct Wobble(pub bool);
+
+/// Tuning of the spring behind [`Wobble`].
+pub struct WobbleSpring {
+    /// Pull toward the cell per pixel away from it, per second squared.
+    pub stiffness: f32,
+    /// Drag on the segment's speed, per second.
+    pub damping: f32,
+}
+
+impl Default for WobbleSpring {
+    fn default() -> Self {
+        Self {
+            stiffness: 300.0,
+            damping: 20.0,
+        }
+    }
+}
+
+/// When set, the borders turn into scrolling dashes whenever the head would wrap through
+/// them instead of dying.
+#[derive(Default)]
+pub struct WrapSeams(pub Option<WrapSeamStyle>);
+
+pub struct WrapSeamStyle {
+    pub color: Color,
+    /// How fast the dashes run along the edges, in cells per second.
+    pub speed: f32,
+}
+
+impl Default for WrapSeamStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::rgb(0.3, 0.6, 0.9),
+            speed: 1.0,
+        }
+    }
+}
+
+/// Whether to highlight the cell the head should move into next to reach the nearest food
+/// safely. `H` turns it on and off.
+#[derive(Default)]
+pub struct MoveHint(pub bool);
+
+/// Whether to tint a border red while the head is right next to it.
+#[derive(Default)]
+pub struct EdgeWarning(pub bool);
+
+/// Whether to draw an alternating background tile behind every cell.
+#[derive(Default)]
+pub struct Checkerboard(pub bool);
+
+/// The color of the faint lines drawn between cells, or `None` to leave them out.
+pub struct GridLines(pub Option<Color>);
+
+impl Default for GridLines {
+    fn default() -> Self {
+        Self(Some(Color::rgb(0.12, 0.12, 0.12)))
+    }
+}
+
+/// The two alternating shades of the checkerboard.
+pub struct CheckerboardShades(pub Color, pub Color);
+
+impl Default for CheckerboardShades {
+    fn default() -> Self {
+        Self(Color::rgb(0.06, 0.06, 0.06), Color::rgb(0.08, 0.08, 0.08))
+    }
+}
+
+/// How the head sprite is made to face its direction of travel.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum HeadStyle {
+    /// Rotate the transform, assuming the art faces right.
+    #[default]
+    Rotate,
+    /// Mirror horizontally when heading left and vertically when heading down.
+    Flip,
+    /// Pick the sprite sheet frame matching the `Direction` variant order.
+    Atlas,
+}
+
+/// Outline of the head, segment and food sprites within their cell.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum CellShape {
+    #[default]
+    Square,
+    Circle,
+}
+
+/// Colors of the snake, plain food and the background, picked with
+/// `--theme classic|neon|mono` and cycled with `T` while playing.
+///
+/// Only [`Theme::Classic`] is free; the others are bought with coins in the menu's shop
+/// and stay locked out of `--theme` and `T` until then.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Neon,
+    Mono,
+}
+
+/// What a [`Theme`] paints in.
+pub(crate) struct ThemeColors {
+    pub(crate) head: Color,
+    pub(crate) segment: Color,
+    pub(crate) food: Color,
+    pub(crate) background: Color,
+}
+
+impl Theme {
+    /// Every theme, in the order the shop lists them.
+    pub(crate) const ALL: [Theme; 3] = [Theme::Classic, Theme::Neon, Theme::Mono];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Neon => "neon",
+            Theme::Mono => "mono",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|theme| theme.name() == name)
+    }
+
+    /// Coins the shop asks for the theme.
+    pub(crate) fn price(self) -> u32 {
+        match self {
+            Theme::Classic => 0,
+            Theme::Neon => 40,
+            Theme::Mono => 25,
+        }
+    }
+
+    pub(crate) fn colors(self) -> ThemeColors {
+        match self {
+            Theme::Classic => ThemeColors {
+                head: Color::rgb(0.4, 0.2, 0.0),
+                segment: Color::rgb(0.0, 0.2, 0.4),
+                food: Color::rgb(1.0, 0.0, 0.0),
+                background: Color::rgb(0.04, 0.04, 0.04),
+            },
+            Theme::Neon => ThemeColors {
+                head: Color::rgb(1.0, 0.1, 0.8),
+                segment: Color::rgb(0.1, 0.9, 1.0),
+                food: Color::rgb(0.6, 1.0, 0.1),
+                background: Color::rgb(0.02, 0.0, 0.06),
+            },
+            Theme::Mono => ThemeColors {
+                head: Color::rgb(0.95, 0.95, 0.95),
+                segment: Color::rgb(0.55, 0.55, 0.55),
+                food: Color::rgb(0.8, 0.8, 0.8),
+                background: Color::rgb(0.04, 0.04, 0.04),
+            },
+        }
+    }
+
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Theme::Classic => Theme::Neon,
+            Theme::Neon => Theme::Mono,
+            Theme::Mono => Theme::Classic,
+        }
+    }
+}
+
+/// When set, only cells within this many steps (Manhattan distance) of the head are shown.
+///
+/// Everything else sits under fog and food there is hidden; it still collides as usual.
+#[derive(Default)]
+pub struct FogOfWar(pub Option<u32>);
+
+/// Radar pings under [`FogOfWar`]: `Tab` sends a ring out from the head that lifts the fog
+/// off the whole board for `reveal`, `pings` times per game.
+pub struct RadarConfig {
+    pub pings: u32,
+    pub reveal: Duration,
+}
+
+impl Default for RadarConfig {
+    fn default() -> Self {
+        Self {
+            pings: 3,
+            reveal: Duration::from_millis(1500),
+        }
+    }
+}
+
+/// Radar pings left this game.
+#[derive(Default)]
+pub(crate) struct RadarPings(pub(crate) u32);
+
+/// Time left on the current radar ping; the ring spreads over the first half of it.
+pub(crate) struct PingReveal(pub(crate) Timer);
+
+impl PingReveal {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.0.finished
+    }
+}
+
+impl Default for PingReveal {
+    fn default() -> Self {
+        Self(Timer {
+            finished: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Whether every segment shows a faint arrow pointing at the one ahead of it.
+#[derive(Default)]
+pub struct SegmentArrows(pub bool);
+
+/// Look of the tongue the head sticks out at food right in front of it.
+pub struct TongueConfig {
+    pub color: Color,
+    /// How far the tongue reaches past the head's cell, as a fraction of a cell.
+    pub length: f32,
+}
+
+impl Default for TongueConfig {
+    fn default() -> Self {
+        Self {
+            color: Color::rgb(0.85, 0.1, 0.3),
+            length: 0.4,
+        }
+    }
+}
+
+/// What part of the arena the camera shows.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Show the whole arena at once.
+    #[default]
+    FitAll,
+    /// Zoom in and smoothly follow the head, never showing past the arena edges.
+    FollowHead,
+}
+
+/// When set, the view of the arena spins at this many radians per second. Only the picture
+/// turns: Up still moves the snake toward the top of the grid.
+#[derive(Default)]
+pub struct RotateArena(pub Option<f32>);
+
+/// Tuning of [`CameraMode::FollowHead`].
+pub struct CameraFollow {
+    /// Fraction of the remaining distance to the head covered per second.
+    pub speed: f32,
+    /// Fraction of the arena visible at once, between 0 and 1.
+    pub zoom: f32,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            speed: 5.0,
+            zoom: 0.5,
+        }
+    }
+}
+
+/// How grid positions are projected onto the window.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Projection {
+    #[default]
+    TopDown,
+    Isometric,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_stats_serialize_to_one_json_line() {
+        let stats = GameStats {
+            length: 3,
+            score: 20,
+            duration: Duration::from_millis(1500),
+            foods_eaten: 2,
+        };
+        assert_eq!(
+            stats.to_json(),
+            r#"{"length":3,"score":20,"duration":1.5,"foods_eaten":2}"#
+        );
+    }
+    #[test]
+    fn arena_holds_cells_from_zero_up_to_its_size() {
+        let arena = ArenaSize {
+            width: 10,
+            height: 6,
+        };
+        assert!(arena.contains(Position { x: 0, y: 0 }));
+        assert!(arena.contains(Position { x: 9, y: 5 }));
+        assert!(!arena.contains(Position { x: -1, y: 0 }));
+        assert!(!arena.contains(Position { x: 10, y: 0 }));
+        assert!(!arena.contains(Position { x: 0, y: -1 }));
+        assert!(!arena.contains(Position { x: 0, y: 6 }));
+    }
+
+    #[test]
+    fn wrapping_comes_back_in_through_the_opposite_edge() {
+        let arena = ArenaSize {
+            width: 10,
+            height: 6,
+        };
+        assert_eq!(
+            arena.wrap(Position { x: -1, y: 2 }),
+            Position { x: 9, y: 2 }
+        );
+        assert_eq!(
+            arena.wrap(Position { x: 10, y: 2 }),
+            Position { x: 0, y: 2 }
+        );
+        assert_eq!(
+            arena.wrap(Position { x: 4, y: -1 }),
+            Position { x: 4, y: 5 }
+        );
+        assert_eq!(arena.wrap(Position { x: 4, y: 6 }), Position { x: 4, y: 0 });
+        assert!(arena.cells().all(|pos| arena.wrap(pos) == pos));
+    }
+
+    #[test]
+    fn key_file_falls_back_per_action() {
+        let path = std::env::temp_dir().join(format!("snek_keys_{}.toml", std::process::id()));
+        let contents = r#"
+            # steering on the home row
+            left = "J"
+            up = ["I", "Up"]
+            right = "F"
+            down = 5
+            jump = "K"
+        "#;
+        std::fs::write(&path, contents).unwrap();
+        let bindings = KeyBindings::load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        let defaults = KeyBindings::default();
+        assert_eq!(bindings.left, vec![KeyCode::J]);
+        assert_eq!(bindings.up, vec![KeyCode::I, KeyCode::Up]);
+        assert_eq!(bindings.right, defaults.right, "F is taken by smoothing");
+        assert_eq!(bindings.down, defaults.down);
+        assert_eq!(bindings.pause, defaults.pause);
+    }
+
+    #[test]
+    fn malformed_key_file_keeps_every_default() {
+        let path = std::env::temp_dir().join(format!("snek_bad_keys_{}.toml", std::process::id()));
+        std::fs::write(&path, "left = \"J\"\nup = [\"I\"").unwrap();
+        let bindings = KeyBindings::load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bindings.left, KeyBindings::default().left);
+    }
+
+    #[test]
+    fn player_one_keeps_their_keys_but_wasd() {
+        let loaded = KeyBindings {
+            left: vec![KeyCode::J, KeyCode::A],
+            up: vec![KeyCode::W],
+            pause: vec![KeyCode::P],
+            ..KeyBindings::default()
+        };
+        let bindings = loaded.without_keys_of(&KeyBindings::wasd());
+        assert_eq!(bindings.left, vec![KeyCode::J]);
+        assert_eq!(
+            bindings.up,
+            vec![KeyCode::Up],
+            "W alone goes back to the arrow"
+        );
+        assert_eq!(bindings.right, vec![KeyCode::Right]);
+        assert_eq!(bindings.down, vec![KeyCode::Down]);
+        assert_eq!(bindings.pause, vec![KeyCode::P]);
+        assert_eq!(bindings.restart, KeyBindings::default().restart);
+    }
+}
@@ -0,0 +1,294 @@
+//! Developer tuning aids, compiled in with the `dev` feature.
+
+use std::time::Duration;
+
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use super::spawn::hud_text;
+use crate::components::{Direction, Enemy, Layer, Position, Size, SnekHead, SnekSegment, Wall};
+use crate::resources::{ArenaSize, KeyBindings, SnekMoveTimer, SnekSegments};
+use crate::HUD_FONT_PATH;
+
+/// How far `+`/`-` move the tick interval per press.
+const TICK_STEP: Duration = Duration::from_millis(10);
+const FASTEST_TICK: Duration = Duration::from_millis(20);
+const SLOWEST_TICK: Duration = Duration::from_millis(1000);
+
+/// Milliseconds added to whatever interval the game itself picked, changed with `+`/`-`.
+#[derive(Default)]
+pub(crate) struct TickAdjust(pub(crate) i64);
+
+pub(crate) struct TickRateText;
+
+/// Frames from a steering key going down to the move that turned the snake, over all turns.
+#[derive(Default)]
+pub(crate) struct InputLatency {
+    pub(crate) frames: u64,
+    pub(crate) turns: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct LatencyProbe {
+    frame: u64,
+    pressed_at: Option<u64>,
+    direction: Option<Direction>,
+}
+
+pub(crate) struct LatencyText;
+
+/// Whether `F3` has the debug overlay on screen: frame rate, tick progress, snake length,
+/// head position and the input latency.
+#[derive(Default)]
+pub(crate) struct DebugOverlay(pub(crate) bool);
+
+pub(crate) struct DebugText;
+
+/// Whether `F4` has the danger heatmap over the board.
+#[derive(Default)]
+pub(crate) struct HeatmapDebug(pub(crate) bool);
+
+/// A cell of the danger heatmap, tinted through a material of its own.
+pub(crate) struct HeatmapTile;
+
+/// Steps from an obstacle beyond which a cell counts as safe on the heatmap.
+const HEATMAP_RANGE: i32 = 3;
+/// Right above the checkerboard, under everything played on.
+const HEATMAP_LAYER: Layer = Layer(2.0);
+
+pub(crate) fn spawn_tick_rate_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(HUD_FONT_PATH);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                left: Val::Px(10.0),
+                top: Val::Px(40.0),
+                ..Default::default()
+            },
+        ))
+        .with(TickRateText);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                left: Val::Px(10.0),
+                top: Val::Px(100.0),
+                ..Default::default()
+            },
+        ))
+        .with(LatencyText);
+    let mut overlay = hud_text(
+        font,
+        Rect {
+            left: Val::Px(10.0),
+            top: Val::Px(130.0),
+            ..Default::default()
+        },
+    );
+    overlay.draw.is_visible = false;
+    commands.spawn(overlay).with(DebugText);
+}
+
+/// Applies [`TickAdjust`] on top of `speed_ramp`, so it has to run after it.
+///
+/// Only the duration changes; the time elapsed toward the next tick is kept.
+pub(crate) fn tick_rate_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut adjust: ResMut<TickAdjust>,
+    mut snek_timer: ResMut<SnekMoveTimer>,
+) {
+    let step = TICK_STEP.as_millis() as i64;
+    if keyboard_input.just_pressed(KeyCode::Equals)
+        || keyboard_input.just_pressed(KeyCode::NumpadAdd)
+    {
+        adjust.0 += step;
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus)
+        || keyboard_input.just_pressed(KeyCode::NumpadSubtract)
+    {
+        adjust.0 -= step;
+    }
+    let (fastest, slowest) = (
+        FASTEST_TICK.as_millis() as i64,
+        SLOWEST_TICK.as_millis() as i64,
+    );
+    let base = (snek_timer.duration * 1000.).round() as i64;
+    // Keep the offset within reach of the bounds so reversing direction takes effect at once.
+    adjust.0 = adjust.0.clamp(fastest - base, slowest - base);
+    let duration = (base + adjust.0) as f32 / 1000.;
+    if snek_timer.duration != duration {
+        snek_timer.duration = duration;
+    }
+}
+
+/// Times each turn from its key press to the tick that applied it.
+///
+/// Runs after `snek_movement`, so a tick that didn't turn and left no turn queued means the
+/// press was refused, say as a reversal, and it is not counted.
+pub(crate) fn input_latency(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    snek_timer: Res<SnekMoveTimer>,
+    mut latency: ResMut<InputLatency>,
+    mut probe: Local<LatencyProbe>,
+    heads: Query<&SnekHead>,
+) {
+    probe.frame += 1;
+    if bindings
+        .keys()
+        .any(|(key, _)| keyboard_input.just_pressed(key))
+    {
+        probe.pressed_at = Some(probe.frame);
+    }
+    let head = match heads.iter().next() {
+        Some(head) => head,
+        None => return,
+    };
+    if !snek_timer.finished {
+        return;
+    }
+    let turned = probe.direction.is_some_and(|dir| dir != head.direction);
+    probe.direction = Some(head.direction);
+    if turned {
+        if let Some(pressed_at) = probe.pressed_at.take() {
+            latency.frames += probe.frame - pressed_at;
+            latency.turns += 1;
+        }
+    } else if head.turns.is_empty() {
+        probe.pressed_at = None;
+    }
+}
+
+pub(crate) fn latency_text(
+    (latency, overlay): (Res<InputLatency>, Res<DebugOverlay>),
+    mut texts: Query<With<LatencyText, &mut Text>>,
+) {
+    for mut text in texts.iter_mut() {
+        text.value = match (overlay.0, latency.turns) {
+            (false, _) => String::new(),
+            (true, 0) => "Input latency: no turns yet".to_string(),
+            (true, turns) => format!(
+                "Input latency: {:.1} frames over {} turns",
+                latency.frames as f64 / turns as f64,
+                turns
+            ),
+        };
+    }
+}
+
+/// Toggles the [`DebugOverlay`] on `F3` and fills it in every frame while it is on.
+///
+/// It is only hidden while off, so turning it back on is just a flag.
+pub(crate) fn debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    (diagnostics, snek_timer, segments): (Res<Diagnostics>, Res<SnekMoveTimer>, Res<SnekSegments>),
+    heads: Query<With<SnekHead, &Position>>,
+    mut texts: Query<With<DebugText, (&mut Text, &mut Draw)>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.0 = !overlay.0;
+    }
+    for (mut text, mut draw) in texts.iter_mut() {
+        draw.is_visible = overlay.0;
+        if !overlay.0 {
+            continue;
+        }
+        let fps = diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.average())
+            .unwrap_or_default();
+        let head = match heads.iter().next() {
+            Some(head) => format!("({}, {})", head.x, head.y),
+            None => "-".to_string(),
+        };
+        text.value = format!(
+            "FPS: {:.0}  Tick: {:.0}%  Length: {}  Head: {}",
+            fps,
+            100. * snek_timer.elapsed / snek_timer.duration,
+            segments.0.len() + 1,
+            head
+        );
+    }
+}
+
+pub(crate) fn spawn_heatmap(
+    mut commands: Commands,
+    arena: Res<ArenaSize>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for pos in arena.cells() {
+        commands
+            .spawn(SpriteComponents {
+                material: materials.add(Color::rgba(1.0, 0.0, 0.0, 0.0).into()),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(HeatmapTile)
+            .with(pos)
+            .with(Size::square(1.0))
+            .with(HEATMAP_LAYER);
+    }
+}
+
+/// Tints every cell redder the fewer steps it is from the body, a wall, an enemy or the
+/// arena edge, recomputed every tick while `F4` has it on.
+pub(crate) fn heatmap(
+    keyboard_input: Res<Input<KeyCode>>,
+    snek_timer: Res<SnekMoveTimer>,
+    arena: Res<ArenaSize>,
+    mut heatmap: ResMut<HeatmapDebug>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    segments: Query<With<SnekSegment, &Position>>,
+    walls: Query<With<Wall, &Position>>,
+    enemies: Query<With<Enemy, &Position>>,
+    mut tiles: Query<With<HeatmapTile, (&Position, &Handle<ColorMaterial>, &mut Draw)>>,
+) {
+    let toggled = keyboard_input.just_pressed(KeyCode::F4);
+    if toggled {
+        heatmap.0 = !heatmap.0;
+    }
+    let refresh = toggled || (heatmap.0 && snek_timer.finished);
+    if !refresh {
+        return;
+    }
+    let obstacles: Vec<Position> = segments
+        .iter()
+        .chain(walls.iter())
+        .chain(enemies.iter())
+        .copied()
+        .collect();
+    let (width, height) = (arena.width as i32, arena.height as i32);
+    for (pos, material, mut draw) in tiles.iter_mut() {
+        draw.is_visible = heatmap.0;
+        if !heatmap.0 {
+            continue;
+        }
+        // The nearest cell off the arena is straight out over the closest edge.
+        let edge = (pos.x + 1)
+            .min(pos.y + 1)
+            .min(width - pos.x)
+            .min(height - pos.y);
+        let nearest = obstacles
+            .iter()
+            .map(|other| (other.x - pos.x).abs() + (other.y - pos.y).abs())
+            .fold(edge, i32::min);
+        let danger = 1. - nearest.min(HEATMAP_RANGE + 1) as f32 / (HEATMAP_RANGE + 1) as f32;
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_a(0.6 * danger);
+        }
+    }
+}
+
+pub(crate) fn tick_rate_text(
+    snek_timer: Res<SnekMoveTimer>,
+    mut texts: Query<With<TickRateText, &mut Text>>,
+) {
+    for mut text in texts.iter_mut() {
+        text.value = format!("Tick: {:.0} ms", snek_timer.duration * 1000.);
+    }
+}
@@ -0,0 +1,389 @@
+use bevy::prelude::*;
+
+use super::spawn::{spawn_sparks, spawn_wall};
+use crate::components::{
+    Coin, Food, FoodCluster, FoodKind, Position, SnekHead, SnekSegment, SpawnedAt,
+};
+use crate::events::{GameOverEvent, GrowthEvent, Player, ScoreGained};
+use crate::resources::{
+    BonusFoodConfig, CellPadding, DoubleScore, DoubleScoreConfig, ExplosiveConfig, FoodDecay,
+    FoodSpawn, Invincible, InvincibleConfig, LastTailPosition, MagnetConfig, MagnetEffect,
+    Materials, PhaseConfig, PhaseSelf, PoisonConfig, ReverseConfig, ReversedControls, RunTime,
+    Score, Scoring, SlowConfig, SlowEffect, SnekMoveTimer, SnekSegments, WallBreakCharges,
+};
+
+pub(crate) fn snek_eating(
+    mut commands: Commands,
+    snek_timer: ResMut<SnekMoveTimer>,
+    mut growth_events: ResMut<Events<GrowthEvent>>,
+    (scoring, decay, run_time, food_spawn): (
+        Res<Scoring>,
+        Res<FoodDecay>,
+        Res<RunTime>,
+        Res<FoodSpawn>,
+    ),
+    (mut score, mut score_events): (ResMut<Score>, ResMut<Events<ScoreGained>>),
+    (magnet_config, mut magnet, mut wall_breaks): (
+        Res<MagnetConfig>,
+        ResMut<MagnetEffect>,
+        ResMut<WallBreakCharges>,
+    ),
+    (reverse, mut reversed): (Res<ReverseConfig>, ResMut<ReversedControls>),
+    (invincible_config, mut invincible, double_score_config, mut double_score): (
+        Res<InvincibleConfig>,
+        ResMut<Invincible>,
+        Res<DoubleScoreConfig>,
+        ResMut<DoubleScore>,
+    ),
+    (phase_config, mut phase, slow_config, mut slow): (
+        Res<PhaseConfig>,
+        ResMut<PhaseSelf>,
+        Res<SlowConfig>,
+        ResMut<SlowEffect>,
+    ),
+    (materials, padding, explosive, bonus): (
+        Res<Materials>,
+        Res<CellPadding>,
+        Res<ExplosiveConfig>,
+        Res<BonusFoodConfig>,
+    ),
+    (poison, mut segments, mut last_tail_position, mut game_over_events): (
+        Res<PoisonConfig>,
+        ResMut<SnekSegments>,
+        ResMut<LastTailPosition>,
+        ResMut<Events<GameOverEvent>>,
+    ),
+    food_positions: Query<
+        With<
+            Food,
+            (
+                Entity,
+                &Position,
+                &FoodKind,
+                &SpawnedAt,
+                Option<&FoodCluster>,
+            ),
+        >,
+    >,
+    head_positions: Query<With<SnekHead, &Position>>,
+    segment_positions: Query<With<SnekSegment, &Position>>,
+    coins: Query<(Entity, &Coin, &Position)>,
+) {
+    if !snek_timer.finished {
+        return;
+    }
+    for head_pos in head_positions.iter() {
+        for (entity, coin, coin_pos) in coins.iter() {
+            if coin_pos == head_pos {
+                commands.despawn(entity);
+                score.0 += coin.value;
+                score_events.send(ScoreGained(coin.value));
+            }
+        }
+        for (entity, food_pos, kind, spawned_at, cluster) in food_positions.iter() {
+            if food_pos != head_pos {
+                continue;
+            }
+            commands.despawn(entity);
+            let mut gained = 0;
+            if let (FoodSpawn::Cluster { bonus, .. }, Some(cluster)) = (*food_spawn, cluster) {
+                let rest = food_positions
+                    .iter()
+                    .any(|(other, _, _, _, other_cluster)| {
+                        other != entity && other_cluster == Some(cluster)
+                    });
+                if !rest {
+                    gained += bonus;
+                }
+            }
+            if *kind == FoodKind::Poison {
+                score.0 = (score.0 + gained).saturating_sub(poison.penalty);
+                if segments.0.len() <= poison.shrink {
+                    game_over_events.send(GameOverEvent(Player::One));
+                    continue;
+                }
+                let keep = segments.0.len() - poison.shrink;
+                for (i, segment) in segments.0.drain(keep..).enumerate() {
+                    // The first removed segment frees the cell right behind the new tail.
+                    if i == 0 {
+                        if let Ok(pos) = segment_positions.get(segment) {
+                            last_tail_position.0 = Some(*pos);
+                        }
+                    }
+                    commands.despawn(segment);
+                }
+                continue;
+            }
+            if *kind == FoodKind::Explosive {
+                let reach = explosive.radius as i32;
+                for (other, other_pos, ..) in food_positions.iter() {
+                    let (dx, dy) = (other_pos.x - food_pos.x, other_pos.y - food_pos.y);
+                    if other != entity && dx * dx + dy * dy <= reach * reach {
+                        commands.despawn(other);
+                        gained += explosive.bonus;
+                    }
+                }
+                spawn_sparks(&mut commands, &materials, &padding, *food_pos);
+            }
+            if *kind == FoodKind::Split {
+                // The front half, rounded up, stays the snake and the rest turns to walls
+                // where it lies, in place of the usual growth.
+                let keep = segments.0.len().div_ceil(2);
+                for segment in segments.0.drain(keep..) {
+                    if let Ok(pos) = segment_positions.get(segment) {
+                        spawn_wall(&mut commands, &materials, &padding, *pos);
+                    }
+                    commands.despawn(segment);
+                }
+            } else {
+                growth_events.send(GrowthEvent {
+                    segments: match *kind {
+                        FoodKind::Mega { growth } => growth,
+                        FoodKind::Bonus => bonus.growth,
+                        _ => 1,
+                    },
+                });
+            }
+            let value = match &decay.0 {
+                Some(decay) => {
+                    decay.value(run_time.0.checked_sub(spawned_at.0).unwrap_or_default())
+                }
+                None => scoring.per_food,
+            };
+            gained += if *kind == FoodKind::Normal && double_score.is_active() {
+                value * double_score_config.multiplier
+            } else {
+                value
+            };
+            score.0 += gained;
+            score_events.send(ScoreGained(gained));
+            match kind {
+                FoodKind::Magnet => magnet.0 = Timer::new(magnet_config.duration, false),
+                FoodKind::WallBreak => wall_breaks.0 += 1,
+                FoodKind::Reverse => reversed.0 = Timer::new(reverse.duration, false),
+                FoodKind::Invincible => {
+                    invincible.0 = Timer::new(invincible_config.duration, false)
+                }
+                FoodKind::DoubleScore => {
+                    double_score.0 = Timer::new(double_score_config.duration, false)
+                }
+                FoodKind::Phase => phase.0 = Timer::new(phase_config.duration, false),
+                FoodKind::Slow => slow.extend(slow_config.duration),
+                _ => {}
+            }
+        }
+    }
+}
+
+pub(crate) fn tick_scoring(
+    snek_timer: Res<SnekMoveTimer>,
+    scoring: Res<Scoring>,
+    mut score: ResMut<Score>,
+) {
+    if snek_timer.finished {
+        score.0 += scoring.per_tick;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use crate::components::{Coin, FoodKind, Position};
+    use crate::events::GameOverEvent;
+    use crate::resources::{
+        DoubleScoreConfig, ExplosiveConfig, FoodDecay, FoodValueDecay, PoisonConfig, RunTime,
+        Score, Scoring, SnekMoveTimer,
+    };
+    use crate::testing::{
+        body, food, head, place_food, quiet_app, run_tick, run_tick_counting, run_ticks, spawn_now,
+        walls,
+    };
+
+    #[test]
+    fn food_is_eaten_in_the_frame_the_head_steps_onto_it() {
+        let mut app = quiet_app(|_| ());
+        let ahead = Position { x: 3, y: 4 };
+        place_food(&mut app, FoodKind::Normal, ahead);
+        loop {
+            app.update();
+            let moved = app.resources.get::<SnekMoveTimer>().unwrap().finished;
+            let score = app.resources.get::<Score>().unwrap().0;
+            if !moved {
+                // Until the head gets there, the food stays put.
+                assert_ne!(head(&app).0, ahead);
+                assert_eq!(food(&app).len(), 1);
+                assert_eq!(score, 0);
+                continue;
+            }
+            assert_eq!(head(&app).0, ahead);
+            assert!(food(&app).is_empty());
+            assert_eq!(score, 1);
+            break;
+        }
+    }
+
+    #[test]
+    fn poison_takes_segments_and_points_and_kills_a_short_snake() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(PoisonConfig {
+                shrink: 2,
+                penalty: 1,
+                spawn_chance: 0.,
+            })
+        });
+        for y in 4..=6 {
+            place_food(&mut app, FoodKind::Normal, Position { x: 3, y });
+        }
+        run_ticks(&mut app, 3);
+        assert_eq!(body(&app).len(), 4);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 3);
+
+        place_food(&mut app, FoodKind::Poison, Position { x: 3, y: 7 });
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 0);
+        assert_eq!(body(&app).len(), 2);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 2);
+
+        // Two more would leave nothing behind the head.
+        place_food(&mut app, FoodKind::Poison, Position { x: 3, y: 8 });
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 1);
+    }
+
+    #[test]
+    fn decayed_food_is_worth_max_fresh_and_min_once_stale() {
+        let decay = FoodValueDecay {
+            max: 5,
+            min: 1,
+            window: Duration::from_secs(10),
+        };
+        let mut app = quiet_app(|resources| resources.insert(FoodDecay(Some(decay))));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        run_tick(&mut app);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 5);
+
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        app.resources.get_mut::<RunTime>().unwrap().0 += Duration::from_secs(10);
+        run_tick(&mut app);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 6);
+    }
+
+    #[test]
+    fn normal_food_scores_multiplied_while_the_aura_lasts() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(DoubleScoreConfig {
+                duration: Duration::from_millis(600),
+                multiplier: 3,
+                spawn_chance: 0.,
+            })
+        });
+        let ahead = |y| Position { x: 3, y };
+        place_food(&mut app, FoodKind::Normal, ahead(4));
+        place_food(&mut app, FoodKind::DoubleScore, ahead(5));
+        place_food(&mut app, FoodKind::Normal, ahead(6));
+        // Four ticks after the aura food, the aura has run out.
+        place_food(&mut app, FoodKind::Normal, ahead(9));
+        let mut scores = Vec::new();
+        for _ in 0..6 {
+            run_tick(&mut app);
+            scores.push(app.resources.get::<Score>().unwrap().0);
+        }
+        assert_eq!(scores, vec![1, 2, 5, 5, 5, 6]);
+    }
+
+    #[test]
+    fn passing_over_a_coin_scores_it_and_picks_it_up() {
+        let mut app = quiet_app(|_| ());
+        spawn_now(&mut app, |commands, _| {
+            commands.spawn((
+                Coin {
+                    value: 5,
+                    lifetime: Timer::from_seconds(10., false),
+                },
+                Position { x: 3, y: 4 },
+            ));
+        });
+        let coins = |app: &App| app.world.query::<&Coin>().count();
+        assert_eq!(coins(&app), 1);
+        run_tick(&mut app);
+        app.update();
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 5);
+        assert_eq!(coins(&app), 0);
+        // Coins don't feed the snake.
+        assert_eq!(body(&app).len(), 1);
+        run_tick(&mut app);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 5);
+    }
+
+    #[test]
+    fn split_food_leaves_the_back_half_behind_as_walls() {
+        let mut app = quiet_app(|_| ());
+        for y in 4..=6 {
+            place_food(&mut app, FoodKind::Normal, Position { x: 3, y });
+        }
+        place_food(&mut app, FoodKind::Split, Position { x: 3, y: 7 });
+        run_ticks(&mut app, 3);
+        assert_eq!(body(&app).len(), 4);
+        run_tick(&mut app);
+        app.update();
+        let cell = |y| Position { x: 3, y };
+        assert_eq!(body(&app), vec![cell(6), cell(5)]);
+        let mut walls = walls(&app);
+        walls.sort_by_key(|pos| pos.y);
+        assert_eq!(walls, vec![cell(3), cell(4)]);
+    }
+
+    #[test]
+    fn explosive_food_clears_the_food_within_its_radius() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(ExplosiveConfig {
+                radius: 3,
+                bonus: 2,
+                spawn_chance: 0.,
+            })
+        });
+        place_food(&mut app, FoodKind::Explosive, Position { x: 3, y: 4 });
+        // Three right on or inside the radius, and two outside of it.
+        for &(x, y) in &[(5, 6), (3, 7), (6, 4), (6, 6), (9, 9)] {
+            place_food(&mut app, FoodKind::Normal, Position { x, y });
+        }
+        run_tick(&mut app);
+        app.update();
+        let mut left: Vec<Position> = food(&app).into_iter().map(|(pos, _)| pos).collect();
+        left.sort_by_key(|pos| pos.x);
+        assert_eq!(left, vec![Position { x: 6, y: 6 }, Position { x: 9, y: 9 }]);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 1 + 3 * 2);
+    }
+
+    #[test]
+    fn surviving_a_tick_scores_per_tick() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(Scoring {
+                per_tick: 1,
+                ..Default::default()
+            })
+        });
+        let length = body(&app).len();
+        for tick in 1..=5 {
+            run_ticks(&mut app, 1);
+            assert_eq!(app.resources.get::<Score>().unwrap().0, tick);
+        }
+        // Nothing was eaten on the way.
+        assert_eq!(body(&app).len(), length);
+    }
+
+    #[test]
+    fn mega_food_grows_all_its_segments_in_one_tick() {
+        let mut app = quiet_app(|_| ());
+        place_food(
+            &mut app,
+            FoodKind::Mega { growth: 5 },
+            Position { x: 3, y: 4 },
+        );
+        let length = body(&app).len();
+        run_tick(&mut app);
+        assert!(food(&app).is_empty());
+        assert_eq!(body(&app).len(), length + 5);
+    }
+}
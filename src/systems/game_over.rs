@@ -0,0 +1,798 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use bevy::prelude::*;
+
+use super::hooks::board_snapshot;
+use super::spawn::{hud_text, spawn_food, spawn_initial_snake, spawn_segment};
+use crate::components::{
+    Enemy, Food, FoodKind, GameOverText, Position, Projectile, ReplaySprite, Size, SnekHead,
+    SnekSegment, Wall,
+};
+use crate::daily::ActiveDaily;
+use crate::events::{GameOverEvent, GrowthEvent, LifeLost, Player, ResetGame, Rewound};
+use crate::resources::{
+    BaseSpeed, BestRun, BodySpacing, Boost, CellPadding, DeathPlayback, DeathReplay, DoubleScore,
+    FoodChain, FoodRng, FoodSeed, FoodSpawnTimer, GameOverScreen, GameState, GameStats, Invincible,
+    KeyBindings, LastTailPosition, Level, Lives, LivesLeft, MagnetEffect, Materials, PaintMode,
+    Paused, PendingGrowth, PhaseSelf, Playback, QuitPrompt, RecentTicks, ReversedControls,
+    RewindHistory, RewindOnDeath, RewindPoint, RunRecorder, RunTime, Score, SlowEffect,
+    SnekMoveTimer, SnekPath, SnekSegments, StatsFile, TimeAttack, TimeUp, Tutorial, TwoPlayer,
+    WallBreakCharges, Winner,
+};
+use crate::HUD_FONT_PATH;
+
+pub(crate) fn game_over(
+    mut reader: Local<EventReader<GameOverEvent>>,
+    game_over_events: Res<Events<GameOverEvent>>,
+    snek_timer: Res<SnekMoveTimer>,
+    (replay, mut recent, mut playback, mut paused): (
+        Res<DeathReplay>,
+        ResMut<RecentTicks>,
+        ResMut<DeathPlayback>,
+        ResMut<Paused>,
+    ),
+    (mut invincible, mut double_score, mut phase, mut slow): (
+        ResMut<Invincible>,
+        ResMut<DoubleScore>,
+        ResMut<PhaseSelf>,
+        ResMut<SlowEffect>,
+    ),
+    (tutorial, paint, mut lives_left, mut over): (
+        Res<Tutorial>,
+        Res<PaintMode>,
+        ResMut<LivesLeft>,
+        ResMut<GameOverScreen>,
+    ),
+    (rewind, mut history, mut rewound_events): (
+        Res<RewindOnDeath>,
+        ResMut<RewindHistory>,
+        ResMut<Events<Rewound>>,
+    ),
+    (two_player, mut winner): (Res<TwoPlayer>, ResMut<Winner>),
+    mut life_lost_events: ResMut<Events<LifeLost>>,
+    mut reset_events: ResMut<Events<ResetGame>>,
+) {
+    // Several collisions in the same tick still cost a single life.
+    let crashed: Vec<Player> = reader
+        .iter(&game_over_events)
+        .map(|event| event.0)
+        .collect();
+    if crashed.is_empty() || paint.0 {
+        return;
+    }
+    *invincible = Invincible::default();
+    *double_score = DoubleScore::default();
+    *phase = PhaseSelf::default();
+    *slow = SlowEffect::default();
+    if tutorial.0 {
+        // Practice is free: start it over without a replay or losing a life.
+        reset_events.send(ResetGame);
+        return;
+    }
+    if two_player.0.is_some() {
+        // A round is over with the first crash, without lives, rewinds or replays.
+        winner.0 = [Player::One, Player::Two]
+            .iter()
+            .copied()
+            .find(|player| !crashed.contains(player));
+        over.0 = true;
+        paused.0 = true;
+        return;
+    }
+    if let Some(config) = &rewind.0 {
+        let left = config.per_game.is_none_or(|limit| history.used < limit);
+        if left && !history.points.is_empty() {
+            history.used += 1;
+            rewound_events.send(Rewound);
+            return;
+        }
+    }
+    if let Some(config) = &replay.0 {
+        if !recent.0.is_empty() {
+            // `death_replay` ends the attempt once the last frame has been shown.
+            paused.0 = true;
+            playback.0 = Some(Playback {
+                frames: recent.0.drain(..).collect(),
+                timer: Timer::from_seconds(snek_timer.duration * config.slowdown, true),
+            });
+            return;
+        }
+    }
+    end_attempt(
+        &mut lives_left,
+        &mut life_lost_events,
+        &mut over,
+        &mut paused,
+    );
+}
+
+/// Costs a life, or ends the game when it was the last one.
+fn end_attempt(
+    lives_left: &mut LivesLeft,
+    life_lost_events: &mut Events<LifeLost>,
+    over: &mut GameOverScreen,
+    paused: &mut Paused,
+) {
+    if lives_left.0 > 1 {
+        lives_left.0 -= 1;
+        life_lost_events.send(LifeLost);
+    } else {
+        // `game_over_screen` waits for the restart.
+        over.0 = true;
+        paused.0 = true;
+    }
+}
+
+pub(crate) fn spawn_game_over_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(hud_text(
+            asset_server.load(HUD_FONT_PATH),
+            Rect {
+                left: Val::Percent(25.0),
+                top: Val::Percent(45.0),
+                ..Default::default()
+            },
+        ))
+        .with(GameOverText);
+}
+
+/// Shows the game over notice while the last life is gone, or who won a [`TwoPlayer`] round,
+/// and starts over on `Enter`.
+pub(crate) fn game_over_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    (over, prompt): (Res<GameOverScreen>, Res<QuitPrompt>),
+    (two_player, winner): (Res<TwoPlayer>, Res<Winner>),
+    mut reset_events: ResMut<Events<ResetGame>>,
+    mut texts: Query<With<GameOverText, &mut Text>>,
+) {
+    if over.0 && !prompt.open && keyboard_input.just_pressed(KeyCode::Return) {
+        reset_events.send(ResetGame);
+    }
+    for mut text in texts.iter_mut() {
+        let notice = match winner.0 {
+            _ if two_player.0.is_none() => "GAME OVER",
+            Some(Player::One) => "PLAYER 1 WINS",
+            Some(Player::Two) => "PLAYER 2 WINS",
+            None => "DRAW",
+        };
+        text.value = if over.0 && !prompt.open {
+            format!("{} - press Enter to restart", notice)
+        } else {
+            String::new()
+        };
+    }
+}
+
+pub(crate) fn record_recent_ticks(
+    snek_timer: Res<SnekMoveTimer>,
+    (replay, score, segments): (Res<DeathReplay>, Res<Score>, Res<SnekSegments>),
+    mut recent: ResMut<RecentTicks>,
+    heads: Query<With<SnekHead, &Position>>,
+    positions: Query<With<SnekSegment, &Position>>,
+    food: Query<With<Food, &Position>>,
+) {
+    let config = match &replay.0 {
+        Some(config) => config,
+        None => return,
+    };
+    if !snek_timer.finished {
+        return;
+    }
+    recent
+        .0
+        .push_back(board_snapshot(&score, &segments, &heads, &positions, &food));
+    while recent.0.len() > config.ticks {
+        recent.0.pop_front();
+    }
+}
+
+/// Under [`RewindOnDeath`], remembers the board after every tick the snake survived.
+pub(crate) fn record_rewind_points(
+    snek_timer: Res<SnekMoveTimer>,
+    (rewind, mut history, segments, path): (
+        Res<RewindOnDeath>,
+        ResMut<RewindHistory>,
+        Res<SnekSegments>,
+        Res<SnekPath>,
+    ),
+    (game_over_events, mut game_over_reader): (
+        Res<Events<GameOverEvent>>,
+        Local<EventReader<GameOverEvent>>,
+    ),
+    (life_lost_events, mut life_lost_reader): (Res<Events<LifeLost>>, Local<EventReader<LifeLost>>),
+    (reset_events, mut reset_reader): (Res<Events<ResetGame>>, Local<EventReader<ResetGame>>),
+    heads: Query<(&SnekHead, &Position)>,
+    positions: Query<With<SnekSegment, &Position>>,
+    food: Query<With<Food, (&Position, &FoodKind)>>,
+) {
+    let config = match &rewind.0 {
+        Some(config) => config,
+        None => return,
+    };
+    if reset_reader.iter(&reset_events).next().is_some() {
+        *history = RewindHistory::default();
+    }
+    if life_lost_reader.iter(&life_lost_events).next().is_some() {
+        history.points.clear();
+    }
+    // The board a tick left the snake dead on is no place to go back to.
+    let died = game_over_reader.iter(&game_over_events).next().is_some();
+    if !snek_timer.finished || died {
+        return;
+    }
+    let (head, pos) = match heads.iter().next() {
+        Some(head) => head,
+        None => return,
+    };
+    history.points.push_back(RewindPoint {
+        head: *pos,
+        direction: head.direction,
+        segments: segments
+            .0
+            .iter()
+            .filter_map(|entity| positions.get(*entity).ok().copied())
+            .collect(),
+        path: path.0.clone(),
+        food: food.iter().map(|(pos, kind)| (*pos, *kind)).collect(),
+    });
+    while history.points.len() > config.ticks.max(1) {
+        history.points.pop_front();
+    }
+}
+
+/// Puts the snake and food back the way they were at the oldest [`RewindHistory`] point,
+/// which stays the only one so that dying again right away goes back to the same spot.
+pub(crate) fn rewind_board(
+    mut commands: Commands,
+    mut reader: Local<EventReader<Rewound>>,
+    rewound_events: Res<Events<Rewound>>,
+    (materials, padding, run_time): (Res<Materials>, Res<CellPadding>, Res<RunTime>),
+    (mut history, mut segment_res, mut path, mut last_tail_position): (
+        ResMut<RewindHistory>,
+        ResMut<SnekSegments>,
+        ResMut<SnekPath>,
+        ResMut<LastTailPosition>,
+    ),
+    mut heads: Query<(&mut SnekHead, &mut Position)>,
+    segments: Query<With<SnekSegment, Entity>>,
+    food: Query<With<Food, Entity>>,
+) {
+    if reader.iter(&rewound_events).next().is_none() {
+        return;
+    }
+    history.points.truncate(1);
+    let point = match history.points.front() {
+        Some(point) => point,
+        None => return,
+    };
+    for (mut head, mut pos) in heads.iter_mut() {
+        *pos = point.head;
+        head.direction = point.direction;
+        head.turns.clear();
+    }
+    for entity in segments.iter() {
+        commands.despawn(entity);
+    }
+    segment_res.0 = point
+        .segments
+        .iter()
+        .map(|pos| spawn_segment(&mut commands, &materials.segment_material, &padding, *pos))
+        .collect();
+    path.0 = point.path.clone();
+    *last_tail_position = LastTailPosition::default();
+    for entity in food.iter() {
+        commands.despawn(entity);
+    }
+    for &(pos, kind) in point.food.iter() {
+        spawn_food(&mut commands, &materials, &padding, kind, pos, &run_time);
+    }
+}
+
+/// Steps through the recorded frames in place of the hidden board, then ends the attempt.
+pub(crate) fn death_replay(
+    mut commands: Commands,
+    time: Res<Time>,
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    (mut paused, mut playback): (ResMut<Paused>, ResMut<DeathPlayback>),
+    (mut lives_left, mut life_lost_events, mut over): (
+        ResMut<LivesLeft>,
+        ResMut<Events<LifeLost>>,
+        ResMut<GameOverScreen>,
+    ),
+    shown: Query<With<ReplaySprite, Entity>>,
+    mut board: Query<(
+        &mut Draw,
+        Option<&SnekHead>,
+        Option<&SnekSegment>,
+        Option<&Food>,
+    )>,
+) {
+    let replay = match playback.0.as_mut() {
+        Some(replay) => replay,
+        None => return,
+    };
+    replay.timer.tick(time.delta_seconds);
+    let started = shown.iter().next().is_some();
+    if started && !replay.timer.just_finished {
+        return;
+    }
+    for entity in shown.iter() {
+        commands.despawn(entity);
+    }
+    let replaying = match replay.frames.pop_front() {
+        Some(frame) => {
+            let sprites = frame
+                .head
+                .map(|pos| (pos, &materials.head_material, padding.head))
+                .into_iter()
+                .chain(
+                    frame
+                        .segments
+                        .iter()
+                        .map(|pos| (*pos, &materials.segment_material, padding.segment)),
+                )
+                .chain(
+                    frame
+                        .food
+                        .iter()
+                        .map(|pos| (*pos, &materials.food_material, padding.food)),
+                );
+            for (pos, material, size) in sprites {
+                commands
+                    .spawn(SpriteComponents {
+                        material: material.clone(),
+                        ..Default::default()
+                    })
+                    .with(ReplaySprite)
+                    .with(pos)
+                    .with(Size::square(size));
+            }
+            true
+        }
+        None => {
+            playback.0 = None;
+            paused.0 = false;
+            end_attempt(
+                &mut lives_left,
+                &mut life_lost_events,
+                &mut over,
+                &mut paused,
+            );
+            false
+        }
+    };
+    for (mut draw, head, segment, food) in board.iter_mut() {
+        if head.is_some() || segment.is_some() || food.is_some() {
+            draw.is_visible = !replaying;
+        }
+    }
+}
+
+/// Puts a fresh snake back at the start after a lost life, leaving the rest of the game as is.
+pub(crate) fn respawn_snake(
+    mut commands: Commands,
+    mut reader: Local<EventReader<LifeLost>>,
+    life_lost_events: Res<Events<LifeLost>>,
+    (materials, padding, spacing): (Res<Materials>, Res<CellPadding>, Res<BodySpacing>),
+    (mut last_tail_position, mut pending_growth): (ResMut<LastTailPosition>, ResMut<PendingGrowth>),
+    (segment_res, mut path): (ResMut<SnekSegments>, ResMut<SnekPath>),
+    mut recent: ResMut<RecentTicks>,
+    segments: Query<(Entity, &SnekSegment)>,
+    heads: Query<(Entity, &SnekHead)>,
+    walls: Query<(Entity, &Wall)>,
+) {
+    if reader.iter(&life_lost_events).next().is_none() {
+        return;
+    }
+    recent.0.clear();
+    for (ent, _) in segments.iter() {
+        commands.despawn(ent);
+    }
+    for (ent, _) in heads.iter() {
+        commands.despawn(ent);
+    }
+    // The fresh snake would spawn right into its old trail otherwise.
+    for (ent, _) in walls.iter() {
+        commands.despawn(ent);
+    }
+    *last_tail_position = LastTailPosition::default();
+    *pending_growth = PendingGrowth::default();
+    spawn_initial_snake(
+        commands,
+        &materials,
+        &padding,
+        &spacing,
+        &mut path,
+        segment_res,
+    );
+}
+
+pub(crate) fn time_attack(
+    (limit, run_time): (Res<TimeAttack>, Res<RunTime>),
+    (mut time_up, mut paused): (ResMut<TimeUp>, ResMut<Paused>),
+) {
+    if let Some(limit) = limit.0 {
+        if !time_up.0 && run_time.0 >= limit {
+            time_up.0 = true;
+            paused.0 = true;
+        }
+    }
+}
+
+/// Prints a result line for the [`DailyChallenge`](crate::DailyChallenge) whenever one of
+/// its runs ends, to paste wherever players compare scores.
+pub(crate) fn daily_result(
+    mut reader: Local<EventReader<ResetGame>>,
+    reset_events: Res<Events<ResetGame>>,
+    (daily, score, segments): (Res<ActiveDaily>, Res<Score>, Res<SnekSegments>),
+) {
+    let challenge = match &daily.0 {
+        Some(challenge) => challenge,
+        None => return,
+    };
+    if reader.iter(&reset_events).next().is_some() && score.0 > 0 {
+        println!("{}", challenge.share_line(score.0, segments.0.len() + 1));
+    }
+}
+
+/// Keeps [`GameStats`] up to date and prints them as JSON once the game is over, appending
+/// them to the [`StatsFile`] too if there is one.
+pub(crate) fn game_stats(
+    (growth_events, mut growth_reader): (Res<Events<GrowthEvent>>, Local<EventReader<GrowthEvent>>),
+    (reset_events, mut reset_reader): (Res<Events<ResetGame>>, Local<EventReader<ResetGame>>),
+    (over, score, segments, run_time): (
+        Res<GameOverScreen>,
+        Res<Score>,
+        Res<SnekSegments>,
+        Res<RunTime>,
+    ),
+    (mut was_over, stats_file): (Local<bool>, Res<StatsFile>),
+    mut stats: ResMut<GameStats>,
+) {
+    if reset_reader.iter(&reset_events).next().is_some() {
+        *stats = GameStats::default();
+    }
+    stats.foods_eaten += growth_reader.iter(&growth_events).count() as u32;
+    // The board is frozen under the game over notice, so these stay as they were at the end.
+    stats.length = segments.0.len() + 1;
+    stats.score = score.0;
+    stats.duration = run_time.0;
+    if over.0 && !*was_over {
+        let json = stats.to_json();
+        println!("{}", json);
+        if let Some(path) = &stats_file.0 {
+            if let Err(e) = append_line(path, &json) {
+                eprintln!("failed to save stats to {}: {}", path, e);
+            }
+        }
+    }
+    *was_over = over.0;
+}
+
+/// Adds `line` to the end of the file at `path`, creating it first if need be.
+fn append_line(path: &str, line: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+pub(crate) fn restart_hotkey(
+    (keyboard_input, bindings): (Res<Input<KeyCode>>, Res<KeyBindings>),
+    state: Res<GameState>,
+    mut reset_events: ResMut<Events<ResetGame>>,
+) {
+    let pressed = bindings
+        .restart
+        .iter()
+        .any(|key| keyboard_input.just_pressed(*key));
+    // There is nothing to restart before the menu starts the first game.
+    if *state == GameState::Running && pressed {
+        reset_events.send(ResetGame);
+    }
+}
+
+/// Despawns every gameplay entity, resets all per-run state and respawns the initial snake.
+pub(crate) fn reset_game(
+    mut commands: Commands,
+    mut reader: Local<EventReader<ResetGame>>,
+    reset_events: Res<Events<ResetGame>>,
+    (materials, padding, spacing): (Res<Materials>, Res<CellPadding>, Res<BodySpacing>),
+    (lives, mut lives_left, chain, mut level): (
+        Res<Lives>,
+        ResMut<LivesLeft>,
+        Res<FoodChain>,
+        ResMut<Level>,
+    ),
+    (food_seed, mut food_rng): (Res<FoodSeed>, ResMut<FoodRng>),
+    (mut paused, mut recent, mut playback, mut time_up, mut over): (
+        ResMut<Paused>,
+        ResMut<RecentTicks>,
+        ResMut<DeathPlayback>,
+        ResMut<TimeUp>,
+        ResMut<GameOverScreen>,
+    ),
+    (
+        mut score,
+        mut run_time,
+        base,
+        mut snek_timer,
+        mut food_timer,
+        mut last_tail_position,
+        mut pending_growth,
+    ): (
+        ResMut<Score>,
+        ResMut<RunTime>,
+        Res<BaseSpeed>,
+        ResMut<SnekMoveTimer>,
+        ResMut<FoodSpawnTimer>,
+        ResMut<LastTailPosition>,
+        ResMut<PendingGrowth>,
+    ),
+    (
+        mut magnet,
+        mut reversed,
+        mut invincible,
+        mut double_score,
+        mut phase,
+        mut slow,
+        mut boost,
+        mut wall_breaks,
+    ): (
+        ResMut<MagnetEffect>,
+        ResMut<ReversedControls>,
+        ResMut<Invincible>,
+        ResMut<DoubleScore>,
+        ResMut<PhaseSelf>,
+        ResMut<SlowEffect>,
+        ResMut<Boost>,
+        ResMut<WallBreakCharges>,
+    ),
+    (mut recorder, mut best): (ResMut<RunRecorder>, ResMut<BestRun>),
+    (segment_res, mut path): (ResMut<SnekSegments>, ResMut<SnekPath>),
+    segments: Query<(Entity, &SnekSegment)>,
+    food: Query<(Entity, &Food)>,
+    heads: Query<(Entity, &SnekHead)>,
+    enemies: Query<(Entity, &Enemy)>,
+    walls: Query<(Entity, &Wall)>,
+    // Systems take at most six separate queries.
+    leftovers: QuerySet<(
+        Query<With<ReplaySprite, Entity>>,
+        Query<With<Projectile, Entity>>,
+    )>,
+) {
+    if reader.iter(&reset_events).next().is_none() {
+        return;
+    }
+    // Restarting in the middle of a death replay cuts it short.
+    if playback.0.take().is_some() {
+        paused.0 = false;
+    }
+    if time_up.0 {
+        time_up.0 = false;
+        paused.0 = false;
+    }
+    if over.0 {
+        over.0 = false;
+        paused.0 = false;
+    }
+    recent.0.clear();
+    for ent in leftovers.q0().iter() {
+        commands.despawn(ent);
+    }
+    // A shot still in flight would break a wall of the new game.
+    for ent in leftovers.q1().iter() {
+        commands.despawn(ent);
+    }
+    for (ent, _) in segments.iter() {
+        commands.despawn(ent);
+    }
+    for (ent, _) in food.iter() {
+        commands.despawn(ent);
+    }
+    for (ent, _) in heads.iter() {
+        commands.despawn(ent);
+    }
+    for (ent, _) in enemies.iter() {
+        commands.despawn(ent);
+    }
+    for (ent, _) in walls.iter() {
+        commands.despawn(ent);
+    }
+    lives_left.0 = lives.0;
+    if let Some(config) = &chain.0 {
+        *level = Level::new(1, config);
+    }
+    *food_rng = FoodRng::new(*food_seed);
+    score.0 = 0;
+    *run_time = RunTime::default();
+    *snek_timer = SnekMoveTimer(Timer::new(base.0, true));
+    // Keeps the interval, which the difficulty may have changed.
+    food_timer.0.reset();
+    *last_tail_position = LastTailPosition::default();
+    *pending_growth = PendingGrowth::default();
+    *magnet = MagnetEffect::default();
+    *reversed = ReversedControls::default();
+    *invincible = Invincible::default();
+    *double_score = DoubleScore::default();
+    *phase = PhaseSelf::default();
+    *slow = SlowEffect::default();
+    *boost = Boost::default();
+    *wall_breaks = WallBreakCharges::default();
+    recorder.0.clear();
+    best.tick = 0;
+    spawn_initial_snake(
+        commands,
+        &materials,
+        &padding,
+        &spacing,
+        &mut path,
+        segment_res,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use crate::components::{Direction, FoodKind, Position, Projectile};
+    use crate::resources::{
+        GameOverScreen, Invincible, LastTailPosition, Lives, LivesLeft, Paused, PendingGrowth,
+        RewindConfig, RewindOnDeath, Score, ShootTail, SnekMoveTimer, SnekPath, TimeAttack, TimeUp,
+        WallBreakCharges, WallMode,
+    };
+    use crate::testing::{
+        body, food, head, place_food, place_wall, press_key, quiet_app, release_key, run_tick,
+        run_ticks,
+    };
+
+    /// Everything a reset puts back, as far as the snake and the score go.
+    #[derive(Debug, PartialEq)]
+    struct Board {
+        head: (Position, Direction),
+        body: Vec<Position>,
+        path: Vec<Position>,
+        food: Vec<(Position, FoodKind)>,
+        last_tail: Option<Position>,
+        pending: u32,
+        score: u32,
+        interval: f32,
+        lives: u32,
+        paused: bool,
+        over: bool,
+        wall_breaks: u32,
+        invincible: bool,
+    }
+
+    fn board(app: &App) -> Board {
+        let resources = &app.resources;
+        Board {
+            head: head(app),
+            body: body(app),
+            path: resources
+                .get::<SnekPath>()
+                .unwrap()
+                .0
+                .iter()
+                .copied()
+                .collect(),
+            food: food(app),
+            last_tail: resources.get::<LastTailPosition>().unwrap().0,
+            pending: resources.get::<PendingGrowth>().unwrap().0,
+            score: resources.get::<Score>().unwrap().0,
+            interval: resources.get::<SnekMoveTimer>().unwrap().duration,
+            lives: resources.get::<LivesLeft>().unwrap().0,
+            paused: resources.get::<Paused>().unwrap().0,
+            over: resources.get::<GameOverScreen>().unwrap().0,
+            wall_breaks: resources.get::<WallBreakCharges>().unwrap().0,
+            invincible: resources.get::<Invincible>().unwrap().is_active(),
+        }
+    }
+
+    #[test]
+    fn a_reset_game_looks_like_a_fresh_start() {
+        let fresh = board(&quiet_app(|_| ()));
+
+        let mut app = quiet_app(|_| ());
+        place_food(&mut app, FoodKind::WallBreak, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Invincible, Position { x: 3, y: 5 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 6 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 7, y: 7 });
+        run_ticks(&mut app, 4);
+        let played = board(&app);
+        assert_ne!(played, fresh);
+        assert!(played.wall_breaks > 0 && played.invincible);
+
+        press_key(&mut app, KeyCode::R);
+        app.update();
+        release_key(&mut app, KeyCode::R);
+        app.update();
+        assert_eq!(board(&app), fresh);
+    }
+
+    #[test]
+    fn a_reset_takes_shots_in_flight_off_the_board() {
+        let mut app = quiet_app(|resources| resources.insert(ShootTail(true)));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        run_tick(&mut app);
+        press_key(&mut app, KeyCode::X);
+        app.update();
+        release_key(&mut app, KeyCode::X);
+        assert_eq!(app.world.query::<&Projectile>().count(), 1);
+
+        press_key(&mut app, KeyCode::R);
+        app.update();
+        release_key(&mut app, KeyCode::R);
+        app.update();
+        assert_eq!(app.world.query::<&Projectile>().count(), 0);
+    }
+
+    #[test]
+    fn losing_two_of_three_lives_keeps_the_game_going() {
+        let mut app = quiet_app(|resources| resources.insert(Lives(3)));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_wall(&mut app, Position { x: 3, y: 5 });
+        run_ticks(&mut app, 2);
+        app.update();
+        assert_eq!(app.resources.get::<LivesLeft>().unwrap().0, 2);
+
+        place_wall(&mut app, Position { x: 3, y: 4 });
+        run_tick(&mut app);
+        app.update();
+        assert_eq!(app.resources.get::<LivesLeft>().unwrap().0, 1);
+        assert!(!app.resources.get::<GameOverScreen>().unwrap().0);
+        // Back on the start, still with the point scored before the first crash.
+        assert_eq!(head(&app), (Position { x: 3, y: 3 }, Direction::Up));
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 1);
+    }
+
+    #[test]
+    fn a_time_attack_run_stops_once_its_time_is_up() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(TimeAttack(Some(Duration::from_secs(1))));
+        });
+        let up = |app: &App| app.resources.get::<TimeUp>().unwrap().0;
+        run_ticks(&mut app, 3);
+        assert!(!up(&app));
+        // A quarter of a second later at 60 frames a second, and then some.
+        for _ in 0..20 {
+            app.update();
+        }
+        assert!(up(&app));
+        assert!(app.resources.get::<Paused>().unwrap().0);
+        let stopped = head(&app);
+        for _ in 0..30 {
+            app.update();
+        }
+        assert_eq!(head(&app), stopped);
+    }
+
+    #[test]
+    fn death_rewinds_to_the_board_a_few_ticks_back() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(RewindOnDeath(Some(RewindConfig {
+                ticks: 3,
+                per_game: None,
+            })))
+        });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 7, y: 7 });
+        let mut survived = Vec::new();
+        for _ in 0..6 {
+            run_tick(&mut app);
+            survived.push(board(&app));
+        }
+        // Out through the top edge.
+        run_tick(&mut app);
+        app.update();
+        // The oldest of the three boards kept, which has yet to leave a cell behind.
+        let rewound = Board {
+            last_tail: None,
+            ..survived.swap_remove(3)
+        };
+        assert_eq!(board(&app), rewound);
+        assert_eq!(rewound.score, 1);
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::components::Direction;
+use crate::resources::{GamepadInput, StickDeadzone};
+
+fn dpad_button(dir: Direction) -> GamepadButtonType {
+    match dir {
+        Direction::Left => GamepadButtonType::DPadLeft,
+        Direction::Up => GamepadButtonType::DPadUp,
+        Direction::Right => GamepadButtonType::DPadRight,
+        Direction::Down => GamepadButtonType::DPadDown,
+    }
+}
+
+/// The direction the left stick of `pad` is pushed in, if it is past the deadzone.
+///
+/// Only the axis pushed furthest counts, so a diagonal never holds two directions at once.
+fn stick_direction(axes: &Axis<GamepadAxis>, pad: Gamepad, deadzone: f32) -> Option<Direction> {
+    let x = axes
+        .get(GamepadAxis(pad, GamepadAxisType::LeftStickX))
+        .unwrap_or(0.0);
+    let y = axes
+        .get(GamepadAxis(pad, GamepadAxisType::LeftStickY))
+        .unwrap_or(0.0);
+    if x.abs().max(y.abs()) <= deadzone {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x < 0.0 {
+            Direction::Left
+        } else {
+            Direction::Right
+        })
+    } else {
+        Some(if y > 0.0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        })
+    }
+}
+
+/// Keeps track of connected gamepads and sums up what they hold for `snek_movement` and
+/// `pause` to read along with the keyboard.
+pub(crate) fn gamepad_input(
+    (mut reader, events): (Local<EventReader<GamepadEvent>>, Res<Events<GamepadEvent>>),
+    (buttons, axes): (Res<Input<GamepadButton>>, Res<Axis<GamepadAxis>>),
+    deadzone: Res<StickDeadzone>,
+    mut input: ResMut<GamepadInput>,
+) {
+    for GamepadEvent(pad, event) in reader.iter(&events) {
+        match event {
+            GamepadEventType::Connected if !input.pads.contains(pad) => input.pads.push(*pad),
+            GamepadEventType::Disconnected => input.pads.retain(|connected| connected != pad),
+            _ => {}
+        }
+    }
+    let pads = &input.pads;
+    let held = Direction::ALL
+        .iter()
+        .copied()
+        .filter(|dir| {
+            pads.iter().any(|pad| {
+                buttons.pressed(GamepadButton(*pad, dpad_button(*dir)))
+                    || stick_direction(&axes, *pad, deadzone.0) == Some(*dir)
+            })
+        })
+        .collect();
+    let start = pads
+        .iter()
+        .any(|pad| buttons.just_pressed(GamepadButton(*pad, GamepadButtonType::Start)));
+    input.held = held;
+    input.start_pressed = start;
+}
@@ -0,0 +1,432 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::spawn::{spawn_wall, START_POSITION};
+use crate::components::{
+    Door, Food, Gate, GateOpening, Key, Obstacle, Position, Size, SnekHead, SnekSegment, Wall,
+};
+use crate::events::{GrowthEvent, ResetGame};
+use crate::resources::{
+    ArenaShrink, ArenaSize, CellPadding, FoodsEaten, GateCells, Keys, KeysAndDoors, Materials,
+    ObstacleCells, RunTime,
+};
+use crate::{MIN_ARENA_HEIGHT, MIN_ARENA_WIDTH};
+
+/// How long an opened gate takes to shrink away.
+const GATE_OPENING_SECS: f32 = 0.3;
+
+/// The cells the snake could get to from the start without going through `closed` ones.
+fn reachable(arena: ArenaSize, closed: &HashSet<Position>) -> HashSet<Position> {
+    let mut seen: HashSet<Position> = HashSet::new();
+    let mut queue: VecDeque<Position> = VecDeque::new();
+    if !closed.contains(&START_POSITION) {
+        seen.insert(START_POSITION);
+        queue.push_back(START_POSITION);
+    }
+    while let Some(pos) = queue.pop_front() {
+        for (dx, dy) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let next = Position {
+                x: pos.x + dx,
+                y: pos.y + dy,
+            };
+            if arena.contains(next) && !closed.contains(&next) && seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Falls back to the default [`ArenaSize`] if the one asked for can't fit the starting snake.
+pub(crate) fn validate_arena(mut arena: ResMut<ArenaSize>) {
+    if arena.width >= MIN_ARENA_WIDTH && arena.height >= MIN_ARENA_HEIGHT {
+        return;
+    }
+    let fallback = ArenaSize::default();
+    eprintln!(
+        "ignoring the {}x{} arena: the snake needs at least {}x{}, using {}x{}",
+        arena.width,
+        arena.height,
+        MIN_ARENA_WIDTH,
+        MIN_ARENA_HEIGHT,
+        fallback.width,
+        fallback.height
+    );
+    *arena = fallback;
+}
+
+/// Closes the [`ArenaSize`] in by a column and a row per [`ArenaShrink`] interval of the
+/// run, from the size it started on, and despawns food left outside where it can't be eaten.
+///
+/// [`RunTime`] starts over on restart, so the arena grows back to full size along with it.
+pub(crate) fn shrink_arena(
+    mut commands: Commands,
+    (shrink, run_time): (Res<ArenaShrink>, Res<RunTime>),
+    mut arena: ResMut<ArenaSize>,
+    mut full: Local<Option<ArenaSize>>,
+    food: Query<With<Food, (Entity, &Position)>>,
+) {
+    let every = match shrink.0 {
+        Some(every) if every > Duration::default() => every,
+        _ => return,
+    };
+    let full = *full.get_or_insert(*arena);
+    let steps = (run_time.0.as_secs_f64() / every.as_secs_f64()) as u32;
+    let shrunk = full.shrunk(steps);
+    if shrunk == *arena {
+        return;
+    }
+    *arena = shrunk;
+    for (entity, position) in food.iter() {
+        if !arena.contains(*position) {
+            commands.despawn(entity);
+        }
+    }
+}
+
+/// Drops every obstacle if some free cell could never be reached from the start past them.
+pub(crate) fn validate_obstacles(arena: Res<ArenaSize>, mut obstacles: ResMut<ObstacleCells>) {
+    let closed: HashSet<Position> = obstacles.0.iter().copied().collect();
+    if closed.is_empty() {
+        return;
+    }
+    let free = arena.area() - closed.len();
+    let seen = reachable(*arena, &closed);
+    if seen.len() < free {
+        eprintln!(
+            "ignoring {} obstacles: they close off {} cells from the start",
+            obstacles.0.len(),
+            free - seen.len()
+        );
+        obstacles.0.clear();
+    }
+}
+
+/// Drops every gate if, with all of them closed, some free cell could never be reached from
+/// the start; food spawned there would be out of reach.
+pub(crate) fn validate_gates(
+    arena: Res<ArenaSize>,
+    obstacles: Res<ObstacleCells>,
+    mut gates: ResMut<GateCells>,
+) {
+    let mut closed: HashSet<Position> = gates.0.iter().map(|gate| gate.position).collect();
+    if closed.is_empty() {
+        return;
+    }
+    closed.extend(obstacles.0.iter().copied());
+    let free = arena.area() - closed.len();
+    let seen = reachable(*arena, &closed);
+    if seen.len() < free {
+        eprintln!(
+            "ignoring {} gates: they close off {} cells from the start",
+            gates.0.len(),
+            free - seen.len()
+        );
+        gates.0.clear();
+    }
+}
+
+/// Drops the whole [`KeysAndDoors`] layout unless collecting every key within reach, and
+/// going through every door that opens, gets through all of the doors in the end.
+pub(crate) fn validate_keys_and_doors(arena: Res<ArenaSize>, mut layout: ResMut<KeysAndDoors>) {
+    if layout.doors.is_empty() {
+        return;
+    }
+    let mut closed: HashSet<Position> = layout.doors.iter().map(|door| door.position).collect();
+    loop {
+        let seen = reachable(*arena, &closed);
+        let held = layout.keys.iter().filter(|key| seen.contains(key)).count() as u32;
+        let opened: Vec<Position> = layout
+            .doors
+            .iter()
+            .filter(|door| closed.contains(&door.position) && door.requires <= held)
+            .map(|door| door.position)
+            .collect();
+        if opened.is_empty() {
+            break;
+        }
+        for position in opened {
+            closed.remove(&position);
+        }
+    }
+    if !closed.is_empty() {
+        eprintln!(
+            "ignoring keys and doors: {} doors can never get enough keys",
+            closed.len()
+        );
+        layout.keys.clear();
+        layout.doors.clear();
+    }
+}
+
+/// Picks up keys the head runs over, opens the doors they unlock and puts closed doors back
+/// after walls were cleared away; a reset also lays out every key again.
+pub(crate) fn keys_and_doors(
+    mut commands: Commands,
+    (layout, mut held): (Res<KeysAndDoors>, ResMut<Keys>),
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    (reset_events, mut reset_reader, mut laid_out): (
+        Res<Events<ResetGame>>,
+        Local<EventReader<ResetGame>>,
+        Local<bool>,
+    ),
+    heads: Query<With<SnekHead, &Position>>,
+    keys: Query<With<Key, (Entity, &Position)>>,
+    closed: Query<With<Wall, (Entity, &Door, &Position)>>,
+    occupants: Query<(
+        &Position,
+        Option<&SnekHead>,
+        Option<&SnekSegment>,
+        Option<&Food>,
+    )>,
+) {
+    if layout.keys.is_empty() && layout.doors.is_empty() {
+        return;
+    }
+    if reset_reader.iter(&reset_events).next().is_some() || !*laid_out {
+        *laid_out = true;
+        held.0 = 0;
+        for (entity, _) in keys.iter() {
+            commands.despawn(entity);
+        }
+        for &position in layout.keys.iter() {
+            commands
+                .spawn(SpriteComponents {
+                    material: materials.key_material.clone(),
+                    ..Default::default()
+                })
+                .with(Key)
+                .with(position)
+                .with(Size::square(padding.food * 0.6));
+        }
+    } else {
+        for head in heads.iter() {
+            for (entity, _) in keys.iter().filter(|(_, pos)| *pos == head) {
+                commands.despawn(entity);
+                held.0 += 1;
+            }
+        }
+    }
+    let mut standing = HashSet::new();
+    for (entity, door, pos) in closed.iter() {
+        if held.0 >= door.requires {
+            commands.remove_one::<Wall>(entity);
+            commands.insert_one(
+                entity,
+                GateOpening(Timer::from_seconds(GATE_OPENING_SECS, false)),
+            );
+        } else {
+            standing.insert(*pos);
+        }
+    }
+    let taken = occupied(&occupants);
+    for door in layout.doors.iter() {
+        if held.0 >= door.requires
+            || standing.contains(&door.position)
+            || taken.contains(&door.position)
+        {
+            continue;
+        }
+        commands
+            .spawn(SpriteComponents {
+                material: materials.door_material.clone(),
+                ..Default::default()
+            })
+            .with(Wall)
+            .with(Door {
+                requires: door.requires,
+            })
+            .with(door.position)
+            .with(Size::square(padding.segment));
+    }
+}
+
+/// Puts obstacles back after walls were cleared away by a lost life or a reset.
+pub(crate) fn obstacles(
+    mut commands: Commands,
+    config: Res<ObstacleCells>,
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    standing: Query<With<Obstacle, With<Wall, &Position>>>,
+    occupants: Query<(
+        &Position,
+        Option<&SnekHead>,
+        Option<&SnekSegment>,
+        Option<&Food>,
+    )>,
+) {
+    if config.0.is_empty() {
+        return;
+    }
+    let standing: HashSet<Position> = standing.iter().copied().collect();
+    let taken = occupied(&occupants);
+    for &position in config.0.iter() {
+        // Like a gate, it only comes back onto an empty cell.
+        if standing.contains(&position) || taken.contains(&position) {
+            continue;
+        }
+        spawn_wall(&mut commands, &materials, &padding, position);
+        commands.with(Obstacle);
+    }
+}
+
+/// Cells a gate or door must not close on: anything of the snake itself, and food.
+fn occupied(
+    occupants: &Query<(
+        &Position,
+        Option<&SnekHead>,
+        Option<&SnekSegment>,
+        Option<&Food>,
+    )>,
+) -> HashSet<Position> {
+    occupants
+        .iter()
+        .filter(|(_, head, segment, food)| head.is_some() || segment.is_some() || food.is_some())
+        .map(|(pos, ..)| *pos)
+        .collect()
+}
+
+/// Counts eaten food, opens the gates it unlocks and puts the closed ones back after walls
+/// were cleared away by a lost life or a reset.
+pub(crate) fn gates(
+    mut commands: Commands,
+    time: Res<Time>,
+    (config, mut eaten): (Res<GateCells>, ResMut<FoodsEaten>),
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    (growth_events, reset_events): (Res<Events<GrowthEvent>>, Res<Events<ResetGame>>),
+    (mut growth_reader, mut reset_reader): (
+        Local<EventReader<GrowthEvent>>,
+        Local<EventReader<ResetGame>>,
+    ),
+    closed: Query<With<Wall, (Entity, &Gate, &Position)>>,
+    mut opening: Query<(Entity, &mut GateOpening, &mut Size)>,
+    occupants: Query<(
+        &Position,
+        Option<&SnekHead>,
+        Option<&SnekSegment>,
+        Option<&Food>,
+    )>,
+) {
+    for (entity, mut timer, mut size) in opening.iter_mut() {
+        timer.0.tick(time.delta_seconds);
+        if timer.0.finished {
+            commands.despawn(entity);
+        } else {
+            let left = 1. - timer.0.elapsed / timer.0.duration;
+            *size = Size::square(padding.segment * left);
+        }
+    }
+    if config.0.is_empty() {
+        return;
+    }
+    if reset_reader.iter(&reset_events).next().is_some() {
+        eaten.0 = 0;
+    }
+    eaten.0 += growth_reader.iter(&growth_events).count() as u32;
+    let mut standing = HashSet::new();
+    for (entity, gate, pos) in closed.iter() {
+        if eaten.0 >= gate.opens_after {
+            // Passable right away; the shrinking is only there to show it opened.
+            commands.remove_one::<Wall>(entity);
+            commands.insert_one(
+                entity,
+                GateOpening(Timer::from_seconds(GATE_OPENING_SECS, false)),
+            );
+        } else {
+            standing.insert(*pos);
+        }
+    }
+    let taken = occupied(&occupants);
+    for gate in config.0.iter() {
+        // A gate comes back only onto an empty cell, so nothing is ever shut inside it.
+        if eaten.0 >= gate.opens_after
+            || standing.contains(&gate.position)
+            || taken.contains(&gate.position)
+        {
+            continue;
+        }
+        commands
+            .spawn(SpriteComponents {
+                material: materials.gate_material.clone(),
+                ..Default::default()
+            })
+            .with(Wall)
+            .with(Gate {
+                opens_after: gate.opens_after,
+            })
+            .with(gate.position)
+            .with(Size::square(padding.segment));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::{Direction, FoodKind, Position};
+    use crate::resources::{ArenaSize, DoorCell, GateCell, GateCells, Keys, KeysAndDoors};
+    use crate::testing::{head, place_food, quiet_app, run_tick, walls};
+
+    #[test]
+    fn an_arena_too_small_for_the_snake_falls_back_to_the_default() {
+        let app = quiet_app(|resources| {
+            resources.insert(ArenaSize {
+                width: 1,
+                height: 1,
+            })
+        });
+        assert_eq!(
+            *app.resources.get::<ArenaSize>().unwrap(),
+            ArenaSize::default()
+        );
+        assert_eq!(head(&app), (Position { x: 3, y: 3 }, Direction::Up));
+    }
+
+    #[test]
+    fn a_gate_opens_on_the_food_that_reaches_its_threshold() {
+        let gate = Position { x: 0, y: 0 };
+        let mut app = quiet_app(|resources| {
+            resources.insert(GateCells(vec![GateCell {
+                position: gate,
+                opens_after: 2,
+            }]))
+        });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        // Gates go up on the first frame.
+        app.update();
+        assert!(walls(&app).contains(&gate));
+        run_tick(&mut app);
+        assert!(walls(&app).contains(&gate));
+        run_tick(&mut app);
+        assert!(!walls(&app).contains(&gate));
+    }
+
+    #[test]
+    fn collecting_keys_opens_the_doors_they_add_up_to() {
+        let (first, second) = (Position { x: 0, y: 0 }, Position { x: 9, y: 0 });
+        let mut app = quiet_app(|resources| {
+            resources.insert(KeysAndDoors {
+                keys: vec![Position { x: 3, y: 4 }, Position { x: 3, y: 5 }],
+                doors: vec![
+                    DoorCell {
+                        position: first,
+                        requires: 1,
+                    },
+                    DoorCell {
+                        position: second,
+                        requires: 2,
+                    },
+                ],
+            })
+        });
+        // Keys and doors are laid out on the first frame.
+        app.update();
+        assert!(walls(&app).contains(&first) && walls(&app).contains(&second));
+        run_tick(&mut app);
+        assert_eq!(app.resources.get::<Keys>().unwrap().0, 1);
+        assert!(!walls(&app).contains(&first) && walls(&app).contains(&second));
+        run_tick(&mut app);
+        assert_eq!(app.resources.get::<Keys>().unwrap().0, 2);
+        assert!(!walls(&app).contains(&second));
+    }
+}
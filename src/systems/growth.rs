@@ -0,0 +1,412 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::movement::gameplay_delta;
+use super::spawn::spawn_segment;
+use crate::components::{Position, SnekHead, SnekSegment};
+use crate::events::{GameOverEvent, GrowthEvent, LifeLost, Player, ResetGame};
+use crate::resources::{
+    BaseSpeed, BestRun, CellPadding, FoodChain, FoodRng, GrowthMode, LastTailPosition, Level,
+    LivesLeft, Materials, MusicBpm, Paused, PendingGrowth, ReplayLog, ReplayRecorder, RunRecorder,
+    SaveFiles, Score, SnekMoveTimer, SnekSegments, SpeedBasis, SpeedRamp, Starvation,
+    StarvationInterval, Tutorial, TwoPlayer, Wallet,
+};
+use crate::{BEST_RUN_PATH, REPLAY_PATH, WALLET_PATH};
+
+/// How far `[` and `]` move the [`BaseSpeed`], and the range they keep it in.
+const BASE_SPEED_STEP: Duration = Duration::from_millis(25);
+const FASTEST_BASE_SPEED: Duration = Duration::from_millis(100);
+const SLOWEST_BASE_SPEED: Duration = Duration::from_millis(500);
+
+/// Slows the snake down on `[` and speeds it up on `]`, for the rest of the session.
+///
+/// It changes the [`BaseSpeed`], so `speed_ramp` applies it right away and a reset starts
+/// from it too. The dev build already tunes the tick on `-` and `=`.
+pub(crate) fn speed_control(keyboard_input: Res<Input<KeyCode>>, mut base: ResMut<BaseSpeed>) {
+    let speed = if keyboard_input.just_pressed(KeyCode::LBracket) {
+        base.0 + BASE_SPEED_STEP
+    } else if keyboard_input.just_pressed(KeyCode::RBracket) {
+        base.0.checked_sub(BASE_SPEED_STEP).unwrap_or_default()
+    } else {
+        return;
+    };
+    base.0 = speed.max(FASTEST_BASE_SPEED).min(SLOWEST_BASE_SPEED);
+}
+
+pub(crate) fn speed_ramp(
+    (ramp, base): (Res<SpeedRamp>, Res<BaseSpeed>),
+    basis: Res<SpeedBasis>,
+    bpm: Res<MusicBpm>,
+    score: Res<Score>,
+    segments: Res<SnekSegments>,
+    mut snek_timer: ResMut<SnekMoveTimer>,
+) {
+    let duration = match bpm.0 {
+        Some(bpm) if bpm > 0. => 60. / bpm,
+        _ => {
+            let steps = match *basis {
+                SpeedBasis::Score => score.0,
+                SpeedBasis::Length => segments.0.len().saturating_sub(1) as u32,
+            };
+            let base = base.0.as_secs_f32();
+            let floor = ramp.floor.as_secs_f32().min(base);
+            (base - ramp.step.as_secs_f32() * steps as f32).max(floor)
+        }
+    };
+    if snek_timer.duration != duration {
+        snek_timer.duration = duration;
+    }
+}
+
+pub(crate) fn snek_growth(
+    mut commands: Commands,
+    last_tail_position: Res<LastTailPosition>,
+    growth_events: Res<Events<GrowthEvent>>,
+    (mode, snek_timer, mut pending): (Res<GrowthMode>, Res<SnekMoveTimer>, ResMut<PendingGrowth>),
+    mut segments: ResMut<SnekSegments>,
+    mut growth_reader: Local<EventReader<GrowthEvent>>,
+    materials: Res<Materials>,
+    padding: Res<CellPadding>,
+) {
+    let grown: u32 = growth_reader
+        .iter(&growth_events)
+        .map(|growth| growth.segments)
+        .sum();
+    let grow = match *mode {
+        GrowthMode::Tail => grown,
+        // The food is eaten on a tick, so the tail has to hold still on the one after it.
+        // Adding the segment where the tail just left is the same as the tail not moving.
+        GrowthMode::Head => {
+            let owed = if snek_timer.finished {
+                std::mem::take(&mut pending.0)
+            } else {
+                0
+            };
+            pending.0 += grown;
+            owed
+        }
+    };
+    // The tail position is only known once the snake has moved. Several new segments all
+    // start out stacked on it and unfold from there as the snake moves on.
+    if let Some(tail) = last_tail_position.0 {
+        for _ in 0..grow {
+            segments.0.push(spawn_segment(
+                &mut commands,
+                &materials.segment_material,
+                &padding,
+                tail,
+            ));
+        }
+    }
+}
+
+/// Takes the tail whenever [`StarvationInterval`] passes without eating, ending the run when
+/// the last segment would go.
+///
+/// The clock starts over on every meal, lost life and reset.
+pub(crate) fn starvation(
+    mut commands: Commands,
+    (time, paused, interval): (Res<Time>, Res<Paused>, Res<StarvationInterval>),
+    mut starvation: ResMut<Starvation>,
+    (mut segments, mut last_tail_position): (ResMut<SnekSegments>, ResMut<LastTailPosition>),
+    mut game_over_events: ResMut<Events<GameOverEvent>>,
+    (growth_events, life_lost_events, reset_events): (
+        Res<Events<GrowthEvent>>,
+        Res<Events<LifeLost>>,
+        Res<Events<ResetGame>>,
+    ),
+    (mut growth_reader, mut life_lost_reader, mut reset_reader): (
+        Local<EventReader<GrowthEvent>>,
+        Local<EventReader<LifeLost>>,
+        Local<EventReader<ResetGame>>,
+    ),
+    positions: Query<With<SnekSegment, &Position>>,
+) {
+    let interval = match interval.0 {
+        Some(interval) => interval,
+        None => return,
+    };
+    let fed = growth_reader.iter(&growth_events).next().is_some();
+    let restarted = life_lost_reader.iter(&life_lost_events).next().is_some()
+        | reset_reader.iter(&reset_events).next().is_some();
+    if fed || restarted || starvation.0.duration != interval.as_secs_f32() {
+        starvation.0 = Timer::new(interval, true);
+    }
+    if paused.0 {
+        return;
+    }
+    starvation.0.tick(gameplay_delta(&time));
+    if !starvation.0.just_finished {
+        return;
+    }
+    // Like poison, starving down to a bare head is fatal.
+    if segments.0.len() <= 1 {
+        game_over_events.send(GameOverEvent(Player::One));
+        return;
+    }
+    if let Some(tail) = segments.0.pop() {
+        // The freed cell is where the tail would grow back into.
+        if let Ok(pos) = positions.get(tail) {
+            last_tail_position.0 = Some(*pos);
+        }
+        commands.despawn(tail);
+    }
+}
+
+/// Counts eaten food toward the current level and ends the attempt once its time runs out.
+pub(crate) fn level_objective(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    chain: Res<FoodChain>,
+    mut level: ResMut<Level>,
+    growth_events: Res<Events<GrowthEvent>>,
+    mut growth_reader: Local<EventReader<GrowthEvent>>,
+    mut game_over_events: ResMut<Events<GameOverEvent>>,
+) {
+    let config = match &chain.0 {
+        Some(config) => config,
+        None => return,
+    };
+    let eaten = growth_reader.iter(&growth_events).count() as u32;
+    level.foods_remaining = level.foods_remaining.saturating_sub(eaten);
+    if level.foods_remaining == 0 {
+        *level = Level::new(level.number + 1, config);
+        return;
+    }
+    if !paused.0 {
+        level.time_limit.tick(gameplay_delta(&time));
+    }
+    if level.time_limit.finished {
+        game_over_events.send(GameOverEvent(Player::One));
+        // With lives to spare the level starts over; a reset puts it back at the first one.
+        *level = Level::new(level.number, config);
+    }
+}
+
+pub(crate) fn record_run(
+    snek_timer: Res<SnekMoveTimer>,
+    mut recorder: ResMut<RunRecorder>,
+    heads: Query<With<SnekHead, &Position>>,
+) {
+    if !snek_timer.finished {
+        return;
+    }
+    recorder.0.extend(heads.iter().copied());
+}
+
+pub(crate) fn save_best_run(
+    mut reader: Local<EventReader<GameOverEvent>>,
+    game_over_events: Res<Events<GameOverEvent>>,
+    (score, save_files): (Res<Score>, Res<SaveFiles>),
+    (lives_left, tutorial): (Res<LivesLeft>, Res<Tutorial>),
+    mut recorder: ResMut<RunRecorder>,
+    mut best: ResMut<BestRun>,
+) {
+    // Runs before `game_over`, so the last life is still counted here.
+    let final_death = lives_left.0 <= 1 && !tutorial.0;
+    if reader.iter(&game_over_events).next().is_some() && final_death && score.0 > best.score {
+        best.score = score.0;
+        best.path = std::mem::take(&mut recorder.0);
+        if !save_files.0 {
+            return;
+        }
+        if let Err(e) = best.save(BEST_RUN_PATH) {
+            eprintln!("failed to save best run to {}: {}", BEST_RUN_PATH, e);
+        }
+    }
+}
+
+/// Saves the game just over as a [`ReplayLog`], and starts recording afresh on a reset.
+pub(crate) fn save_replay(
+    (game_over_events, mut game_over_reader): (
+        Res<Events<GameOverEvent>>,
+        Local<EventReader<GameOverEvent>>,
+    ),
+    (reset_events, mut reset_reader): (Res<Events<ResetGame>>, Local<EventReader<ResetGame>>),
+    (lives_left, tutorial, two_player): (Res<LivesLeft>, Res<Tutorial>, Res<TwoPlayer>),
+    (food_rng, save_files): (Res<FoodRng>, Res<SaveFiles>),
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if reset_reader.iter(&reset_events).next().is_some() {
+        *recorder = ReplayRecorder::default();
+    }
+    // Player two's turns are not recorded, so neither are their games.
+    let final_death = lives_left.0 <= 1 && !tutorial.0 && two_player.0.is_none() && save_files.0;
+    if game_over_reader.iter(&game_over_events).next().is_some() && final_death {
+        let log = ReplayLog {
+            seed: food_rng.seed(),
+            turns: recorder.turns.clone(),
+            food: recorder.food.clone(),
+        };
+        if let Err(e) = log.save(REPLAY_PATH) {
+            eprintln!("failed to save replay to {}: {}", REPLAY_PATH, e);
+        }
+    }
+}
+
+pub(crate) fn award_coins(
+    mut reader: Local<EventReader<GrowthEvent>>,
+    growth_events: Res<Events<GrowthEvent>>,
+    (tutorial, save_files): (Res<Tutorial>, Res<SaveFiles>),
+    mut wallet: ResMut<Wallet>,
+) {
+    let earned = reader.iter(&growth_events).count() as u32;
+    if earned == 0 || tutorial.0 {
+        return;
+    }
+    wallet.0 += earned;
+    if !save_files.0 {
+        return;
+    }
+    if let Err(e) = wallet.save(WALLET_PATH) {
+        eprintln!("failed to save wallet to {}: {}", WALLET_PATH, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use crate::components::{FoodKind, Position};
+    use crate::resources::{
+        BaseSpeed, FoodChain, FoodChainConfig, GrowthMode, Level, MusicBpm, Score, Scoring,
+        SnekMoveTimer, SpeedBasis, StarvationInterval, WallMode, GROWTH_SPEED_STEP,
+    };
+    use crate::testing::{body, place_food, place_wall, press_key, quiet_app, run_tick, run_ticks};
+
+    fn interval(app: &App) -> f32 {
+        app.resources.get::<SnekMoveTimer>().unwrap().duration
+    }
+
+    #[test]
+    fn growing_speeds_up_until_game_over() {
+        let mut app = quiet_app(|_| ());
+        let start = app.resources.get::<BaseSpeed>().unwrap().0.as_secs_f32();
+        assert_eq!(interval(&app), start);
+
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        run_ticks(&mut app, 3);
+        assert_eq!(body(&app).len(), 3);
+        let step = GROWTH_SPEED_STEP.as_secs_f32();
+        assert!((interval(&app) - (start - 2. * step)).abs() < 1e-6);
+
+        place_wall(&mut app, Position { x: 3, y: 7 });
+        run_tick(&mut app);
+        app.update();
+        press_key(&mut app, KeyCode::Return);
+        app.update();
+        app.update();
+        assert_eq!(body(&app).len(), 1);
+        assert_eq!(interval(&app), start);
+    }
+
+    /// The move interval after eating two pieces of food that score nothing, under `basis`.
+    fn interval_after_pointless_meals(basis: SpeedBasis) -> f32 {
+        let mut app = quiet_app(|resources| {
+            resources.insert(basis);
+            resources.insert(Scoring {
+                per_food: 0,
+                per_tick: 0,
+            });
+        });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        run_ticks(&mut app, 3);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 0);
+        interval(&app)
+    }
+
+    #[test]
+    fn length_basis_speeds_up_without_points() {
+        let start = BaseSpeed::default().0.as_secs_f32();
+        assert_eq!(interval_after_pointless_meals(SpeedBasis::Score), start);
+        assert!(interval_after_pointless_meals(SpeedBasis::Length) < start);
+    }
+
+    /// The body on the tick food right ahead is eaten, and on the tick after, in `mode`.
+    fn bodies_around_eating(mode: GrowthMode) -> (Vec<Position>, Vec<Position>) {
+        let mut app = quiet_app(|resources| resources.insert(mode));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        run_tick(&mut app);
+        let eating = body(&app);
+        run_tick(&mut app);
+        (eating, body(&app))
+    }
+
+    #[test]
+    fn tail_growth_shows_at_once_and_head_growth_a_tick_later() {
+        let cell = |y| Position { x: 3, y };
+        // The tail grows back onto the cell it just left.
+        assert_eq!(
+            bodies_around_eating(GrowthMode::Tail),
+            (vec![cell(3), cell(2)], vec![cell(4), cell(3)])
+        );
+        // The tail stays put while the head moves on.
+        assert_eq!(
+            bodies_around_eating(GrowthMode::Head),
+            (vec![cell(3)], vec![cell(4), cell(3)])
+        );
+    }
+
+    #[test]
+    fn going_hungry_for_the_interval_costs_the_tail() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(StarvationInterval(Some(Duration::from_millis(900))));
+        });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        let mut lengths = Vec::new();
+        for _ in 0..6 {
+            run_tick(&mut app);
+            lengths.push(body(&app).len());
+        }
+        // The last meal is at 0.5s, so the snake starves a segment at 1.4s.
+        assert_eq!(lengths, vec![2, 3, 3, 3, 3, 2]);
+    }
+
+    #[test]
+    fn a_music_bpm_sets_the_move_interval() {
+        let mut app = quiet_app(|resources| resources.insert(MusicBpm(Some(120.))));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        app.update();
+        assert_eq!(interval(&app), 0.5);
+        // 30 frames a beat, and growing doesn't speed that up.
+        assert_eq!(run_tick(&mut app), 30);
+        assert_eq!(body(&app).len(), 2);
+        assert_eq!(run_tick(&mut app), 30);
+        assert_eq!(interval(&app), 0.5);
+    }
+
+    #[test]
+    fn clearing_the_chain_moves_up_a_level() {
+        let config = || FoodChainConfig {
+            foods: 2,
+            ..Default::default()
+        };
+        let mut app = quiet_app(|resources| {
+            resources.insert(Level::new(1, &config()));
+            resources.insert(FoodChain(Some(config())));
+        });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        run_tick(&mut app);
+        {
+            let level = app.resources.get::<Level>().unwrap();
+            assert_eq!((level.number, level.foods_remaining), (1, 1));
+        }
+
+        run_tick(&mut app);
+        let level = app.resources.get::<Level>().unwrap();
+        assert_eq!((level.number, level.foods_remaining), (2, 2));
+        assert_eq!(
+            level.time_limit.duration,
+            config().time_limit(2).as_secs_f32()
+        );
+        assert!(config().time_limit(2) < config().time_limit(1));
+    }
+}
@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+use crate::components::{Food, Position, SnekHead, SnekSegment};
+use crate::resources::{GameSnapshot, Score, SnekMoveTimer, SnekSegments, TickHook};
+
+pub(crate) fn tick_hook(
+    snek_timer: Res<SnekMoveTimer>,
+    score: Res<Score>,
+    segments: Res<SnekSegments>,
+    mut hook: ResMut<TickHook>,
+    heads: Query<With<SnekHead, &Position>>,
+    positions: Query<With<SnekSegment, &Position>>,
+    food: Query<With<Food, &Position>>,
+) {
+    if !snek_timer.finished {
+        return;
+    }
+    if let Some(callback) = hook.0.as_mut() {
+        callback(&board_snapshot(
+            &score, &segments, &heads, &positions, &food,
+        ));
+    }
+}
+
+pub(crate) fn board_snapshot(
+    score: &Score,
+    segments: &SnekSegments,
+    heads: &Query<With<SnekHead, &Position>>,
+    positions: &Query<With<SnekSegment, &Position>>,
+    food: &Query<With<Food, &Position>>,
+) -> GameSnapshot {
+    GameSnapshot {
+        head: heads.iter().next().copied(),
+        segments: segments
+            .0
+            .iter()
+            .filter_map(|entity| positions.get(*entity).ok().copied())
+            .collect(),
+        food: food.iter().copied().collect(),
+        score: score.0,
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+
+use super::spawn::hud_text;
+use crate::components::{MenuText, ShopText};
+use crate::events::ResetGame;
+use crate::resources::{
+    GameState, Paused, PurchaseError, QuitPrompt, SaveFiles, Theme, UnlockedThemes, Wallet,
+};
+use crate::{HUD_FONT_PATH, UNLOCKS_PATH, WALLET_PATH};
+
+/// Keys that buy or pick the shop's themes, in the order of [`Theme::ALL`].
+const SHOP_KEYS: [KeyCode; 3] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3];
+
+/// The title and the line below it telling how to start.
+pub(crate) fn spawn_menu_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(HUD_FONT_PATH);
+    for &(line, left, top) in &[("SNEK", 46.0, 40.0), ("Press Enter to Start", 36.0, 46.0)] {
+        commands
+            .spawn(hud_text(
+                font.clone(),
+                Rect {
+                    left: Val::Percent(left),
+                    top: Val::Percent(top),
+                    ..Default::default()
+                },
+            ))
+            .with(MenuText(line));
+    }
+    for &(line, top) in &[(ShopText::Themes, 56.0), (ShopText::Notice, 61.0)] {
+        commands
+            .spawn(hud_text(
+                font.clone(),
+                Rect {
+                    left: Val::Percent(22.0),
+                    top: Val::Percent(top),
+                    ..Default::default()
+                },
+            ))
+            .with(line);
+    }
+}
+
+/// Holds the game paused on the title menu until `Enter` starts it with a fresh snake.
+///
+/// Runs after `tutorial`, so the `Enter` that leaves the menu doesn't also end the practice
+/// round.
+pub(crate) fn menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    prompt: Res<QuitPrompt>,
+    (mut state, mut paused): (ResMut<GameState>, ResMut<Paused>),
+    mut reset_events: ResMut<Events<ResetGame>>,
+    mut texts: Query<(&MenuText, &mut Text)>,
+) {
+    if *state == GameState::Menu && !prompt.open {
+        paused.0 = true;
+        if keyboard_input.just_pressed(KeyCode::Return) {
+            *state = GameState::Running;
+            paused.0 = false;
+            reset_events.send(ResetGame);
+        }
+    }
+    for (line, mut text) in texts.iter_mut() {
+        text.value = if *state == GameState::Menu {
+            line.0.to_string()
+        } else {
+            String::new()
+        };
+    }
+}
+
+/// The theme shop under the title menu: each of `1` to `3` buys its theme with coins from
+/// the [`Wallet`], or picks it once it is unlocked.
+///
+/// A purchase is saved at once, so coins spent are never lost to a crash mid-run.
+pub(crate) fn shop(
+    keyboard_input: Res<Input<KeyCode>>,
+    (state, prompt, save_files): (Res<GameState>, Res<QuitPrompt>, Res<SaveFiles>),
+    (mut wallet, mut unlocked, mut theme): (ResMut<Wallet>, ResMut<UnlockedThemes>, ResMut<Theme>),
+    mut notice: Local<String>,
+    mut texts: Query<(&ShopText, &mut Text)>,
+) {
+    if *state == GameState::Menu && !prompt.open {
+        for (&key, &wanted) in SHOP_KEYS.iter().zip(Theme::ALL.iter()) {
+            if !keyboard_input.just_pressed(key) {
+                continue;
+            }
+            *notice = match unlocked.buy(wanted, &mut wallet) {
+                Ok(()) => {
+                    if save_files.0 {
+                        if let Err(e) = wallet.save(WALLET_PATH) {
+                            eprintln!("failed to save wallet to {}: {}", WALLET_PATH, e);
+                        }
+                        if let Err(e) = unlocked.save(UNLOCKS_PATH) {
+                            eprintln!("failed to save unlocks to {}: {}", UNLOCKS_PATH, e);
+                        }
+                    }
+                    *theme = wanted;
+                    format!("Bought {}", wanted.name())
+                }
+                Err(PurchaseError::AlreadyUnlocked) => {
+                    *theme = wanted;
+                    format!("Picked {}", wanted.name())
+                }
+                Err(PurchaseError::TooFewCoins { price, coins }) => {
+                    format!(
+                        "{} costs {} coins, you have {}",
+                        wanted.name(),
+                        price,
+                        coins
+                    )
+                }
+            };
+        }
+    }
+    for (line, mut text) in texts.iter_mut() {
+        text.value = if *state != GameState::Menu {
+            String::new()
+        } else {
+            match line {
+                ShopText::Themes => shop_line(&wallet, &unlocked, *theme),
+                ShopText::Notice => notice.clone(),
+            }
+        };
+    }
+}
+
+/// The coins at hand and each theme's key, with its price until it is unlocked.
+fn shop_line(wallet: &Wallet, unlocked: &UnlockedThemes, picked: Theme) -> String {
+    let themes: Vec<String> = Theme::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, &theme)| {
+            let status = if theme == picked {
+                "on".to_string()
+            } else if unlocked.contains(theme) {
+                "owned".to_string()
+            } else {
+                theme.price().to_string()
+            };
+            format!("{} {} ({})", i + 1, theme.name(), status)
+        })
+        .collect();
+    format!("Coins {}  {}", wallet.0, themes.join("  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::resources::{GameState, Theme, UnlockedThemes, Wallet};
+    use crate::testing::{press_key, quiet_app, release_key};
+
+    fn tap(app: &mut App, key: KeyCode) {
+        press_key(app, key);
+        app.update();
+        release_key(app, key);
+        app.update();
+    }
+
+    #[test]
+    fn buying_a_theme_needs_enough_coins() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(GameState::Menu);
+            resources.insert(Wallet(Theme::Neon.price() - 1));
+        });
+        tap(&mut app, KeyCode::Key2);
+        assert_eq!(
+            app.resources.get::<Wallet>().unwrap().0,
+            Theme::Neon.price() - 1
+        );
+        assert!(!app
+            .resources
+            .get::<UnlockedThemes>()
+            .unwrap()
+            .contains(Theme::Neon));
+        assert_eq!(*app.resources.get::<Theme>().unwrap(), Theme::Classic);
+
+        app.resources.get_mut::<Wallet>().unwrap().0 += 1;
+        tap(&mut app, KeyCode::Key2);
+        assert_eq!(app.resources.get::<Wallet>().unwrap().0, 0);
+        assert!(app
+            .resources
+            .get::<UnlockedThemes>()
+            .unwrap()
+            .contains(Theme::Neon));
+        assert_eq!(*app.resources.get::<Theme>().unwrap(), Theme::Neon);
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(feature = "dev")]
+mod dev;
+mod eating;
+mod game_over;
+mod gamepad;
+mod gates;
+mod growth;
+mod hooks;
+mod menu;
+mod movement;
+mod music;
+mod pause;
+mod quit;
+mod render;
+mod rival;
+mod spawn;
+mod tutorial;
+
+#[cfg(feature = "dev")]
+pub(crate) use dev::*;
+pub(crate) use eating::*;
+pub(crate) use game_over::*;
+pub(crate) use gamepad::*;
+pub(crate) use gates::*;
+pub(crate) use growth::*;
+pub(crate) use hooks::*;
+pub(crate) use menu::*;
+pub(crate) use movement::*;
+pub(crate) use music::*;
+pub(crate) use pause::*;
+pub(crate) use quit::*;
+pub(crate) use render::*;
+pub(crate) use rival::*;
+pub(crate) use spawn::*;
+pub(crate) use tutorial::*;
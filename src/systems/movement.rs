@@ -0,0 +1,1282 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::spawn::random_free_cell;
+use crate::components::{
+    Coin, Direction, Enemy, Food, Ghost, MovePattern, Portal, Position, Projectile, Size, SnekHead,
+    SnekSegment, Wall,
+};
+use crate::events::{GameOverEvent, Player, ResetGame};
+use crate::resources::{
+    ArenaSize, BestRun, BodySpacing, Boost, BoostMode, CellPadding, DoubleScore, FixedStep,
+    FoodHoming, FoodRng, GamepadInput, InputAssist, Invincible, KeyBindings, LastTailPosition,
+    LoadedReplay, MagnetConfig, MagnetEffect, Materials, OneWayCells, PaintMode, Paused,
+    PendingGrowth, PhaseSelf, Portals, ReplayRecorder, ReversedControls, RhythmMode, RunTime,
+    SelfCollision, ShootTail, SlowConfig, SlowEffect, SnekMoveTimer, SnekPath, SnekSegments,
+    TiePolicy, TurnCooldown, WallBreakCharges, WallMode, WrapUnlockLength,
+};
+use crate::{BEAT_WINDOW, MAX_FRAME_DELTA, TURN_QUEUE_LEN};
+
+pub(crate) fn enemy_movement(
+    snek_timer: Res<SnekMoveTimer>,
+    arena: Res<ArenaSize>,
+    mut game_over_events: ResMut<Events<GameOverEvent>>,
+    mut enemies: Query<(&mut Enemy, &mut Position)>,
+    heads: Query<With<SnekHead, &Position>>,
+) {
+    if !snek_timer.finished {
+        return;
+    }
+    for (mut enemy, mut pos) in enemies.iter_mut() {
+        let MovePattern::Bounce { dx, dy } = &mut enemy.pattern;
+        if !(0..arena.width as i32).contains(&(pos.x + *dx)) {
+            *dx = -*dx;
+        }
+        if !(0..arena.height as i32).contains(&(pos.y + *dy)) {
+            *dy = -*dy;
+        }
+        pos.x += *dx;
+        pos.y += *dy;
+        if heads.iter().any(|head_pos| *head_pos == *pos) {
+            game_over_events.send(GameOverEvent(Player::One));
+        }
+    }
+}
+
+pub(crate) fn snek_movement(
+    mut commands: Commands,
+    (keyboard_input, gamepad): (Res<Input<KeyCode>>, Res<GamepadInput>),
+    snek_timer: ResMut<SnekMoveTimer>,
+    (turn_cooldown, tie_policy, bindings, mut recorder, replay): (
+        Res<TurnCooldown>,
+        Res<TiePolicy>,
+        Res<KeyBindings>,
+        ResMut<ReplayRecorder>,
+        Res<LoadedReplay>,
+    ),
+    mut held: Local<Vec<Direction>>,
+    mut game_over_events: ResMut<Events<GameOverEvent>>,
+    mut last_tail_position: ResMut<LastTailPosition>,
+    mut wall_breaks: ResMut<WallBreakCharges>,
+    (reversed, input_assist, invincible, phase, rhythm, paint): (
+        Res<ReversedControls>,
+        Res<InputAssist>,
+        Res<Invincible>,
+        Res<PhaseSelf>,
+        Res<RhythmMode>,
+        Res<PaintMode>,
+    ),
+    (arena, wall_mode, wrap_unlock, self_collision, one_way, food_rng): (
+        Res<ArenaSize>,
+        Res<WallMode>,
+        Res<WrapUnlockLength>,
+        Res<SelfCollision>,
+        Res<OneWayCells>,
+        Res<FoodRng>,
+    ),
+    (spacing, mut segments, mut path, mut pending): (
+        Res<BodySpacing>,
+        ResMut<SnekSegments>,
+        ResMut<SnekPath>,
+        ResMut<PendingGrowth>,
+    ),
+    mut heads: Query<(&mut SnekHead, &mut Position)>,
+    mut segment_positions: Query<With<SnekSegment, &mut Position>>,
+    enemies: Query<With<Enemy, &Position>>,
+    walls: Query<With<Wall, (Entity, &Position)>>,
+    portals: Query<(&Portal, &Position)>,
+) {
+    // A replay steers on its own, so keys pressed while watching are ignored.
+    let dir: Option<Direction> =
+        steer(&keyboard_input, &gamepad, &bindings, *tie_policy, &mut held)
+            .filter(|_| replay.0.is_none())
+            .filter(|_| !rhythm.0 || on_beat(&snek_timer))
+            // Turning is still checked against the actual heading below, so a reversed key
+            // can never send the snake back into itself.
+            .map(|dir| {
+                if reversed.is_active() {
+                    dir.opposite()
+                } else {
+                    dir
+                }
+            });
+    // Paint mode has nothing to die of, the same as while invincible.
+    let harmless = invincible.is_active() || paint.0;
+    if snek_timer.finished {
+        recorder.tick += 1;
+    }
+
+    for (mut head, mut pos) in heads.iter_mut() {
+        let current_direction = head.direction;
+        if let Some(dir) = dir {
+            // With assist the latest key replaces the last queued turn, and pressing the
+            // heading before it cancels that turn.
+            if input_assist.0 {
+                head.turns.pop_back();
+            }
+            // Each turn is checked against the one queued before it, which is the heading the
+            // snake will have by then.
+            let heading = head.turns.back().copied().unwrap_or(current_direction);
+            let room = head.turns.len() < TURN_QUEUE_LEN;
+            if room && dir != heading && dir != heading.opposite() {
+                head.turns.push_back(dir);
+            }
+        }
+
+        if snek_timer.finished {
+            head.ticks_since_turn = head.ticks_since_turn.saturating_add(1);
+            let mut dir = head.direction;
+            if let Some(next) = head.turns.pop_front() {
+                if head.ticks_since_turn > turn_cooldown.0 {
+                    dir = next;
+                    head.ticks_since_turn = 0;
+                } else {
+                    // The rest were lined up behind the turn just ignored, and could now
+                    // reverse the snake into itself.
+                    head.turns.clear();
+                }
+            }
+            if let Some(turn) = replay.0.as_ref().and_then(|log| log.turn_at(recorder.tick)) {
+                dir = turn;
+            }
+            if dir != head.direction {
+                let tick = recorder.tick;
+                recorder.turns.push((tick, dir));
+            }
+            head.direction = dir;
+            let from = *pos;
+            *pos = pos.step(dir);
+            let in_arena = arena.contains(*pos);
+            let wraps = *wall_mode == WallMode::Wrap || wrap_unlock.unlocked(&segments);
+            if !in_arena && (harmless || wraps) {
+                // The head goes through the edge and comes out on the other side.
+                *pos = arena.wrap(*pos);
+            } else if !in_arena && *wall_mode == WallMode::RandomTeleport {
+                // The path covers every cell the body is in now or moves into this tick.
+                let mut occupied: HashSet<Position> = path.0.iter().copied().collect();
+                occupied.extend(walls.iter().map(|(_, wall_pos)| *wall_pos));
+                // Without a free cell the head stays outside and dies like on a solid edge. The
+                // roll comes from the game's seed, so a replay lands in the same place.
+                let mut rng = food_rng.on_tick(recorder.tick);
+                if let Some(free) = random_free_cell(*arena, &occupied, &mut rng) {
+                    *pos = free;
+                }
+            } else if !in_arena && *wall_mode == WallMode::Reflect {
+                // With the head pushed onto the path, segment `i` takes the cell
+                // `(i + 1) * spacing` back along it, see below.
+                let reflected = Position {
+                    x: 2 * from.x - pos.x,
+                    y: 2 * from.y - pos.y,
+                };
+                let spacing = spacing.0.max(1) as usize;
+                let into_body =
+                    (1..=segments.0.len()).any(|i| path.0.get(i * spacing - 1) == Some(&reflected));
+                // Reversing into the body can't be allowed, so the head stays outside and
+                // dies like on a solid edge.
+                if !into_body {
+                    dir = dir.opposite();
+                    head.direction = dir;
+                    *pos = reflected;
+                }
+            }
+            // The path picks up the exit, so the body follows the head through.
+            if let Some((portal, _)) = portals.iter().find(|(_, portal_pos)| **portal_pos == *pos) {
+                *pos = portal.link;
+            }
+            // Whatever the edge did with the head, it dies on the tick it ends up outside
+            // rather than being drawn out there until the next one.
+            if !arena.contains(*pos) {
+                game_over_events.send(GameOverEvent(Player::One));
+            }
+            if enemies.iter().any(|enemy_pos| *enemy_pos == *pos) {
+                game_over_events.send(GameOverEvent(Player::One));
+            }
+            match one_way.allowed(*pos) {
+                Some(allowed) if allowed != dir => {
+                    game_over_events.send(GameOverEvent(Player::One))
+                }
+                _ => {}
+            }
+            if let Some((wall, _)) = walls.iter().find(|(_, wall_pos)| **wall_pos == *pos) {
+                if harmless {
+                    // Pass over the wall without spending a charge on it.
+                } else if wall_breaks.0 > 0 {
+                    wall_breaks.0 -= 1;
+                    commands.despawn(wall);
+                } else {
+                    game_over_events.send(GameOverEvent(Player::One));
+                }
+            }
+            // Segment `i` sits `(i + 1) * spacing` cells back along the path of the head.
+            let spacing = spacing.0.max(1) as usize;
+            path.0.push_front(*pos);
+            let mut cut = None;
+            for (i, entity) in segments.0.iter().enumerate() {
+                if let Ok(mut segment_pos) = segment_positions.get_mut(*entity) {
+                    // The head only hits a segment where that segment ends up this tick, so
+                    // it may follow the tail into the cell the tail is leaving.
+                    let next = path
+                        .0
+                        .get((i + 1) * spacing)
+                        .copied()
+                        .unwrap_or(*segment_pos);
+                    if next == *pos && !harmless && !phase.is_active() {
+                        match *self_collision {
+                            SelfCollision::Die => game_over_events.send(GameOverEvent(Player::One)),
+                            SelfCollision::Cut => cut = cut.or(Some(i)),
+                        }
+                    } else {
+                        *segment_pos = next;
+                    }
+                }
+            }
+            if let Some(cut) = cut {
+                for entity in segments.0.drain(cut..) {
+                    commands.despawn(entity);
+                }
+            }
+            let next_segment = (segments.0.len() + 1) * spacing;
+            // Owed growth appears on the cell behind the tail this tick, so that one is not
+            // free to move into after all.
+            if pending.0 > 0
+                && path.0.get(next_segment) == Some(&*pos)
+                && !harmless
+                && !phase.is_active()
+            {
+                match *self_collision {
+                    SelfCollision::Die => game_over_events.send(GameOverEvent(Player::One)),
+                    // There is nothing to cut yet, so the owed segments are dropped instead.
+                    SelfCollision::Cut => pending.0 = 0,
+                }
+            }
+            // The path keeps a spacing's worth of cells past the tail, so a segment grown there
+            // still has a cell to take on the next tick.
+            path.0.truncate(next_segment + spacing);
+            last_tail_position.0 = path.0.get(next_segment).copied();
+        }
+    }
+}
+
+/// Whether the move timer is within `BEAT_WINDOW` of a tick, on either side of it.
+pub(crate) fn on_beat(timer: &Timer) -> bool {
+    let phase = timer.elapsed / timer.duration;
+    timer.finished || !(BEAT_WINDOW..=1. - BEAT_WINDOW).contains(&phase)
+}
+
+/// Picks the direction to steer by, settling opposite directions held together with `policy`.
+///
+/// `held` keeps the directions in the order their keys went down, so the outcome never depends
+/// on how `Input` happens to iterate. Keys going down in the same frame are ordered Left, Up,
+/// Right, Down. A direction counts as held while any of its bound keys is, or while a gamepad
+/// holds it.
+fn steer(
+    keyboard_input: &Input<KeyCode>,
+    gamepad: &GamepadInput,
+    bindings: &KeyBindings,
+    policy: TiePolicy,
+    held: &mut Vec<Direction>,
+) -> Option<Direction> {
+    let down = |dir: Direction| {
+        gamepad.held.contains(&dir)
+            || bindings
+                .keys()
+                .any(|(key, bound)| bound == dir && keyboard_input.pressed(key))
+    };
+    held.retain(|dir| down(*dir));
+    for dir in Direction::ALL.iter().copied() {
+        if down(dir) && !held.contains(&dir) {
+            held.push(dir);
+        }
+    }
+    let conflicting = |dir: &Direction| held.contains(&dir.opposite());
+    match policy {
+        TiePolicy::KeepCurrent if held.iter().any(conflicting) => None,
+        TiePolicy::KeepCurrent => held.first().copied(),
+        TiePolicy::Ignore => held.iter().copied().find(|dir| !conflicting(dir)),
+        // The first held key either has no opposite held or went down before it.
+        TiePolicy::FirstPressed => held.first().copied(),
+    }
+}
+
+/// How far gameplay timers advance this frame.
+///
+/// The first frame, and any frame after a stall like a window drag or shader compile, can
+/// report a huge delta; clamping it keeps timers from lurching forward all at once.
+pub(crate) fn gameplay_delta(time: &Time) -> f32 {
+    time.delta_seconds.min(MAX_FRAME_DELTA)
+}
+
+/// Swaps in the [`FixedStep`], if any, for the frame time the clock just measured.
+pub(crate) fn fixed_step(step: Res<FixedStep>, mut time: ResMut<Time>) {
+    if let Some(step) = step.0 {
+        time.delta = step;
+        time.delta_seconds = step.as_secs_f32();
+        time.delta_seconds_f64 = step.as_secs_f64();
+    }
+}
+
+/// Despawns dropped coins nobody picked up in time, and all of them when the game restarts.
+pub(crate) fn coin_lifetime(
+    mut commands: Commands,
+    time: Res<Time>,
+    paused: Res<Paused>,
+    (mut reader, reset_events): (Local<EventReader<ResetGame>>, Res<Events<ResetGame>>),
+    mut coins: Query<(Entity, &mut Coin)>,
+) {
+    let reset = reader.iter(&reset_events).next().is_some();
+    for (entity, mut coin) in coins.iter_mut() {
+        if !paused.0 {
+            coin.lifetime.tick(gameplay_delta(&time));
+        }
+        if reset || coin.lifetime.finished {
+            commands.despawn(entity);
+        }
+    }
+}
+
+pub(crate) fn magnet_timer(time: Res<Time>, paused: Res<Paused>, mut magnet: ResMut<MagnetEffect>) {
+    if !paused.0 {
+        magnet.0.tick(gameplay_delta(&time));
+    }
+}
+
+pub(crate) fn run_timer(time: Res<Time>, paused: Res<Paused>, mut run_time: ResMut<RunTime>) {
+    if !paused.0 {
+        run_time.0 += time.delta.min(Duration::from_secs_f32(MAX_FRAME_DELTA));
+    }
+}
+
+pub(crate) fn invincible_timer(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    mut invincible: ResMut<Invincible>,
+) {
+    if !paused.0 {
+        invincible.0.tick(gameplay_delta(&time));
+    }
+}
+
+pub(crate) fn slow_timer(time: Res<Time>, paused: Res<Paused>, mut slow: ResMut<SlowEffect>) {
+    if !paused.0 {
+        slow.0.tick(gameplay_delta(&time));
+    }
+}
+
+pub(crate) fn phase_timer(time: Res<Time>, paused: Res<Paused>, mut phase: ResMut<PhaseSelf>) {
+    if !paused.0 {
+        phase.0.tick(gameplay_delta(&time));
+    }
+}
+
+pub(crate) fn double_score_timer(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    mut double_score: ResMut<DoubleScore>,
+) {
+    if !paused.0 {
+        double_score.0.tick(gameplay_delta(&time));
+    }
+}
+
+pub(crate) fn reversed_controls_timer(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    mut reversed: ResMut<ReversedControls>,
+) {
+    if !paused.0 {
+        reversed.0.tick(gameplay_delta(&time));
+    }
+}
+
+pub(crate) fn magnet_pull(
+    snek_timer: Res<SnekMoveTimer>,
+    magnet: Res<MagnetEffect>,
+    config: Res<MagnetConfig>,
+    (one_way, portals): (Res<OneWayCells>, Res<Portals>),
+    heads: Query<With<SnekHead, &Position>>,
+    segments: Query<With<SnekSegment, &Position>>,
+    walls: Query<With<Wall, &Position>>,
+    mut food: Query<With<Food, &mut Position>>,
+) {
+    if !snek_timer.finished || !magnet.is_active() {
+        return;
+    }
+    let head = match heads.iter().next() {
+        Some(head) => *head,
+        None => return,
+    };
+    let mut blocked: HashSet<Position> = segments.iter().chain(walls.iter()).copied().collect();
+    blocked.extend(food.iter_mut().map(|pos| *pos));
+    blocked.extend(one_way.positions());
+    blocked.extend(portals.positions());
+    for mut pos in food.iter_mut() {
+        let (dx, dy) = (head.x - pos.x, head.y - pos.y);
+        if (dx.abs() + dy.abs()) as u32 > config.radius {
+            continue;
+        }
+        if let Some(&target) = steps_toward(*pos, head).first() {
+            if !blocked.contains(&target) {
+                blocked.remove(&*pos);
+                blocked.insert(target);
+                *pos = target;
+            }
+        }
+    }
+}
+
+/// Every `FoodHoming` ticks, moves each food a cell closer to the head, around whatever
+/// blocks the direct way if it can.
+pub(crate) fn food_homing(
+    snek_timer: Res<SnekMoveTimer>,
+    homing: Res<FoodHoming>,
+    (one_way, portals): (Res<OneWayCells>, Res<Portals>),
+    mut ticks: Local<u32>,
+    heads: Query<With<SnekHead, &Position>>,
+    segments: Query<With<SnekSegment, &Position>>,
+    walls: Query<With<Wall, &Position>>,
+    mut food: Query<With<Food, &mut Position>>,
+) {
+    let interval = match homing.0 {
+        Some(interval) if interval > 0 => interval,
+        _ => return,
+    };
+    if !snek_timer.finished {
+        return;
+    }
+    *ticks += 1;
+    if *ticks < interval {
+        return;
+    }
+    *ticks = 0;
+    let head = match heads.iter().next() {
+        Some(head) => *head,
+        None => return,
+    };
+    let mut blocked: HashSet<Position> = segments.iter().chain(walls.iter()).copied().collect();
+    blocked.extend(food.iter_mut().map(|pos| *pos));
+    blocked.extend(one_way.positions());
+    blocked.extend(portals.positions());
+    for mut pos in food.iter_mut() {
+        let free = steps_toward(*pos, head)
+            .into_iter()
+            .find(|target| !blocked.contains(target));
+        if let Some(target) = free {
+            blocked.remove(&*pos);
+            blocked.insert(target);
+            *pos = target;
+        }
+    }
+}
+
+/// The cells next to `from` that are closer to `to`, closing the larger gap first so
+/// whatever takes them approaches along a straight line.
+fn steps_toward(from: Position, to: Position) -> Vec<Position> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let horizontal = Position {
+        x: from.x + dx.signum(),
+        y: from.y,
+    };
+    let vertical = Position {
+        x: from.x,
+        y: from.y + dy.signum(),
+    };
+    let steps = if dx.abs() >= dy.abs() {
+        [horizontal, vertical]
+    } else {
+        [vertical, horizontal]
+    };
+    steps.iter().copied().filter(|&step| step != from).collect()
+}
+
+pub(crate) fn snek_timer(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    (mode, boost): (Res<BoostMode>, Res<Boost>),
+    (slow_config, slow): (Res<SlowConfig>, Res<SlowEffect>),
+    mut snek_timer: ResMut<SnekMoveTimer>,
+) {
+    if paused.0 {
+        // Clear a tick that finished right before pausing so nothing moves while paused.
+        snek_timer.finished = false;
+        snek_timer.just_finished = false;
+        return;
+    }
+    // Running the timer faster shortens the tick without touching the interval the speed
+    // ramp sets.
+    let speedup = match &mode.0 {
+        Some(config) if boost.active => config.speedup,
+        _ => 1.0,
+    };
+    let slowdown = if slow.is_active() {
+        slow_config.speed
+    } else {
+        1.0
+    };
+    snek_timer
+        .0
+        .tick(gameplay_delta(&time) * speedup * slowdown);
+}
+
+/// Under [`ShootTail`], turns the tail segment into a projectile just ahead of the head on `X`.
+pub(crate) fn shoot_tail(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    (shoot, paused): (Res<ShootTail>, Res<Paused>),
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    (mut segments, mut last_tail_position): (ResMut<SnekSegments>, ResMut<LastTailPosition>),
+    heads: Query<(&SnekHead, &Position)>,
+    positions: Query<With<SnekSegment, &Position>>,
+) {
+    if !shoot.0 || paused.0 || !keyboard_input.just_pressed(KeyCode::X) {
+        return;
+    }
+    if segments.0.len() < 2 {
+        return;
+    }
+    let (head, head_pos) = match heads.iter().next() {
+        Some(head) => head,
+        None => return,
+    };
+    if let Some(tail) = segments.0.pop() {
+        // The cell the tail sat on is free again, as when poison takes it.
+        if let Ok(pos) = positions.get(tail) {
+            last_tail_position.0 = Some(*pos);
+        }
+        commands.despawn(tail);
+    }
+    commands
+        .spawn(SpriteComponents {
+            material: materials.segment_material.clone(),
+            ..Default::default()
+        })
+        .with(Projectile {
+            direction: head.direction,
+        })
+        .with(head_pos.step(head.direction))
+        .with(Size::square(padding.segment * 0.6));
+}
+
+/// Moves every projectile on each tick, despawning it with the first wall or enemy it
+/// reaches, or once it is out of the arena.
+pub(crate) fn projectile_flight(
+    mut commands: Commands,
+    snek_timer: Res<SnekMoveTimer>,
+    arena: Res<ArenaSize>,
+    mut projectiles: Query<(Entity, &Projectile, &mut Position)>,
+    walls: Query<With<Wall, (Entity, &Position)>>,
+    enemies: Query<With<Enemy, (Entity, &Position)>>,
+) {
+    for (entity, projectile, mut pos) in projectiles.iter_mut() {
+        // Fired this frame, it still checks the cell it appeared on before moving.
+        if snek_timer.finished {
+            *pos = pos.step(projectile.direction);
+        }
+        let in_arena = arena.contains(*pos);
+        let target = walls
+            .iter()
+            .chain(enemies.iter())
+            .find(|(_, target_pos)| **target_pos == *pos)
+            .map(|(target, _)| target);
+        if let Some(target) = target {
+            commands.despawn(target);
+        }
+        if target.is_some() || !in_arena {
+            commands.despawn(entity);
+        }
+    }
+}
+
+/// Boosts while Shift is held and the meter lasts, and refills the meter once it is released.
+pub(crate) fn boost_meter(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    (paused, mode): (Res<Paused>, Res<BoostMode>),
+    mut boost: ResMut<Boost>,
+) {
+    let config = match &mode.0 {
+        Some(config) if !paused.0 => config,
+        _ => {
+            boost.active = false;
+            return;
+        }
+    };
+    let delta = gameplay_delta(&time);
+    let held = keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
+    // Holding on with an empty meter keeps it from refilling, so boosting doesn't flicker
+    // back on for a frame at a time.
+    boost.active = held && boost.meter > 0.;
+    boost.meter = if boost.active {
+        (boost.meter - config.drain * delta).max(0.)
+    } else if !held {
+        (boost.meter + config.regen * delta).min(1.)
+    } else {
+        boost.meter
+    };
+}
+
+pub(crate) fn ghost_movement(
+    mut commands: Commands,
+    snek_timer: Res<SnekMoveTimer>,
+    materials: Res<Materials>,
+    padding: Res<CellPadding>,
+    mut best: ResMut<BestRun>,
+    mut ghosts: Query<With<Ghost, &mut Position>>,
+) {
+    if best.path.is_empty() {
+        return;
+    }
+    let mut ghost = match ghosts.iter_mut().next() {
+        Some(ghost) => ghost,
+        None => {
+            commands
+                .spawn(SpriteComponents {
+                    material: materials.ghost_material.clone(),
+                    ..Default::default()
+                })
+                .with(Ghost)
+                .with(best.path[0])
+                .with(Size::square(padding.head));
+            return;
+        }
+    };
+    if snek_timer.finished {
+        // The ghost stops at the end of its recorded path.
+        if let Some(pos) = best.path.get(best.tick).copied() {
+            *ghost = pos;
+            best.tick += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use crate::components::{Direction, FoodKind, Position, SnekHead};
+    use crate::events::GameOverEvent;
+    use crate::resources::{
+        ArenaSize, BodySpacing, Boost, BoostConfig, BoostMode, FixedStep, FoodHoming, FoodSeed,
+        GamepadInput, GrowthMode, InputAssist, KeyBindings, OneWay, OneWayCells, SelfCollision,
+        ShootTail, TiePolicy, TronMode, TurnCooldown, WallBreakCharges, WallMode, WrapUnlockLength,
+    };
+    use crate::testing::{
+        body, food, head, place_food, place_wall, press_key, quiet_app, release_key, run_tick,
+        run_tick_counting, run_ticks, walls,
+    };
+
+    /// Taps `key` over two frames, too few for the snake to move.
+    fn tap(app: &mut App, key: KeyCode) {
+        press_key(app, key);
+        app.update();
+        release_key(app, key);
+        app.update();
+    }
+
+    /// Taps `key` and has the snake make its next move.
+    fn tap_and_tick(app: &mut App, key: KeyCode) {
+        press_key(app, key);
+        run_tick(app);
+        release_key(app, key);
+    }
+
+    #[test]
+    fn turning_back_on_itself_is_ignored() {
+        let mut app = quiet_app(|_| ());
+        assert_eq!(head(&app), (Position { x: 3, y: 3 }, Direction::Up));
+
+        tap_and_tick(&mut app, KeyCode::Down);
+        assert_eq!(head(&app), (Position { x: 3, y: 4 }, Direction::Up));
+        assert_eq!(body(&app), vec![Position { x: 3, y: 3 }]);
+    }
+
+    #[test]
+    fn leaving_a_solid_arena_ends_the_game() {
+        let mut app = quiet_app(|_| ());
+        // From 3 up to the top row at 9 is still inside.
+        for _ in 0..6 {
+            assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 0);
+        }
+        assert_eq!(head(&app).0, Position { x: 3, y: 9 });
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 1);
+    }
+
+    #[test]
+    fn wrapping_arena_brings_the_head_back_at_the_bottom() {
+        let mut app = quiet_app(|resources| resources.insert(WallMode::Wrap));
+        for _ in 0..6 {
+            run_tick(&mut app);
+        }
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 0);
+        assert_eq!(head(&app).0, Position { x: 3, y: 0 });
+    }
+
+    #[test]
+    fn body_follows_the_cells_the_head_went_through() {
+        let mut app = quiet_app(|_| ());
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        let mut trail = vec![Position { x: 3, y: 2 }, Position { x: 3, y: 3 }];
+        let keys = [
+            None,
+            None,
+            Some(KeyCode::Right),
+            None,
+            Some(KeyCode::Down),
+            None,
+            Some(KeyCode::Left),
+        ];
+        for key in keys.iter() {
+            match key {
+                Some(key) => tap_and_tick(&mut app, *key),
+                None => {
+                    run_tick(&mut app);
+                }
+            }
+            trail.push(head(&app).0);
+            let body = body(&app);
+            let behind: Vec<Position> = trail
+                .iter()
+                .rev()
+                .skip(1)
+                .take(body.len())
+                .copied()
+                .collect();
+            assert_eq!(body, behind);
+        }
+        assert_eq!(body(&app).len(), 3);
+        assert_eq!(head(&app), (Position { x: 4, y: 3 }, Direction::Left));
+    }
+
+    #[test]
+    fn quick_turns_queue_against_the_turn_before_them() {
+        let mut app = quiet_app(|_| ());
+        run_tick(&mut app);
+        // All three land between two ticks.
+        for &key in &[KeyCode::Right, KeyCode::Down, KeyCode::Left] {
+            tap(&mut app, key);
+        }
+        let turns: Vec<Direction> = app
+            .world
+            .query::<&SnekHead>()
+            .next()
+            .unwrap()
+            .turns
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(
+            turns,
+            vec![Direction::Right, Direction::Down, Direction::Left]
+        );
+
+        let mut heading = head(&app).1;
+        for &expected in &[Direction::Right, Direction::Down, Direction::Left] {
+            run_tick(&mut app);
+            let turned = head(&app).1;
+            assert_ne!(turned, heading.opposite());
+            assert_eq!(turned, expected);
+            heading = turned;
+        }
+    }
+
+    #[test]
+    fn turns_within_the_cooldown_are_ignored() {
+        let mut app = quiet_app(|resources| resources.insert(TurnCooldown(2)));
+        tap_and_tick(&mut app, KeyCode::Right);
+        assert_eq!(head(&app).1, Direction::Right);
+        // The two ticks after a turn are the cooldown.
+        for _ in 0..2 {
+            tap_and_tick(&mut app, KeyCode::Up);
+            assert_eq!(head(&app).1, Direction::Right);
+        }
+        tap_and_tick(&mut app, KeyCode::Up);
+        assert_eq!(head(&app).1, Direction::Up);
+    }
+
+    #[test]
+    fn a_wall_break_charge_is_spent_on_the_wall_ahead() {
+        let mut app = quiet_app(|resources| resources.insert(WallBreakCharges(1)));
+        let ahead = Position { x: 3, y: 4 };
+        place_wall(&mut app, ahead);
+
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 0);
+        assert_eq!(head(&app).0, ahead);
+        assert!(walls(&app).is_empty());
+        assert_eq!(app.resources.get::<WallBreakCharges>().unwrap().0, 0);
+
+        // Without a charge left the next wall is deadly again.
+        place_wall(&mut app, Position { x: 3, y: 5 });
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 1);
+    }
+
+    #[test]
+    fn spaced_body_sits_on_every_other_cell_of_the_path() {
+        let mut app = quiet_app(|resources| resources.insert(BodySpacing(2)));
+        assert_eq!(body(&app), vec![Position { x: 3, y: 1 }]);
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 5 });
+        let mut trail: Vec<Position> = (-1..=3).map(|y| Position { x: 3, y }).collect();
+        let keys = [
+            None,
+            None,
+            Some(KeyCode::Right),
+            None,
+            None,
+            Some(KeyCode::Down),
+        ];
+        for key in keys.iter() {
+            match key {
+                Some(key) => tap_and_tick(&mut app, *key),
+                None => {
+                    run_tick(&mut app);
+                }
+            }
+            trail.push(head(&app).0);
+            let body = body(&app);
+            let behind: Vec<Position> = trail
+                .iter()
+                .rev()
+                .skip(2)
+                .step_by(2)
+                .take(body.len())
+                .copied()
+                .collect();
+            assert_eq!(body, behind);
+        }
+        assert_eq!(body(&app).len(), 3);
+    }
+
+    /// Eats the food ahead and runs a tight loop back into the cell the snake started on.
+    fn loop_back(spacing: u32) -> usize {
+        let mut app = quiet_app(|resources| resources.insert(BodySpacing(spacing)));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        run_tick(&mut app);
+        tap_and_tick(&mut app, KeyCode::Right);
+        tap_and_tick(&mut app, KeyCode::Down);
+        press_key(&mut app, KeyCode::Left);
+        let crashes = run_tick_counting::<GameOverEvent>(&mut app);
+        assert_eq!(head(&app).0, Position { x: 3, y: 3 });
+        crashes
+    }
+
+    #[test]
+    fn spaced_body_reaches_twice_as_far_back() {
+        // Two segments cover two cells of the path contiguously, and four spaced out.
+        assert_eq!(loop_back(1), 0);
+        assert_eq!(loop_back(2), 1);
+    }
+
+    /// Where the snake heads after Right and then Left are tapped between two ticks.
+    fn heading_after_right_then_left(assist: bool) -> Direction {
+        let mut app = quiet_app(|resources| resources.insert(InputAssist(assist)));
+        run_tick(&mut app);
+        tap(&mut app, KeyCode::Right);
+        tap(&mut app, KeyCode::Left);
+        run_tick(&mut app);
+        head(&app).1
+    }
+
+    #[test]
+    fn input_assist_lets_the_last_turn_win() {
+        // Left is a reversal of the queued Right, unless assist takes Right back first.
+        assert_eq!(heading_after_right_then_left(false), Direction::Right);
+        assert_eq!(heading_after_right_then_left(true), Direction::Left);
+    }
+
+    /// Runs a 4 by 5 arena off its top edge under [`WallMode::RandomTeleport`], with every
+    /// cell but `free` walled off, and returns the crashes and where the head ended up.
+    fn teleport_with_only(free: Option<Position>) -> (usize, Position) {
+        let arena = ArenaSize {
+            width: 4,
+            height: 5,
+        };
+        let mut app = quiet_app(|resources| {
+            resources.insert(arena);
+            resources.insert(WallMode::RandomTeleport);
+        });
+        run_tick(&mut app);
+        // The body's cells, now and after the next move, are taken already.
+        let path: Vec<Position> = (2..=4).map(|y| Position { x: 3, y }).collect();
+        for cell in arena.cells() {
+            if Some(cell) != free && !path.contains(&cell) {
+                place_wall(&mut app, cell);
+            }
+        }
+        let crashes = run_tick_counting::<GameOverEvent>(&mut app);
+        (crashes, head(&app).0)
+    }
+
+    #[test]
+    fn random_teleport_lands_on_the_free_cell() {
+        let free = Position { x: 0, y: 0 };
+        assert_eq!(teleport_with_only(Some(free)), (0, free));
+        // With nowhere to go the head stays out past the edge.
+        assert_eq!(teleport_with_only(None), (1, Position { x: 3, y: 5 }));
+    }
+
+    #[test]
+    fn random_teleport_is_the_same_for_the_same_seed() {
+        let landing = |seed| {
+            let mut app = quiet_app(|resources| {
+                resources.insert(WallMode::RandomTeleport);
+                resources.insert(FoodSeed(Some(seed)));
+            });
+            for _ in 0..8 {
+                run_tick(&mut app);
+            }
+            head(&app).0
+        };
+        assert_eq!(landing(3), landing(3));
+    }
+
+    #[test]
+    fn invincible_snake_survives_its_body_and_walls() {
+        let mut app = quiet_app(|_| ());
+        for y in 4..=6 {
+            place_food(&mut app, FoodKind::Normal, Position { x: 3, y });
+        }
+        place_food(&mut app, FoodKind::Invincible, Position { x: 3, y: 7 });
+        place_wall(&mut app, Position { x: 2, y: 6 });
+        let mut crashes = 0;
+        for _ in 0..4 {
+            crashes += run_tick_counting::<GameOverEvent>(&mut app);
+        }
+        assert_eq!(body(&app).len(), 5);
+        // A tight loop back into the body, and on through the wall.
+        for &key in &[KeyCode::Right, KeyCode::Down, KeyCode::Left] {
+            press_key(&mut app, key);
+            crashes += run_tick_counting::<GameOverEvent>(&mut app);
+            release_key(&mut app, key);
+        }
+        assert!(body(&app).contains(&Position { x: 3, y: 6 }));
+        crashes += run_tick_counting::<GameOverEvent>(&mut app);
+        assert_eq!(head(&app).0, Position { x: 2, y: 6 });
+        assert_eq!(crashes, 0);
+    }
+
+    #[test]
+    fn phasing_snake_crosses_its_body_but_not_a_wall() {
+        let mut app = quiet_app(|_| ());
+        for y in 4..=6 {
+            place_food(&mut app, FoodKind::Normal, Position { x: 3, y });
+        }
+        place_food(&mut app, FoodKind::Phase, Position { x: 3, y: 7 });
+        place_wall(&mut app, Position { x: 2, y: 6 });
+        run_ticks(&mut app, 4);
+        assert_eq!(body(&app).len(), 5);
+        let mut crashes = 0;
+        for &key in &[KeyCode::Right, KeyCode::Down, KeyCode::Left] {
+            press_key(&mut app, key);
+            crashes += run_tick_counting::<GameOverEvent>(&mut app);
+            release_key(&mut app, key);
+        }
+        assert_eq!(crashes, 0);
+        assert!(body(&app).contains(&Position { x: 3, y: 6 }));
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 1);
+    }
+
+    #[test]
+    fn cutting_keeps_the_segments_before_the_one_hit() {
+        let mut app = quiet_app(|resources| resources.insert(SelfCollision::Cut));
+        for y in 4..=7 {
+            place_food(&mut app, FoodKind::Normal, Position { x: 3, y });
+        }
+        run_ticks(&mut app, 4);
+        assert_eq!(body(&app).len(), 5);
+        // On a grid the head can only come back round onto every other segment; a tight loop
+        // reaches the one at index 3.
+        let mut crashes = 0;
+        for &key in &[KeyCode::Right, KeyCode::Down, KeyCode::Left] {
+            press_key(&mut app, key);
+            crashes += run_tick_counting::<GameOverEvent>(&mut app);
+            release_key(&mut app, key);
+        }
+        app.update();
+        assert_eq!(crashes, 0);
+        assert_eq!(head(&app).0, Position { x: 3, y: 6 });
+        assert_eq!(
+            body(&app),
+            vec![
+                Position { x: 4, y: 6 },
+                Position { x: 4, y: 7 },
+                Position { x: 3, y: 7 },
+            ]
+        );
+    }
+
+    /// What `policy` steers by over frames that each hold down the keys listed for them.
+    fn steer_over(policy: TiePolicy, frames: &[&[KeyCode]]) -> Vec<Option<Direction>> {
+        let (gamepad, bindings) = (GamepadInput::default(), KeyBindings::default());
+        let mut held = Vec::new();
+        frames
+            .iter()
+            .map(|keys| {
+                let mut input = Input::<KeyCode>::default();
+                keys.iter().for_each(|key| input.press(*key));
+                super::steer(&input, &gamepad, &bindings, policy, &mut held)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn opposite_keys_together_follow_the_tie_policy() {
+        use KeyCode::{Left, Right, Up};
+        let both: &[KeyCode] = &[Left, Right];
+        let with_up: &[KeyCode] = &[Left, Right, Up];
+        assert_eq!(
+            steer_over(TiePolicy::KeepCurrent, &[both, with_up]),
+            vec![None, None]
+        );
+        assert_eq!(
+            steer_over(TiePolicy::Ignore, &[both, with_up]),
+            vec![None, Some(Direction::Up)]
+        );
+        // Pressed in the same frame, the first of Left, Up, Right and Down wins.
+        assert_eq!(
+            steer_over(TiePolicy::FirstPressed, &[both]),
+            vec![Some(Direction::Left)]
+        );
+        assert_eq!(
+            steer_over(TiePolicy::FirstPressed, &[&[Right], both]),
+            vec![Some(Direction::Right), Some(Direction::Right)]
+        );
+    }
+
+    #[test]
+    fn tron_trail_cells_are_lethal() {
+        let mut app = quiet_app(|resources| resources.insert(TronMode(true)));
+        run_ticks(&mut app, 2);
+        let trail = walls(&app);
+        assert!(trail.contains(&Position { x: 3, y: 2 }));
+        assert!(trail.contains(&Position { x: 3, y: 3 }));
+
+        // Loop back down into (3, 4), which the tail has just left.
+        tap_and_tick(&mut app, KeyCode::Right);
+        tap_and_tick(&mut app, KeyCode::Down);
+        assert!(walls(&app).contains(&Position { x: 3, y: 4 }));
+        press_key(&mut app, KeyCode::Left);
+        assert_eq!(run_tick_counting::<GameOverEvent>(&mut app), 1);
+    }
+
+    /// Whether the snake dies stepping `heading` onto a one-way cell whose arrow points
+    /// `allowed`.
+    fn dies_entering_one_way(heading: Direction, allowed: Direction) -> bool {
+        let mut app = quiet_app(|_| ());
+        // The snake can't turn straight back, so it goes down by way of the right.
+        let turns: &[KeyCode] = match heading {
+            Direction::Up => &[],
+            Direction::Left => &[KeyCode::Left],
+            Direction::Right => &[KeyCode::Right],
+            Direction::Down => &[KeyCode::Right, KeyCode::Down],
+        };
+        for &key in turns {
+            tap_and_tick(&mut app, key);
+        }
+        let (pos, dir) = head(&app);
+        assert_eq!(dir, heading);
+        let (dx, dy) = heading.delta();
+        app.resources.insert(OneWayCells(vec![OneWay {
+            position: Position {
+                x: pos.x + dx,
+                y: pos.y + dy,
+            },
+            allowed,
+        }]));
+        run_tick_counting::<GameOverEvent>(&mut app) == 1
+    }
+
+    #[test]
+    fn one_way_cells_only_let_the_snake_in_along_their_arrow() {
+        for &heading in &Direction::ALL {
+            for &allowed in &Direction::ALL {
+                assert_eq!(
+                    dies_entering_one_way(heading, allowed),
+                    allowed != heading,
+                    "heading {:?} onto a cell allowing {:?}",
+                    heading,
+                    allowed
+                );
+            }
+        }
+    }
+
+    /// Whether the two-cell snake dies on leaving the top edge with `unlock` set.
+    fn dies_at_the_edge_with(unlock: u32) -> bool {
+        let mut app = quiet_app(|resources| resources.insert(WrapUnlockLength(Some(unlock))));
+        run_ticks(&mut app, 6);
+        run_tick_counting::<GameOverEvent>(&mut app) == 1
+    }
+
+    #[test]
+    fn edges_wrap_once_the_snake_is_long_enough() {
+        assert!(dies_at_the_edge_with(3));
+        assert!(!dies_at_the_edge_with(2));
+        assert!(!dies_at_the_edge_with(1));
+
+        let mut app = quiet_app(|resources| resources.insert(WrapUnlockLength(Some(2))));
+        run_ticks(&mut app, 7);
+        assert_eq!(head(&app).0, Position { x: 3, y: 0 });
+    }
+
+    #[test]
+    fn a_long_frame_moves_the_snake_one_cell_at_most() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(FixedStep(Some(Duration::from_secs(5))));
+        });
+        let mut y = head(&app).0.y;
+        for frame in 0..6 {
+            app.update();
+            let moved = head(&app).0.y - y;
+            assert!(moved <= 1, "{} cells on frame {}", moved, frame);
+            y += moved;
+        }
+        // Clamped to a tenth of a second each, six frames only make two moves.
+        assert_eq!(y, 5);
+    }
+
+    /// Steers the snake right into the right edge under [`WallMode::Reflect`], returning how
+    /// many games ended on the tick it gets there.
+    fn run_right_into_a_mirror(app: &mut App) -> usize {
+        tap_and_tick(app, KeyCode::Right);
+        run_ticks(app, 5);
+        assert_eq!(head(app), (Position { x: 9, y: 3 }, Direction::Right));
+        run_tick_counting::<GameOverEvent>(app)
+    }
+
+    #[test]
+    fn a_mirror_edge_bounces_the_head_back_the_way_it_came() {
+        let mut app = quiet_app(|resources| resources.insert(WallMode::Reflect));
+        assert_eq!(run_right_into_a_mirror(&mut app), 0);
+        assert_eq!(head(&app), (Position { x: 8, y: 3 }, Direction::Left));
+        run_tick(&mut app);
+        assert_eq!(head(&app), (Position { x: 7, y: 3 }, Direction::Left));
+
+        // The neck moves up to where the head was, but two cells back it is in the way.
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Reflect);
+            resources.insert(BodySpacing(2));
+        });
+        assert_eq!(run_right_into_a_mirror(&mut app), 1);
+    }
+
+    #[test]
+    fn homing_food_creeps_a_cell_closer_every_interval() {
+        let mut app = quiet_app(|resources| resources.insert(FoodHoming(Some(2))));
+        place_food(&mut app, FoodKind::Normal, Position { x: 9, y: 4 });
+        let gap = |from: Position, to: Position| (from.x - to.x).abs() + (from.y - to.y).abs();
+        let mut before = food(&app)[0].0;
+        for tick in 1..=6 {
+            run_tick(&mut app);
+            let (after, head) = (food(&app)[0].0, head(&app).0);
+            if tick % 2 == 0 {
+                assert_eq!(gap(after, before), 1, "on tick {}", tick);
+                assert!(gap(after, head) < gap(before, head), "on tick {}", tick);
+            } else {
+                assert_eq!(after, before, "on tick {}", tick);
+            }
+            before = after;
+        }
+    }
+
+    #[test]
+    fn a_shot_tail_clears_the_wall_ahead_and_shortens_the_snake() {
+        let mut app = quiet_app(|resources| resources.insert(ShootTail(true)));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_wall(&mut app, Position { x: 3, y: 8 });
+        run_tick(&mut app);
+        assert_eq!(body(&app).len(), 2);
+        tap(&mut app, KeyCode::X);
+        assert_eq!(body(&app).len(), 1);
+        // The shot keeps a cell ahead of the head, so the wall is gone before it gets there.
+        let mut crashes = 0;
+        for _ in 0..5 {
+            crashes += run_tick_counting::<GameOverEvent>(&mut app);
+        }
+        assert_eq!(crashes, 0);
+        assert_eq!(head(&app).0, Position { x: 3, y: 9 });
+        assert!(walls(&app).is_empty());
+    }
+
+    /// Grows the snake to four cells on its way up, then turns it right and down, so the head
+    /// sits at a corner of a two by two loop, next to the tail unless it has grown again.
+    fn coil(app: &mut App) -> usize {
+        place_food(app, FoodKind::Normal, Position { x: 3, y: 4 });
+        place_food(app, FoodKind::Normal, Position { x: 3, y: 5 });
+        run_ticks(app, 2);
+        let mut crashes = 0;
+        for &key in &[KeyCode::Right, KeyCode::Down] {
+            press_key(app, key);
+            crashes += run_tick_counting::<GameOverEvent>(app);
+            release_key(app, key);
+        }
+        assert_eq!(head(app).0, Position { x: 4, y: 4 });
+        crashes
+    }
+
+    #[test]
+    fn the_head_can_chase_its_tail_round_a_tight_loop() {
+        let mut app = quiet_app(|_| ());
+        let mut crashes = coil(&mut app);
+        assert_eq!(body(&app).last(), Some(&Position { x: 3, y: 4 }));
+        // Every move goes into the cell the tail is leaving on the same tick.
+        for _ in 0..3 {
+            for &key in &[KeyCode::Left, KeyCode::Up, KeyCode::Right, KeyCode::Down] {
+                press_key(&mut app, key);
+                crashes += run_tick_counting::<GameOverEvent>(&mut app);
+                release_key(&mut app, key);
+            }
+        }
+        assert_eq!(crashes, 0);
+        assert_eq!(head(&app).0, Position { x: 4, y: 4 });
+        assert_eq!(body(&app).len(), 3);
+    }
+
+    #[test]
+    fn the_tail_stays_in_the_way_while_growth_is_owed() {
+        for &mode in &[GrowthMode::Tail, GrowthMode::Head] {
+            let mut app = quiet_app(|resources| resources.insert(mode));
+            // Eaten on the last move of the coil, so the tail doesn't move off next time.
+            place_food(&mut app, FoodKind::Normal, Position { x: 4, y: 4 });
+            assert_eq!(coil(&mut app), 0);
+            press_key(&mut app, KeyCode::Left);
+            assert_eq!(
+                run_tick_counting::<GameOverEvent>(&mut app),
+                1,
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn holding_boost_drains_the_meter_and_moves_faster() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(BoostMode(Some(BoostConfig::default())));
+        });
+        run_tick(&mut app);
+        let cruising = run_tick(&mut app);
+        assert_eq!(app.resources.get::<Boost>().unwrap().meter, 1.);
+
+        press_key(&mut app, KeyCode::LShift);
+        run_tick(&mut app);
+        let boosted = run_tick(&mut app);
+        let meter = app.resources.get::<Boost>().unwrap().meter;
+        assert!(
+            boosted < cruising,
+            "{} frames against {}",
+            boosted,
+            cruising
+        );
+        assert!(meter < 1.);
+
+        release_key(&mut app, KeyCode::LShift);
+        run_tick(&mut app);
+        assert!(app.resources.get::<Boost>().unwrap().meter > meter);
+        assert_eq!(run_tick(&mut app), cruising);
+    }
+}
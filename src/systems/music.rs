@@ -0,0 +1,104 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use crate::events::{GameOverEvent, GrowthEvent};
+use crate::resources::{AudioAssets, MusicTrack, SnekMoveTimer, SoundEffects};
+
+#[derive(Default)]
+pub(crate) struct MusicState {
+    track: Option<Handle<AudioSource>>,
+    failed: bool,
+    /// Ticks into the current loop.
+    ticks: u32,
+}
+
+/// Plays the [`MusicTrack`] from the start on the first tick of every loop.
+///
+/// Bevy only plays a sound through once, so looping means playing it again. A loop that
+/// comes around before the track has loaded is skipped rather than queued up.
+pub(crate) fn music(
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    track: Res<MusicTrack>,
+    snek_timer: Res<SnekMoveTimer>,
+    mut state: Local<MusicState>,
+) {
+    let path = match &track.path {
+        Some(path) if !state.failed => path,
+        _ => return,
+    };
+    let handle = state
+        .track
+        .get_or_insert_with(|| asset_server.load(path.as_str()))
+        .clone();
+    if !snek_timer.finished {
+        return;
+    }
+    let first = state.ticks == 0;
+    state.ticks = (state.ticks + 1) % track.ticks_per_loop.max(1);
+    match asset_server.get_load_state(&handle) {
+        LoadState::Loaded if first => audio.play(handle),
+        LoadState::Failed => {
+            eprintln!("could not load music {}, playing without it", path);
+            state.failed = true;
+        }
+        _ => {}
+    }
+}
+
+/// Starts loading the [`SoundEffects`] that were picked.
+pub(crate) fn load_sounds(
+    asset_server: Res<AssetServer>,
+    sounds: Res<SoundEffects>,
+    mut assets: ResMut<AudioAssets>,
+) {
+    let load = |path: &Option<String>| path.as_ref().map(|path| asset_server.load(path.as_str()));
+    assets.eat = load(&sounds.eat);
+    assets.death = load(&sounds.death);
+}
+
+/// Plays the eat sound once for every [`GrowthEvent`] and the death sound once in a frame
+/// with any [`GameOverEvent`].
+///
+/// One death can send several game over events, say running into an enemy and a wall at
+/// once, so those are only counted once. A sound that failed to load is dropped for good.
+pub(crate) fn sound_effects(
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    sounds: Res<SoundEffects>,
+    mut assets: ResMut<AudioAssets>,
+    (growth_events, game_over_events): (Res<Events<GrowthEvent>>, Res<Events<GameOverEvent>>),
+    (mut growth_reader, mut game_over_reader): (
+        Local<EventReader<GrowthEvent>>,
+        Local<EventReader<GameOverEvent>>,
+    ),
+) {
+    let eaten = growth_reader.iter(&growth_events).count();
+    let died = game_over_reader.iter(&game_over_events).next().is_some();
+    let assets = &mut *assets;
+    for (sound, path, plays) in &mut [
+        (&mut assets.eat, &sounds.eat, eaten),
+        (&mut assets.death, &sounds.death, died as usize),
+    ] {
+        let handle = match sound {
+            Some(handle) => handle.clone(),
+            None => continue,
+        };
+        match asset_server.get_load_state(&handle) {
+            LoadState::Loaded => {
+                for _ in 0..*plays {
+                    audio.play(handle.clone());
+                }
+            }
+            LoadState::Failed => {
+                eprintln!(
+                    "could not load sound {}, playing without it",
+                    path.as_deref().unwrap_or_default()
+                );
+                **sound = None;
+            }
+            // Events from before it has loaded are skipped rather than queued up.
+            _ => {}
+        }
+    }
+}
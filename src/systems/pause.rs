@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use super::spawn::hud_text;
+use crate::components::PauseText;
+use crate::resources::{
+    DeathPlayback, GameOverScreen, GameState, GamepadInput, KeyBindings, Paused, PlayerPause,
+    QuitPrompt, TimeUp,
+};
+use crate::HUD_FONT_PATH;
+
+pub(crate) fn spawn_pause_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(hud_text(
+            asset_server.load(HUD_FONT_PATH),
+            Rect {
+                left: Val::Percent(44.0),
+                top: Val::Percent(40.0),
+                ..Default::default()
+            },
+        ))
+        .with(PauseText);
+}
+
+/// Pauses and resumes on the pause key, `Space` by default, or a gamepad's `Start`, showing
+/// "PAUSED" in the meantime.
+///
+/// A pause something else started, like the title menu, the quit prompt, a death replay, the game over notice
+/// or the end of a time attack, is left for that to lift.
+pub(crate) fn pause(
+    (keyboard_input, gamepad, bindings): (Res<Input<KeyCode>>, Res<GamepadInput>, Res<KeyBindings>),
+    (state, prompt, playback, time_up, over): (
+        Res<GameState>,
+        Res<QuitPrompt>,
+        Res<DeathPlayback>,
+        Res<TimeUp>,
+        Res<GameOverScreen>,
+    ),
+    (mut paused, mut player_pause): (ResMut<Paused>, ResMut<PlayerPause>),
+    mut texts: Query<With<PauseText, &mut Text>>,
+) {
+    if !paused.0 {
+        player_pause.0 = false;
+    }
+    let busy =
+        *state == GameState::Menu || prompt.open || playback.0.is_some() || time_up.0 || over.0;
+    let pressed = bindings
+        .pause
+        .iter()
+        .any(|key| keyboard_input.just_pressed(*key));
+    if !busy && (pressed || gamepad.start_pressed) {
+        if player_pause.0 {
+            player_pause.0 = false;
+            paused.0 = false;
+        } else if !paused.0 {
+            player_pause.0 = true;
+            paused.0 = true;
+        }
+    }
+    for mut text in texts.iter_mut() {
+        text.value = if player_pause.0 && !prompt.open {
+            "PAUSED".to_string()
+        } else {
+            String::new()
+        };
+    }
+}
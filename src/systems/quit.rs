@@ -0,0 +1,102 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use super::spawn::hud_text;
+use crate::components::QuitText;
+use crate::resources::{ConfirmQuit, Paused, QuitPrompt};
+use crate::HUD_FONT_PATH;
+
+pub(crate) fn spawn_quit_prompt(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(hud_text(
+            asset_server.load(HUD_FONT_PATH),
+            Rect {
+                left: Val::Percent(40.0),
+                top: Val::Percent(45.0),
+                ..Default::default()
+            },
+        ))
+        .with(QuitText);
+}
+
+/// Quits on `Escape`, unless [`ConfirmQuit`] asks first.
+///
+/// The prompt pauses the game: `Y` quits, `N` or `Escape` again goes back to it.
+pub(crate) fn quit(
+    keyboard_input: Res<Input<KeyCode>>,
+    confirm: Res<ConfirmQuit>,
+    (mut prompt, mut paused): (ResMut<QuitPrompt>, ResMut<Paused>),
+    mut exit_events: ResMut<Events<AppExit>>,
+    mut texts: Query<With<QuitText, &mut Text>>,
+) {
+    if prompt.open {
+        if keyboard_input.just_pressed(KeyCode::Y) {
+            exit_events.send(AppExit);
+        } else if keyboard_input.just_pressed(KeyCode::N)
+            || keyboard_input.just_pressed(KeyCode::Escape)
+        {
+            prompt.open = false;
+            paused.0 = prompt.was_paused;
+        } else {
+            // Keep it paused even if something else, like a restart, resumed it meanwhile.
+            paused.0 = true;
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        if confirm.0 {
+            prompt.open = true;
+            prompt.was_paused = paused.0;
+            paused.0 = true;
+        } else {
+            exit_events.send(AppExit);
+        }
+    }
+    for mut text in texts.iter_mut() {
+        text.value = if prompt.open {
+            "Quit? Y/N".to_string()
+        } else {
+            String::new()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::app::AppExit;
+    use bevy::prelude::*;
+
+    use crate::resources::{ConfirmQuit, Paused, QuitPrompt};
+    use crate::testing::{press_key, quiet_app, release_key};
+
+    /// Taps `key` and counts the quit events it led to.
+    fn tap_counting_exits(app: &mut App, key: KeyCode) -> usize {
+        let mut reader = app.resources.get::<Events<AppExit>>().unwrap().get_reader();
+        press_key(app, key);
+        app.update();
+        release_key(app, key);
+        app.update();
+        reader
+            .iter(&app.resources.get::<Events<AppExit>>().unwrap())
+            .count()
+    }
+
+    #[test]
+    fn escape_only_quits_once_confirmed() {
+        let mut app = quiet_app(|resources| resources.insert(ConfirmQuit(true)));
+        assert_eq!(tap_counting_exits(&mut app, KeyCode::Escape), 0);
+        assert!(app.resources.get::<QuitPrompt>().unwrap().open);
+        assert!(app.resources.get::<Paused>().unwrap().0);
+
+        assert_eq!(tap_counting_exits(&mut app, KeyCode::N), 0);
+        assert!(!app.resources.get::<QuitPrompt>().unwrap().open);
+        assert!(!app.resources.get::<Paused>().unwrap().0);
+
+        assert_eq!(tap_counting_exits(&mut app, KeyCode::Escape), 0);
+        assert_eq!(tap_counting_exits(&mut app, KeyCode::Y), 1);
+    }
+
+    #[test]
+    fn escape_quits_at_once_without_confirm_quit() {
+        let mut app = quiet_app(|resources| resources.insert(ConfirmQuit(false)));
+        assert_eq!(tap_counting_exits(&mut app, KeyCode::Escape), 1);
+    }
+}
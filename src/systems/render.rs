@@ -0,0 +1,1567 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::render::pass::ClearColor;
+use bevy::render::texture::FilterMode;
+use bevy::window::WindowResized;
+
+use super::movement::{gameplay_delta, on_beat};
+use super::spawn::hud_text;
+use crate::components::{
+    Aura, Backdrop, BeatMarker, BoostBar, Border, Direction, Enemy, FogTile, Food, FoodKind,
+    GridLine, HintMarker, Layer, LengthText, LevelText, LivesText, MainCamera, PeekCell, Position,
+    RunTimeText, ScorePopup, ScoreText, SeamDash, SegmentArrow, SegmentSpring, Size, SnekHead,
+    SnekSegment, Spark, StarvationBar, Tongue, Wall, WallBreakText, WrapText,
+};
+use crate::error::SnekError;
+use crate::events::{ResetGame, ScoreGained};
+use crate::resources::{
+    AgeColor, AgeScale, ArenaSize, BackgroundImage, BaseSpeed, BestRun, Boost, BoostMode,
+    CameraFollow, CameraMode, CellPadding, CellShape, DoubleScore, EdgeWarning, FogOfWar,
+    FoodChain, FrameSettings, HeadStyle, Invincible, Level, Lives, LivesLeft, Materials, MoveHint,
+    MusicBpm, OneWayCells, Paused, PhaseSelf, PingReveal, Projection, RadarConfig, RadarPings,
+    RhythmMode, RotateArena, RunTime, SaveFiles, Score, ScorePopups, SegmentArrows, SettingsDirty,
+    SnekMoveTimer, SnekSegments, SpeedColor, Starvation, StarvationInterval, Theme, TimeAttack,
+    TimeUp, TongueConfig, Tutorial, UnlockedThemes, WallBreakCharges, WallMode, Wobble,
+    WobbleSpring, WrapPeek, WrapSeams, WrapUnlockLength,
+};
+use crate::{
+    AGE_COLOR_FADE, BORDER_THICKNESS, GRID_LINE_THICKNESS, HUD_BAR_WIDTH, HUD_FONT_PATH,
+    PEEK_RADIUS, SETTINGS_PATH, SPEED_COLOR_FASTEST, WINDOW_TITLE,
+};
+
+pub(crate) fn primary_window_size(windows: &Windows) -> Result<(f32, f32), SnekError> {
+    windows
+        .get_primary()
+        .map(|window| (window.width() as f32, window.height() as f32))
+        .ok_or(SnekError::WindowMissing)
+}
+
+pub(crate) fn border_layout(
+    windows: Res<Windows>,
+    mut q: Query<(&Border, &mut Sprite, &mut Transform)>,
+) {
+    // Skip the frame if the primary window is gone, e.g. while it is being recreated.
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    for (border, mut sprite, mut transform) in q.iter_mut() {
+        let (size, x, y) = match border.0 {
+            Direction::Left => (Vec2::new(BORDER_THICKNESS, height), -width / 2., 0.),
+            Direction::Right => (Vec2::new(BORDER_THICKNESS, height), width / 2., 0.),
+            Direction::Up => (Vec2::new(width, BORDER_THICKNESS), 0., height / 2.),
+            Direction::Down => (Vec2::new(width, BORDER_THICKNESS), 0., -height / 2.),
+        };
+        sprite.size = size;
+        // Strips are centered on the window edge, so only their inner half is visible.
+        transform.translation = Vec3::new(x, y, Layer::OVERLAY.0);
+    }
+}
+
+/// Lines the grid up with the cell edges `position_translation` lays the sprites out by.
+///
+/// Isometric tiles are diamonds that straight lines can't follow, so the grid is hidden then.
+pub(crate) fn grid_layout(
+    windows: Res<Windows>,
+    (arena, projection): (Res<ArenaSize>, Res<Projection>),
+    mut q: Query<(&GridLine, &mut Sprite, &mut Transform, &mut Draw)>,
+) {
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    let (cell_width, cell_height) = (width / arena.width as f32, height / arena.height as f32);
+    for (line, mut sprite, mut transform, mut draw) in q.iter_mut() {
+        draw.is_visible = *projection == Projection::TopDown;
+        let along = line.index as f32;
+        let (size, x, y) = if line.vertical {
+            let x = along * cell_width - width / 2.;
+            (Vec2::new(GRID_LINE_THICKNESS, height), x, 0.)
+        } else {
+            let y = along * cell_height - height / 2.;
+            (Vec2::new(width, GRID_LINE_THICKNESS), 0., y)
+        };
+        sprite.size = size;
+        transform.translation = Vec3::new(x, y, Layer::GRID.0);
+    }
+}
+
+/// Scales the background image to cover the window, or drops it if it fails to load.
+pub(crate) fn background_layout(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    asset_server: Res<AssetServer>,
+    background: Res<BackgroundImage>,
+    textures: Res<Assets<Texture>>,
+    mut q: Query<(Entity, &Backdrop, &mut Sprite)>,
+) {
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    for (entity, backdrop, mut sprite) in q.iter_mut() {
+        if let Some(texture) = textures.get(&backdrop.0) {
+            let scale = (width / texture.size.x()).max(height / texture.size.y());
+            sprite.size = texture.size * scale;
+        } else if asset_server.get_load_state(&backdrop.0) == LoadState::Failed {
+            let path = background.0.clone().unwrap_or_default();
+            eprintln!("{}, using the clear color", SnekError::AssetLoad(path));
+            commands.despawn(entity);
+        }
+    }
+}
+
+pub(crate) fn edge_warning(
+    warning: Res<EdgeWarning>,
+    (wall_mode, arena): (Res<WallMode>, Res<ArenaSize>),
+    (wrap_unlock, segments): (Res<WrapUnlockLength>, Res<SnekSegments>),
+    materials: Res<Materials>,
+    heads: Query<With<SnekHead, &Position>>,
+    mut borders: Query<(&Border, &mut Handle<ColorMaterial>)>,
+) {
+    let head = heads.iter().next().copied();
+    let solid = *wall_mode == WallMode::Solid && !wrap_unlock.unlocked(&segments);
+    for (border, mut material) in borders.iter_mut() {
+        let adjacent = match head {
+            // Only solid edges are worth a warning.
+            Some(pos) if warning.0 && solid => match border.0 {
+                Direction::Left => pos.x == 0,
+                Direction::Right => pos.x == arena.width as i32 - 1,
+                Direction::Up => pos.y == arena.height as i32 - 1,
+                Direction::Down => pos.y == 0,
+            },
+            _ => false,
+        };
+        *material = if adjacent {
+            materials.border_warning_material.clone()
+        } else {
+            materials.border_material.clone()
+        };
+    }
+}
+
+/// Shows the [`WrapPeek`] inset while the head is within [`PEEK_RADIUS`] cells of the edge it
+/// is heading for and would wrap through it.
+///
+/// The inset is centered [`PEEK_RADIUS`] cells past where the head comes back in, so its near
+/// side is the entry cell and the rest is what lies ahead after wrapping.
+pub(crate) fn wrap_peek(
+    (peek, arena): (Res<WrapPeek>, Res<ArenaSize>),
+    (wall_mode, wrap_unlock, segments, invincible): (
+        Res<WallMode>,
+        Res<WrapUnlockLength>,
+        Res<SnekSegments>,
+        Res<Invincible>,
+    ),
+    materials: Res<Materials>,
+    heads: Query<(&SnekHead, &Position)>,
+    body: Query<With<SnekSegment, &Position>>,
+    food: Query<With<Food, (&Position, &FoodKind)>>,
+    walls: Query<With<Wall, &Position>>,
+    enemies: Query<With<Enemy, &Position>>,
+    mut cells: Query<(&PeekCell, &mut Handle<ColorMaterial>, &mut Draw)>,
+) {
+    if !peek.0 {
+        return;
+    }
+    let (width, height) = (arena.width as i32, arena.height as i32);
+    let wraps =
+        *wall_mode == WallMode::Wrap || wrap_unlock.unlocked(&segments) || invincible.is_active();
+    let center = heads.iter().next().and_then(|(head, pos)| {
+        let (dx, dy) = head.direction.delta();
+        let ahead = PEEK_RADIUS + 1;
+        let leaves = !(0..width).contains(&(pos.x + dx * ahead))
+            || !(0..height).contains(&(pos.y + dy * ahead));
+        if !wraps || !leaves {
+            return None;
+        }
+        // Run straight off the edge to find the cell the head wraps into.
+        let mut entry = *pos;
+        while (0..width).contains(&entry.x) && (0..height).contains(&entry.y) {
+            entry.x += dx;
+            entry.y += dy;
+        }
+        entry.x = entry.x.rem_euclid(width);
+        entry.y = entry.y.rem_euclid(height);
+        Some(Position {
+            x: entry.x + dx * PEEK_RADIUS,
+            y: entry.y + dy * PEEK_RADIUS,
+        })
+    });
+    let center = match center {
+        Some(center) => center,
+        None => {
+            for (_, _, mut draw) in cells.iter_mut() {
+                draw.is_visible = false;
+            }
+            return;
+        }
+    };
+    let mut contents: HashMap<Position, &Handle<ColorMaterial>> = HashMap::new();
+    contents.extend(walls.iter().map(|pos| (*pos, &materials.wall_material)));
+    contents.extend(enemies.iter().map(|pos| (*pos, &materials.enemy_material)));
+    contents.extend(food.iter().map(|(pos, kind)| (*pos, materials.food(*kind))));
+    contents.extend(body.iter().map(|pos| (*pos, &materials.segment_material)));
+    contents.extend(
+        heads
+            .iter()
+            .map(|(_, pos)| (*pos, &materials.head_material)),
+    );
+    for (cell, mut material, mut draw) in cells.iter_mut() {
+        // The far side of the inset may wrap once more, like the head would.
+        let pos = Position {
+            x: (center.x + cell.dx).rem_euclid(width),
+            y: (center.y + cell.dy).rem_euclid(height),
+        };
+        *material = contents
+            .get(&pos)
+            .copied()
+            .unwrap_or(&materials.peek_material)
+            .clone();
+        draw.is_visible = true;
+    }
+}
+
+/// Swaps the solid borders for dashes running clockwise around the arena while the head
+/// would wrap through its edges.
+pub(crate) fn wrap_seams(
+    (time, windows, seams, arena): (Res<Time>, Res<Windows>, Res<WrapSeams>, Res<ArenaSize>),
+    (wall_mode, wrap_unlock, segments, invincible): (
+        Res<WallMode>,
+        Res<WrapUnlockLength>,
+        Res<SnekSegments>,
+        Res<Invincible>,
+    ),
+    mut borders: Query<With<Border, &mut Draw>>,
+    mut dashes: Query<(&SeamDash, &mut Sprite, &mut Transform, &mut Draw)>,
+) {
+    let style = match &seams.0 {
+        Some(style) => style,
+        None => return,
+    };
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    let wraps =
+        *wall_mode == WallMode::Wrap || wrap_unlock.unlocked(&segments) || invincible.is_active();
+    for mut draw in borders.iter_mut() {
+        draw.is_visible = !wraps;
+    }
+    let scroll = (time.seconds_since_startup as f32 * style.speed).fract();
+    let (cell_width, cell_height) = (width / arena.width as f32, height / arena.height as f32);
+    for (dash, mut sprite, mut transform, mut draw) in dashes.iter_mut() {
+        draw.is_visible = wraps;
+        if !wraps {
+            continue;
+        }
+        // Dashes start a cell before the edge does and fill half of every cell after that.
+        let along = dash.index as f32 - 1. + scroll + 0.25;
+        let (size, x, y) = match dash.side {
+            Direction::Up => (
+                Vec2::new(cell_width / 2., BORDER_THICKNESS),
+                -width / 2. + along * cell_width,
+                height / 2.,
+            ),
+            Direction::Right => (
+                Vec2::new(BORDER_THICKNESS, cell_height / 2.),
+                width / 2.,
+                height / 2. - along * cell_height,
+            ),
+            Direction::Down => (
+                Vec2::new(cell_width / 2., BORDER_THICKNESS),
+                width / 2. - along * cell_width,
+                -height / 2.,
+            ),
+            Direction::Left => (
+                Vec2::new(BORDER_THICKNESS, cell_height / 2.),
+                -width / 2.,
+                -height / 2. + along * cell_height,
+            ),
+        };
+        sprite.size = size;
+        transform.translation = Vec3::new(x, y, Layer::OVERLAY.0);
+    }
+}
+
+/// Highlights the neighbour of the head closest to any food, among those it can move into
+/// next without dying; with no such cell, or no food, nothing is highlighted.
+pub(crate) fn move_hint(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut hint: ResMut<MoveHint>,
+    (arena, wall_mode, wrap_unlock, segments, invincible, one_way): (
+        Res<ArenaSize>,
+        Res<WallMode>,
+        Res<WrapUnlockLength>,
+        Res<SnekSegments>,
+        Res<Invincible>,
+        Res<OneWayCells>,
+    ),
+    heads: Query<(&SnekHead, &Position)>,
+    body: Query<With<SnekSegment, &Position>>,
+    walls: Query<With<Wall, &Position>>,
+    enemies: Query<With<Enemy, &Position>>,
+    food: Query<With<Food, &Position>>,
+    mut markers: Query<With<HintMarker, (&mut Position, &mut Draw)>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::H) {
+        hint.0 = !hint.0;
+    }
+    let wraps =
+        *wall_mode == WallMode::Wrap || wrap_unlock.unlocked(&segments) || invincible.is_active();
+    let blocked: HashSet<Position> = body
+        .iter()
+        .chain(walls.iter())
+        .chain(enemies.iter())
+        .copied()
+        .collect();
+    let food: Vec<Position> = food.iter().copied().collect();
+    let suggestion = heads
+        .iter()
+        .next()
+        .filter(|_| hint.0)
+        .and_then(|(head, pos)| {
+            [
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+            ]
+            .iter()
+            .filter(|&&dir| dir != head.direction.opposite())
+            .filter_map(|&dir| {
+                let mut next = pos.step(dir);
+                if wraps {
+                    next = arena.wrap(next);
+                }
+                let in_arena = arena.contains(next);
+                let enterable = one_way.allowed(next).is_none_or(|allowed| allowed == dir);
+                Some(next).filter(|next| in_arena && enterable && !blocked.contains(next))
+            })
+            .filter_map(|next| {
+                food.iter()
+                    .map(|food| (food.x - next.x).abs() + (food.y - next.y).abs())
+                    .min()
+                    .map(|distance| (distance, next))
+            })
+            // The first of equally close cells wins, so the hint doesn't flicker between them.
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, next)| next)
+        });
+    for (mut pos, mut draw) in markers.iter_mut() {
+        draw.is_visible = suggestion.is_some();
+        if let Some(suggestion) = suggestion {
+            *pos = suggestion;
+        }
+    }
+}
+
+/// Spends a radar ping on `Tab` and runs the reveal it starts; a new game refills the pings.
+pub(crate) fn radar_ping(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    (fog, config, paused): (Res<FogOfWar>, Res<RadarConfig>, Res<Paused>),
+    (mut pings, mut reveal): (ResMut<RadarPings>, ResMut<PingReveal>),
+    (reset_events, mut reset_reader, mut refilled): (
+        Res<Events<ResetGame>>,
+        Local<EventReader<ResetGame>>,
+        Local<bool>,
+    ),
+) {
+    if fog.0.is_none() {
+        return;
+    }
+    if reset_reader.iter(&reset_events).next().is_some() || !*refilled {
+        *refilled = true;
+        pings.0 = config.pings;
+        *reveal = PingReveal::default();
+    }
+    if paused.0 {
+        return;
+    }
+    reveal.0.tick(gameplay_delta(&time));
+    if keyboard_input.just_pressed(KeyCode::Tab) && pings.0 > 0 && !reveal.is_active() {
+        pings.0 -= 1;
+        reveal.0 = Timer::new(config.reveal, false);
+    }
+}
+
+/// Lifts the fog around the head, or as far as a radar ping has spread, and hides the food
+/// outside of it.
+pub(crate) fn fog_of_war(
+    fog: Res<FogOfWar>,
+    paused: Res<Paused>,
+    reveal: Res<PingReveal>,
+    arena: Res<ArenaSize>,
+    heads: Query<With<SnekHead, &Position>>,
+    mut tiles: Query<With<FogTile, (&Position, &mut Draw)>>,
+    mut food: Query<With<Food, (&Position, &mut Draw)>>,
+) {
+    // The death replay hides the board while paused, so leave its visibility alone.
+    let radius = match fog.0 {
+        Some(radius) if !paused.0 => radius as i32,
+        _ => return,
+    };
+    let head = match heads.iter().next() {
+        Some(head) => *head,
+        None => return,
+    };
+    let radius = if reveal.is_active() {
+        // The ring reaches the far corner halfway through and holds there until it is over.
+        let spread = (reveal.0.elapsed / reveal.0.duration * 2.).min(1.);
+        let corner = (arena.width + arena.height) as f32;
+        radius.max((spread * corner) as i32)
+    } else {
+        radius
+    };
+    let seen = |pos: &Position| (pos.x - head.x).abs() + (pos.y - head.y).abs() <= radius;
+    for (pos, mut draw) in tiles.iter_mut() {
+        draw.is_visible = !seen(pos);
+    }
+    for (pos, mut draw) in food.iter_mut() {
+        draw.is_visible = seen(pos);
+    }
+}
+
+pub(crate) fn size_scaling(
+    windows: Res<Windows>,
+    arena: Res<ArenaSize>,
+    mut q: Query<(&Size, &mut Sprite)>,
+) {
+    // Skip the frame if the primary window is gone, e.g. while it is being recreated.
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    for (size, mut sprite) in q.iter_mut() {
+        sprite.size = Vec2::new(
+            size.width / arena.width as f32 * width,
+            size.height / arena.height as f32 * height,
+        );
+    }
+}
+
+/// Room above a sprite's layer taken up by the snake's body, and by isometric depth.
+const BODY_Z_SPAN: f32 = 1.0;
+const SEGMENT_Z_STEP: f32 = 0.001;
+
+/// Where the center of cell `p` goes along an axis `bound_window` pixels long with
+/// `bound_game` cells, with the window's center at 0.
+///
+/// Cell `p` spans `bound_window / bound_game` pixels and is centered half a cell in, so cell
+/// 0 starts at the window's lower/left edge and cell `bound_game - 1` ends at the opposite
+/// one; every cell the game can kill, spawn or move on is fully on screen.
+fn cell_to_window(p: f32, bound_window: f32, bound_game: f32) -> f32 {
+    p / bound_game * bound_window - (bound_window / 2.) + (bound_window / bound_game / 2.)
+}
+
+pub(crate) fn position_translation(
+    windows: Res<Windows>,
+    (projection, arena): (Res<Projection>, Res<ArenaSize>),
+    segments: Res<SnekSegments>,
+    mut q: Query<(
+        Entity,
+        &Position,
+        &mut Transform,
+        Option<&Layer>,
+        Option<&SnekHead>,
+    )>,
+) {
+    // Skip the frame if the primary window is gone, e.g. while it is being recreated.
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    let (arena_width, arena_height) = (arena.width as f32, arena.height as f32);
+    // Body parts share a layer but must never trade places while they overlap: the neck sits
+    // just below the head and every segment a touch below the one before it. All of it stays
+    // under one layer step, with the head above the isometric depth of any segment.
+    let order: HashMap<Entity, usize> = segments
+        .0
+        .iter()
+        .enumerate()
+        .map(|(index, &entity)| (entity, index))
+        .collect();
+    for (entity, pos, mut transform, layer, head) in q.iter_mut() {
+        let mut layer = layer.copied().unwrap_or(Layer::DEFAULT);
+        if head.is_some() {
+            layer.0 += 2. * BODY_Z_SPAN;
+        } else if let Some(&index) = order.get(&entity) {
+            layer.0 += (BODY_Z_SPAN - (index + 1) as f32 * SEGMENT_Z_STEP).max(0.);
+        }
+        transform.translation = match *projection {
+            Projection::TopDown => Vec3::new(
+                cell_to_window(pos.x as f32, width, arena_width),
+                cell_to_window(pos.y as f32, height, arena_height),
+                layer.0,
+            ),
+            Projection::Isometric => {
+                // Diamond tiles are twice as wide as they are high; size them so the
+                // whole board fits the window and center it on the middle of the board.
+                let diagonal = arena_width + arena_height;
+                let tile_width = (2. * width / diagonal).min(4. * height / diagonal);
+                let (x, y) = (pos.x as f32, pos.y as f32);
+                let iso_x = (x - y) - (arena_width - arena_height) / 2.;
+                let iso_y = (x + y) - (diagonal - 2.) / 2.;
+                // Rows closer to the viewer are drawn on top.
+                let depth = (diagonal - (x + y)) / diagonal;
+                Vec3::new(
+                    iso_x * tile_width / 2.,
+                    iso_y * tile_width / 4.,
+                    layer.0 + depth,
+                )
+            }
+        };
+    }
+}
+
+/// Springs every segment from where it was drawn last frame toward the spot
+/// `position_translation` just put it on.
+pub(crate) fn wobble(
+    mut commands: Commands,
+    time: Res<Time>,
+    (windows, arena): (Res<Windows>, Res<ArenaSize>),
+    (wobble, spring): (Res<Wobble>, Res<WobbleSpring>),
+    mut segments: Query<With<SnekSegment, (Entity, &mut Transform, Option<&mut SegmentSpring>)>>,
+) {
+    if !wobble.0 {
+        return;
+    }
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    // Anything further off than this jumped, e.g. by wrapping, and is not worth chasing.
+    let snap = 2. * (width / arena.width as f32).max(height / arena.height as f32);
+    let dt = gameplay_delta(&time);
+    for (entity, mut transform, state) in segments.iter_mut() {
+        let target = transform.translation;
+        let mut state = match state {
+            Some(state) => state,
+            None => {
+                commands.insert_one(
+                    entity,
+                    SegmentSpring {
+                        position: target,
+                        velocity: Vec3::zero(),
+                    },
+                );
+                continue;
+            }
+        };
+        let offset = target - state.position;
+        if offset.x().abs().max(offset.y().abs()) > snap {
+            state.position = target;
+            state.velocity = Vec3::zero();
+            continue;
+        }
+        spring_toward(&mut state, target, &spring, dt);
+        transform.translation = state.position;
+    }
+}
+
+/// Moves a segment `dt` seconds further along its damped spring toward `target`.
+fn spring_toward(state: &mut SegmentSpring, target: Vec3, spring: &WobbleSpring, dt: f32) {
+    let offset = target - state.position;
+    let accel = offset * spring.stiffness - state.velocity * spring.damping;
+    state.velocity += accel * dt;
+    let velocity = state.velocity;
+    state.position += velocity * dt;
+    // Keep the depth `position_translation` picked.
+    state.position.set_z(target.z());
+}
+
+/// Flings the sparks of exploded food out from the cell `position_translation` put them on,
+/// shrinking them until they burn out.
+pub(crate) fn sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    (windows, arena): (Res<Windows>, Res<ArenaSize>),
+    padding: Res<CellPadding>,
+    mut sparks: Query<(Entity, &mut Spark, &mut Transform, &mut Size)>,
+) {
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    let cell = Vec2::new(width / arena.width as f32, height / arena.height as f32);
+    for (entity, mut spark, mut transform, mut size) in sparks.iter_mut() {
+        spark.timer.tick(time.delta_seconds);
+        if spark.timer.finished {
+            commands.despawn(entity);
+            continue;
+        }
+        let offset = spark.heading * spark.timer.elapsed * cell;
+        transform.translation += offset.extend(0.);
+        let left = 1. - spark.timer.elapsed / spark.timer.duration;
+        *size = Size::square(padding.food * 0.4 * left);
+    }
+}
+
+/// Snaps the camera back inside the arena after the window changed size.
+///
+/// `camera_follow` only eases toward its target, so without this a burst of resizes could
+/// leave the view off center, or past the arena edges, for a while.
+pub(crate) fn recenter_on_resize(
+    mut reader: Local<EventReader<WindowResized>>,
+    resized_events: Res<Events<WindowResized>>,
+    windows: Res<Windows>,
+    mut cameras: Query<With<MainCamera, &mut Transform>>,
+) {
+    // Only the final size matters, however many resizes happened this frame.
+    if reader.iter(&resized_events).last().is_none() {
+        return;
+    }
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    for mut camera in cameras.iter_mut() {
+        let max_x = (width / 2. * (1. - camera.scale.x())).max(0.);
+        let max_y = (height / 2. * (1. - camera.scale.y())).max(0.);
+        let x = camera.translation.x().clamp(-max_x, max_x);
+        let y = camera.translation.y().clamp(-max_y, max_y);
+        camera.translation.set_x(x);
+        camera.translation.set_y(y);
+    }
+}
+
+pub(crate) fn camera_follow(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    mode: Res<CameraMode>,
+    follow: Res<CameraFollow>,
+    heads: Query<With<SnekHead, &Transform>>,
+    mut cameras: Query<With<MainCamera, &mut Transform>>,
+) {
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    for mut camera in cameras.iter_mut() {
+        let (target, zoom) = match *mode {
+            CameraMode::FitAll => (Vec3::zero(), 1.0),
+            CameraMode::FollowHead => {
+                let zoom = follow.zoom.clamp(0.01, 1.0);
+                let head = match heads.iter().next() {
+                    Some(head) => head.translation,
+                    None => camera.translation,
+                };
+                // The arena spans the window, so keep the zoomed view inside it.
+                let max_x = width / 2. * (1. - zoom);
+                let max_y = height / 2. * (1. - zoom);
+                let x = head.x().clamp(-max_x, max_x);
+                let y = head.y().clamp(-max_y, max_y);
+                (Vec3::new(x, y, camera.translation.z()), zoom)
+            }
+        };
+        let t = (follow.speed * time.delta_seconds).min(1.0);
+        let z = camera.translation.z();
+        camera.translation = camera.translation.lerp(target, t);
+        camera.translation.set_z(z);
+        camera.scale = Vec3::new(zoom, zoom, 1.0);
+    }
+}
+
+pub(crate) fn rotate_arena(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    rotate: Res<RotateArena>,
+    mut cameras: Query<With<MainCamera, &mut Transform>>,
+) {
+    let speed = match rotate.0 {
+        Some(speed) if !paused.0 => speed,
+        _ => return,
+    };
+    let turn = Quat::from_rotation_z(speed * gameplay_delta(&time));
+    for mut camera in cameras.iter_mut() {
+        camera.rotation = (turn * camera.rotation).normalize();
+    }
+}
+
+pub(crate) fn head_orientation(
+    style: Res<HeadStyle>,
+    mut heads: Query<(&SnekHead, &mut Transform, Option<&mut TextureAtlasSprite>)>,
+) {
+    for (head, mut transform, atlas_sprite) in heads.iter_mut() {
+        let (rotation, scale) = match *style {
+            HeadStyle::Rotate => (Quat::from_rotation_z(head.direction.angle()), Vec3::one()),
+            HeadStyle::Flip => {
+                let x = if head.direction == Direction::Left {
+                    -1.0
+                } else {
+                    1.0
+                };
+                let y = if head.direction == Direction::Down {
+                    -1.0
+                } else {
+                    1.0
+                };
+                (Quat::identity(), Vec3::new(x, y, 1.0))
+            }
+            HeadStyle::Atlas => {
+                if let Some(mut atlas_sprite) = atlas_sprite {
+                    atlas_sprite.index = head.direction as u32;
+                }
+                (Quat::identity(), Vec3::one())
+            }
+        };
+        transform.rotation = rotation;
+        transform.scale = scale;
+    }
+}
+
+/// Sticks the tongue out while food sits on the cell right ahead of the head.
+///
+/// It is drawn from the head's transform and sprite, so it has to run after
+/// `position_translation` and `size_scaling`.
+pub(crate) fn tongue(
+    mut commands: Commands,
+    (config, handles, padding): (Res<TongueConfig>, Res<Materials>, Res<CellPadding>),
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    (mut tongue, mut applied_color): (Local<Option<Entity>>, Local<Option<Color>>),
+    heads: Query<(&SnekHead, &Position, &Transform, &Sprite)>,
+    food: Query<With<Food, &Position>>,
+    mut tongues: Query<With<Tongue, (&mut Transform, &mut Sprite)>>,
+) {
+    if *applied_color != Some(config.color) {
+        *applied_color = Some(config.color);
+        if let Some(material) = materials.get_mut(&handles.tongue_material) {
+            material.color = config.color;
+        }
+    }
+    let target = heads
+        .iter()
+        .next()
+        .and_then(|(head, pos, transform, sprite)| {
+            let (dx, dy) = head.direction.delta();
+            let ahead = Position {
+                x: pos.x + dx,
+                y: pos.y + dy,
+            };
+            if food.iter().all(|food_pos| *food_pos != ahead) {
+                return None;
+            }
+            Some((
+                Vec2::new(dx as f32, dy as f32),
+                transform.translation,
+                sprite.size,
+            ))
+        });
+    let (dir, head, head_size) = match target {
+        Some(target) => target,
+        None => {
+            if let Some(entity) = tongue.take() {
+                commands.despawn(entity);
+            }
+            return;
+        }
+    };
+    let entity = match *tongue {
+        Some(entity) => entity,
+        None => {
+            commands
+                .spawn(SpriteComponents {
+                    material: handles.tongue_material.clone(),
+                    sprite: Sprite::new(Vec2::zero()),
+                    ..Default::default()
+                })
+                .with(Tongue);
+            *tongue = commands.current_entity();
+            return;
+        }
+    };
+    if let Ok((mut transform, mut sprite)) = tongues.get_mut(entity) {
+        let cell = head_size / padding.head.max(0.01);
+        let length = config.length * cell.x().max(cell.y());
+        let thickness = 0.15 * cell.x().min(cell.y());
+        // Start at the middle of the head so no gap shows between head and tongue.
+        let reach = 0.5 * cell.x().max(cell.y()) + length;
+        let center = head.truncate() + dir * (reach / 2.);
+        sprite.size = if dir.x() != 0. {
+            Vec2::new(reach, thickness)
+        } else {
+            Vec2::new(thickness, reach)
+        };
+        // Just below the head, above the grid and markings.
+        transform.translation = center.extend(head.z() - 0.5);
+    }
+}
+
+/// Pulses a glow around the head while normal food is worth extra.
+///
+/// It is drawn from the head's transform and sprite, so it has to run after
+/// `position_translation` and `size_scaling`.
+pub(crate) fn double_score_aura(
+    mut commands: Commands,
+    (time, handles, padding): (Res<Time>, Res<Materials>, Res<CellPadding>),
+    double_score: Res<DoubleScore>,
+    mut aura: Local<Option<Entity>>,
+    heads: Query<With<SnekHead, (&Transform, &Sprite)>>,
+    mut auras: Query<With<Aura, (&mut Transform, &mut Sprite)>>,
+) {
+    let head = match heads.iter().next() {
+        Some((transform, sprite)) if double_score.is_active() => {
+            (transform.translation, sprite.size)
+        }
+        _ => {
+            if let Some(entity) = aura.take() {
+                commands.despawn(entity);
+            }
+            return;
+        }
+    };
+    let entity = match *aura {
+        Some(entity) => entity,
+        None => {
+            commands
+                .spawn(SpriteComponents {
+                    material: handles.aura_material.clone(),
+                    sprite: Sprite::new(Vec2::zero()),
+                    ..Default::default()
+                })
+                .with(Aura);
+            *aura = commands.current_entity();
+            return;
+        }
+    };
+    if let Ok((mut transform, mut sprite)) = auras.get_mut(entity) {
+        let (head, head_size) = head;
+        let cell = head_size / padding.head.max(0.01);
+        let pulse = 1.5 + 0.2 * (time.seconds_since_startup as f32 * 6.).sin();
+        sprite.size = cell * pulse;
+        // Below the head and its tongue, so it shows as a ring around them.
+        transform.translation = head.truncate().extend(head.z() - 0.75);
+    }
+}
+
+/// Points an arrow on every segment at its neighbour toward the head.
+///
+/// Arrows are laid over the segments' sprites, so this has to run after
+/// `position_translation` and `size_scaling`.
+pub(crate) fn segment_arrows(
+    mut commands: Commands,
+    (enabled, handles, segments): (Res<SegmentArrows>, Res<Materials>, Res<SnekSegments>),
+    arena: Res<ArenaSize>,
+    mut arrows: Local<Vec<Entity>>,
+    heads: Query<With<SnekHead, &Position>>,
+    body: Query<With<SnekSegment, (&Position, &Transform, &Sprite, &Draw)>>,
+    mut sprites: Query<With<SegmentArrow, (&mut Transform, &mut Sprite, &mut Draw)>>,
+) {
+    let wanted = if enabled.0 { segments.0.len() } else { 0 };
+    while arrows.len() > wanted {
+        if let Some(entity) = arrows.pop() {
+            commands.despawn(entity);
+        }
+    }
+    while arrows.len() < wanted {
+        commands
+            .spawn(SpriteComponents {
+                material: handles.segment_arrow_material.clone(),
+                sprite: Sprite::new(Vec2::zero()),
+                ..Default::default()
+            })
+            .with(SegmentArrow);
+        arrows.extend(commands.current_entity());
+    }
+    let mut ahead = heads.iter().next().copied();
+    for (segment, arrow) in segments.0.iter().zip(arrows.iter()) {
+        let (pos, transform, sprite, draw) = match body.get(*segment) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+        if let (Some(ahead), Ok((mut arrow_transform, mut arrow_sprite, mut arrow_draw))) =
+            (ahead, sprites.get_mut(*arrow))
+        {
+            // A neighbour more than half the arena away is across an edge the head wrapped
+            // through.
+            let toward = |from: i32, to: i32, side: u32| {
+                let d = to - from;
+                if d.abs() * 2 > side as i32 {
+                    -d.signum()
+                } else {
+                    d.signum()
+                }
+            };
+            let dx = toward(pos.x, ahead.x, arena.width) as f32;
+            let dy = toward(pos.y, ahead.y, arena.height) as f32;
+            arrow_sprite.size = sprite.size * 0.6;
+            // Hidden along with its segment, say while blinking or during a death replay.
+            arrow_draw.is_visible = draw.is_visible;
+            // Between this segment and the one ahead of it, which sorts just above.
+            arrow_transform.translation =
+                transform.translation + Vec3::new(0., 0., SEGMENT_Z_STEP / 2.);
+            arrow_transform.rotation = Quat::from_rotation_z(dy.atan2(dx));
+        }
+        ahead = Some(*pos);
+    }
+}
+
+/// Keeps the score and length in the window title, touching it only when either changed.
+pub(crate) fn window_title(
+    mut windows: ResMut<Windows>,
+    (score, segments, tutorial): (Res<Score>, Res<SnekSegments>, Res<Tutorial>),
+    mut shown: Local<Option<String>>,
+) {
+    // The practice round is not a run worth keeping score of.
+    let title = if tutorial.0 {
+        WINDOW_TITLE.to_string()
+    } else {
+        format!(
+            "{} — Score {} (len {})",
+            WINDOW_TITLE,
+            score.0,
+            segments.0.len() + 1
+        )
+    };
+    if shown.as_ref() == Some(&title) {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_title(title.clone());
+        *shown = Some(title);
+    }
+}
+
+pub(crate) fn wall_break_text(
+    charges: Res<WallBreakCharges>,
+    mut texts: Query<With<WallBreakText, &mut Text>>,
+) {
+    for mut text in texts.iter_mut() {
+        text.value = if charges.0 > 0 {
+            format!("Wall breaks: {}", charges.0)
+        } else {
+            String::new()
+        };
+    }
+}
+
+pub(crate) fn lives_text(
+    lives: Res<Lives>,
+    lives_left: Res<LivesLeft>,
+    mut texts: Query<With<LivesText, &mut Text>>,
+) {
+    for mut text in texts.iter_mut() {
+        // A single life is the classic game, so there is nothing to show.
+        text.value = if lives.0 > 1 {
+            "\u{2665} "
+                .repeat(lives_left.0 as usize)
+                .trim_end()
+                .to_string()
+        } else {
+            String::new()
+        };
+    }
+}
+
+/// Shows the score next to the best one, which is the score of the saved best run.
+pub(crate) fn score_text(
+    score: Res<Score>,
+    best: Res<BestRun>,
+    mut shown: Local<Option<(u32, u32)>>,
+    mut texts: Query<With<ScoreText, &mut Text>>,
+) {
+    let current = (score.0, best.score);
+    if *shown == Some(current) {
+        return;
+    }
+    *shown = Some(current);
+    for mut text in texts.iter_mut() {
+        text.value = format!("Score: {}  Best: {}", score.0, best.score);
+    }
+}
+
+/// Shows how many segments the snake has behind its head.
+pub(crate) fn length_display(
+    segments: Res<SnekSegments>,
+    mut shown: Local<Option<usize>>,
+    mut texts: Query<With<LengthText, &mut Text>>,
+) {
+    let length = segments.0.len();
+    if *shown == Some(length) {
+        return;
+    }
+    *shown = Some(length);
+    for mut text in texts.iter_mut() {
+        text.value = format!("Length: {}", length);
+    }
+}
+
+pub(crate) fn level_text(
+    chain: Res<FoodChain>,
+    level: Res<Level>,
+    mut texts: Query<With<LevelText, &mut Text>>,
+) {
+    for mut text in texts.iter_mut() {
+        text.value = if chain.0.is_some() {
+            let left = (level.time_limit.duration - level.time_limit.elapsed).max(0.0);
+            format!(
+                "Level {}: {} to go, {:.0}s",
+                level.number,
+                level.foods_remaining,
+                left.ceil()
+            )
+        } else {
+            String::new()
+        };
+    }
+}
+
+pub(crate) fn wrap_text(
+    (wrap_unlock, segments): (Res<WrapUnlockLength>, Res<SnekSegments>),
+    mut texts: Query<With<WrapText, &mut Text>>,
+) {
+    for mut text in texts.iter_mut() {
+        text.value = if wrap_unlock.unlocked(&segments) {
+            "Wrap unlocked".to_string()
+        } else {
+            String::new()
+        };
+    }
+}
+
+pub(crate) fn boost_bar(
+    (mode, boost): (Res<BoostMode>, Res<Boost>),
+    mut bars: Query<With<BoostBar, &mut Style>>,
+) {
+    let width = match mode.0 {
+        Some(_) => boost.meter * HUD_BAR_WIDTH,
+        None => 0.,
+    };
+    for mut style in bars.iter_mut() {
+        style.size.width = Val::Px(width);
+    }
+}
+
+/// Spawns a popup for every meal's points and floats the live ones up while they fade.
+pub(crate) fn score_popups(
+    mut commands: Commands,
+    (time, windows, asset_server, popups): (
+        Res<Time>,
+        Res<Windows>,
+        Res<AssetServer>,
+        Res<ScorePopups>,
+    ),
+    (score_events, mut reader): (Res<Events<ScoreGained>>, Local<EventReader<ScoreGained>>),
+    heads: Query<With<SnekHead, &Transform>>,
+    cameras: Query<With<MainCamera, &Transform>>,
+    mut live: Query<(Entity, &mut ScorePopup, &mut Style, &mut Text)>,
+) {
+    let config = match &popups.0 {
+        Some(config) => config,
+        None => return,
+    };
+    for ScoreGained(points) in reader.iter(&score_events) {
+        let origin = match heads.iter().next() {
+            Some(head) => head.translation,
+            None => continue,
+        };
+        let mut text = hud_text(
+            asset_server.load(HUD_FONT_PATH),
+            Rect {
+                left: Val::Px(0.),
+                bottom: Val::Px(0.),
+                ..Default::default()
+            },
+        );
+        text.text.value = format!("+{}", points);
+        // Hidden until it has been placed over the head below.
+        text.draw.is_visible = false;
+        commands.spawn(text).with(ScorePopup {
+            origin,
+            timer: Timer::new(config.duration, false),
+        });
+    }
+    let (width, height) = match primary_window_size(&windows) {
+        Ok(size) => size,
+        Err(_) => return,
+    };
+    let camera = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    for (entity, mut popup, mut style, mut text) in live.iter_mut() {
+        popup.timer.tick(time.delta_seconds);
+        if popup.timer.finished {
+            commands.despawn(entity);
+            continue;
+        }
+        let progress = popup.timer.elapsed / popup.timer.duration.max(f32::EPSILON);
+        // Follow the head's old cell wherever the camera has moved or turned since.
+        let offset =
+            camera.rotation.conjugate() * (popup.origin - camera.translation) / camera.scale;
+        style.position.left = Val::Px(width / 2. + offset.x());
+        style.position.bottom = Val::Px(height / 2. + offset.y() + progress * config.rise);
+        text.style.color.set_a(1. - progress);
+    }
+}
+
+/// Empties toward the moment hunger takes the tail.
+pub(crate) fn starvation_bar(
+    (interval, starvation): (Res<StarvationInterval>, Res<Starvation>),
+    mut bars: Query<With<StarvationBar, &mut Style>>,
+) {
+    let width = match interval.0 {
+        Some(_) => {
+            let left = 1. - starvation.0.elapsed / starvation.0.duration.max(f32::EPSILON);
+            left.clamp(0., 1.) * HUD_BAR_WIDTH
+        }
+        None => 0.,
+    };
+    for mut style in bars.iter_mut() {
+        style.size.width = Val::Px(width);
+    }
+}
+
+pub(crate) fn beat_marker(
+    (rhythm, bpm): (Res<RhythmMode>, Res<MusicBpm>),
+    snek_timer: Res<SnekMoveTimer>,
+    mut markers: Query<With<BeatMarker, &mut Draw>>,
+) {
+    let lit = (rhythm.0 || bpm.0.is_some()) && on_beat(&snek_timer);
+    for mut draw in markers.iter_mut() {
+        draw.is_visible = lit;
+    }
+}
+
+/// Shows how long the run has lasted, or under a [`TimeAttack`] how long it has left.
+pub(crate) fn run_time_text(
+    run_time: Res<RunTime>,
+    (time_attack, time_up, score): (Res<TimeAttack>, Res<TimeUp>, Res<Score>),
+    mut shown: Local<Option<(u64, bool)>>,
+    mut texts: Query<With<RunTimeText, &mut Text>>,
+) {
+    // Round the countdown up, so it only reads 00:00 once time is up.
+    let secs = match time_attack.0 {
+        Some(limit) => {
+            let left = limit.checked_sub(run_time.0).unwrap_or_default();
+            left.as_secs() + u64::from(left.subsec_nanos() > 0)
+        }
+        None => run_time.0.as_secs(),
+    };
+    // Only touch the text when the displayed second changes.
+    if *shown == Some((secs, time_up.0)) {
+        return;
+    }
+    *shown = Some((secs, time_up.0));
+    for mut text in texts.iter_mut() {
+        text.value = if time_up.0 {
+            format!("Time's up! Score: {}", score.0)
+        } else {
+            format!("{:02}:{:02}", secs / 60, secs % 60)
+        };
+    }
+}
+
+/// Greys the snake out as it ages, tints it toward red as it speeds up, and fades it while it
+/// phases through itself.
+pub(crate) fn speed_color(
+    speed_color: Res<SpeedColor>,
+    (snek_timer, base): (Res<SnekMoveTimer>, Res<BaseSpeed>),
+    phase: Res<PhaseSelf>,
+    (age_color, age_scale, run_time): (Res<AgeColor>, Res<AgeScale>, Res<RunTime>),
+    handles: Res<Materials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut applied: Local<Option<(f32, f32, bool, Color, Color)>>,
+) {
+    let tint = if speed_color.0 {
+        let base = base.0.as_secs_f32();
+        let span = (base - SPEED_COLOR_FASTEST).max(0.001);
+        ((base - snek_timer.duration) / span).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let age = if age_color.0 && age_scale.0 > Duration::from_secs(0) {
+        let age = run_time.0.as_secs_f32() / age_scale.0.as_secs_f32();
+        // Steps of a hundredth keep the materials from being rewritten every frame.
+        (age.min(1.0) * 100.).floor() / 100.
+    } else {
+        0.0
+    };
+    let phasing = phase.is_active();
+    let current = (
+        age,
+        tint,
+        phasing,
+        handles.head_color,
+        handles.segment_color,
+    );
+    if *applied == Some(current) {
+        return;
+    }
+    *applied = Some(current);
+    let faded = Vec4::from(Color::rgb(0.25, 0.25, 0.25));
+    let danger = Vec4::from(Color::rgb(0.9, 0.05, 0.05));
+    for (handle, color) in &[
+        (&handles.head_material, handles.head_color),
+        (&handles.segment_material, handles.segment_color),
+    ] {
+        if let Some(material) = materials.get_mut(*handle) {
+            let mut color: Color = Vec4::from(*color)
+                .lerp(faded, age * AGE_COLOR_FADE)
+                .lerp(danger, tint)
+                .into();
+            if phasing {
+                color.set_a(0.4);
+            }
+            material.color = color;
+        }
+    }
+}
+
+/// Repaints the snake, plain food and background in the [`Theme`], cycling it on `T`
+/// through the [`UnlockedThemes`].
+///
+/// Like [`cell_shape`], it changes the shared materials, so sprites already on the board
+/// change along with those spawned later. `speed_color` tints from the new colors.
+pub(crate) fn theme(
+    keyboard_input: Res<Input<KeyCode>>,
+    (mut theme, unlocked): (ResMut<Theme>, Res<UnlockedThemes>),
+    (mut handles, mut clear_color): (ResMut<Materials>, ResMut<ClearColor>),
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut applied: Local<Option<Theme>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::T) {
+        *theme = unlocked.next_after(*theme);
+    }
+    if *applied == Some(*theme) {
+        return;
+    }
+    *applied = Some(*theme);
+    let colors = theme.colors();
+    handles.head_color = colors.head;
+    handles.segment_color = colors.segment;
+    clear_color.0 = colors.background;
+    for (handle, color) in &[
+        (&handles.head_material, colors.head),
+        (&handles.segment_material, colors.segment),
+        (&handles.food_material, colors.food),
+    ] {
+        if let Some(material) = materials.get_mut(*handle) {
+            material.color = *color;
+        }
+    }
+}
+
+/// Masks the snake and food materials with a disc for [`CellShape::Circle`], so sprites
+/// spawned before and after a switch all share the same look.
+pub(crate) fn cell_shape(
+    shape: Res<CellShape>,
+    handles: Res<Materials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut applied: Local<Option<CellShape>>,
+) {
+    if *applied == Some(*shape) {
+        return;
+    }
+    *applied = Some(*shape);
+    let texture = match *shape {
+        CellShape::Square => None,
+        CellShape::Circle => Some(handles.circle_texture.clone()),
+    };
+    for handle in handles.shaped().iter() {
+        if let Some(material) = materials.get_mut(*handle) {
+            material.texture = texture.clone();
+        }
+    }
+}
+
+/// Blinks the snake while it is invincible.
+pub(crate) fn invincible_flash(
+    time: Res<Time>,
+    paused: Res<Paused>,
+    invincible: Res<Invincible>,
+    mut flash: Local<Option<Timer>>,
+    mut sprites: Query<(&mut Draw, Option<&SnekHead>, Option<&SnekSegment>)>,
+) {
+    // The death replay hides the snake while paused, so leave its visibility alone.
+    if paused.0 {
+        return;
+    }
+    let visible = if invincible.is_active() {
+        let timer = flash.get_or_insert_with(|| Timer::from_seconds(0.1, true));
+        timer.tick(time.delta_seconds);
+        if !timer.just_finished {
+            return;
+        }
+        None
+    } else if flash.take().is_some() {
+        Some(true)
+    } else {
+        return;
+    };
+    for (mut draw, head, segment) in sprites.iter_mut() {
+        if head.is_some() || segment.is_some() {
+            draw.is_visible = visible.unwrap_or(!draw.is_visible);
+        }
+    }
+}
+
+/// Applies [`FrameSettings::smooth`] to every textured sprite; `F` flips it while playing.
+pub(crate) fn texture_smoothing(
+    keyboard_input: Res<Input<KeyCode>>,
+    (mut settings, mut dirty): (ResMut<FrameSettings>, ResMut<SettingsDirty>),
+    materials: Res<Assets<ColorMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut applied: Local<Option<(bool, usize)>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F) {
+        settings.smooth = !settings.smooth;
+        dirty.0 = true;
+    }
+    let handles: Vec<Handle<Texture>> = materials
+        .iter()
+        .filter_map(|(_, material)| material.texture.clone())
+        .collect();
+    // Textures can still be loading, so check again whenever more of them show up.
+    let loaded = handles
+        .iter()
+        .filter(|handle| textures.contains(*handle))
+        .count();
+    if *applied == Some((settings.smooth, loaded)) {
+        return;
+    }
+    *applied = Some((settings.smooth, loaded));
+    let filter = if settings.smooth {
+        FilterMode::Linear
+    } else {
+        FilterMode::Nearest
+    };
+    for handle in handles {
+        if let Some(texture) = textures.get_mut(&handle) {
+            texture.sampler.mag_filter = filter;
+            texture.sampler.min_filter = filter;
+        }
+    }
+}
+
+const SETTINGS_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Writes changed settings to disk, at most once a second however often they change.
+///
+/// A failed write is logged and tried again a second later.
+pub(crate) fn save_settings(
+    (settings, save_files): (Res<FrameSettings>, Res<SaveFiles>),
+    mut dirty: ResMut<SettingsDirty>,
+    mut last_write: Local<Option<Instant>>,
+) {
+    if !save_files.0 {
+        dirty.0 = false;
+        return;
+    }
+    if !dirty.0 || last_write.is_some_and(|last| last.elapsed() < SETTINGS_SAVE_INTERVAL) {
+        return;
+    }
+    *last_write = Some(Instant::now());
+    match settings.save(SETTINGS_PATH) {
+        Ok(()) => dirty.0 = false,
+        Err(e) => eprintln!("failed to save settings to {}: {}", SETTINGS_PATH, e),
+    }
+}
+
+/// Sleeps away whatever is left of the frame budget when an FPS cap is set.
+pub(crate) fn frame_limiter(settings: Res<FrameSettings>, mut last_frame: Local<Option<Instant>>) {
+    if let Some(fps) = settings.fps_cap {
+        let budget = Duration::from_secs(1) / fps;
+        if let Some(elapsed) = last_frame.map(|last| last.elapsed()) {
+            if elapsed < budget {
+                std::thread::sleep(budget - elapsed);
+            }
+        }
+    }
+    *last_frame = Some(Instant::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::window::{WindowCommand, WindowId};
+
+    use super::*;
+    use crate::testing::{
+        body, head, place_food, place_wall, press_key, quiet_app, release_key, run_tick, walls,
+    };
+
+    #[test]
+    fn first_and_last_cells_fit_the_window_edge_to_edge() {
+        for &window in &[500., 720., 1280.] {
+            for &cells in &[4_u32, 5, 9, 10, 17] {
+                let (bound_game, half_cell) = (cells as f32, window / cells as f32 / 2.);
+                let first = cell_to_window(0., window, bound_game);
+                let last = cell_to_window(bound_game - 1., window, bound_game);
+                assert!((first - half_cell + window / 2.).abs() < 1e-3);
+                assert!((last + half_cell - window / 2.).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn food_beyond_the_fog_radius_is_hidden() {
+        let mut app = quiet_app(|resources| resources.insert(FogOfWar(Some(2))));
+        // The head starts at (3, 3).
+        place_food(&mut app, FoodKind::Normal, Position { x: 5, y: 3 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 9, y: 9 });
+        app.update();
+        let mut visible: Vec<(Position, bool)> = app
+            .world
+            .query::<(&Food, &Position, &Draw)>()
+            .map(|(_, pos, draw)| (*pos, draw.is_visible))
+            .collect();
+        visible.sort_by_key(|(pos, _)| pos.x);
+        assert_eq!(
+            visible,
+            vec![
+                (Position { x: 5, y: 3 }, true),
+                (Position { x: 9, y: 9 }, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_rotating_arena_turns_the_camera_as_time_goes_by() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(RotateArena(Some(0.5)));
+        });
+        let angle = |app: &App| {
+            let (_, camera) = app
+                .world
+                .query::<(&MainCamera, &Transform)>()
+                .next()
+                .unwrap();
+            let x = camera.rotation * Vec3::unit_x();
+            x.y().atan2(x.x())
+        };
+        let start = angle(&app);
+        for _ in 0..60 {
+            app.update();
+        }
+        // Half a radian over a second of 60 frames.
+        assert!((angle(&app) - start - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn a_wobbling_segment_settles_on_its_cell() {
+        let target = Vec3::new(100., 40., 1.);
+        // A cell's width away on a 500 pixel window, as after a move.
+        let mut state = SegmentSpring {
+            position: Vec3::new(50., 40., 1.),
+            velocity: Vec3::zero(),
+        };
+        let spring = WobbleSpring::default();
+        for _ in 0..30 {
+            spring_toward(&mut state, target, &spring, 1. / 60.);
+            assert!((state.position - target).length() < 50.);
+        }
+        assert!((state.position - target).length() < 1.);
+    }
+
+    #[test]
+    fn the_move_hint_steers_around_walls_toward_food() {
+        let mut app = quiet_app(|resources| resources.insert(MoveHint(true)));
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 6 });
+        // Straight on and the first way round are both walled off.
+        place_wall(&mut app, Position { x: 3, y: 4 });
+        place_wall(&mut app, Position { x: 4, y: 3 });
+        let hint = |app: &App| {
+            let (_, pos, draw) = app
+                .world
+                .query::<(&HintMarker, &Position, &Draw)>()
+                .next()
+                .unwrap();
+            Some(*pos).filter(|_| draw.is_visible)
+        };
+        app.update();
+        assert_eq!(hint(&app), Some(Position { x: 2, y: 3 }));
+        press_key(&mut app, KeyCode::Left);
+        for _ in 0..40 {
+            app.update();
+            let hint = match hint(&app) {
+                Some(hint) => hint,
+                None => continue,
+            };
+            assert_ne!(hint, head(&app).0);
+            assert!(!body(&app).contains(&hint));
+            assert!(!walls(&app).contains(&hint));
+        }
+    }
+
+    #[test]
+    fn a_radar_ping_shows_far_food_for_a_while() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(FogOfWar(Some(2)));
+            resources.insert(RadarConfig {
+                pings: 1,
+                reveal: Duration::from_secs(1),
+            });
+        });
+        // Six columns over from the head's, out of the fog's radius all the way up.
+        place_food(&mut app, FoodKind::Normal, Position { x: 9, y: 9 });
+        let shown = |app: &App| {
+            let (_, draw) = app.world.query::<(&Food, &Draw)>().next().unwrap();
+            draw.is_visible
+        };
+        let frames = |app: &mut App, frames: usize| (0..frames).for_each(|_| app.update());
+        let ping = |app: &mut App| {
+            press_key(app, KeyCode::Tab);
+            app.update();
+            release_key(app, KeyCode::Tab);
+        };
+        app.update();
+        assert!(!shown(&app));
+        ping(&mut app);
+        // The ring reaches the far corner halfway through the reveal.
+        frames(&mut app, 40);
+        assert!(shown(&app));
+        frames(&mut app, 30);
+        assert!(!shown(&app));
+        // With no pings left, the fog stays.
+        ping(&mut app);
+        frames(&mut app, 40);
+        assert!(!shown(&app));
+    }
+
+    /// Titles the window was given since last asked.
+    fn titles_set(app: &mut App) -> Vec<String> {
+        let mut windows = app.resources.get_mut::<Windows>().unwrap();
+        windows
+            .get_primary_mut()
+            .unwrap()
+            .drain_commands()
+            .filter_map(|command| match command {
+                WindowCommand::SetTitle { title } => Some(title),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_title_only_changes_along_with_the_score_or_length() {
+        let mut app = quiet_app(|_| ());
+        app.resources.get_mut::<Windows>().unwrap().add(Window::new(
+            WindowId::primary(),
+            &WindowDescriptor::default(),
+        ));
+        app.update();
+        assert_eq!(
+            titles_set(&mut app),
+            vec![format!("{} — Score 0 (len 2)", WINDOW_TITLE)]
+        );
+        for _ in 0..30 {
+            app.update();
+        }
+        assert!(titles_set(&mut app).is_empty());
+
+        place_food(&mut app, FoodKind::Normal, Position { x: 3, y: 6 });
+        run_tick(&mut app);
+        // The title catches up on the frame after the food is eaten.
+        app.update();
+        assert_eq!(
+            titles_set(&mut app),
+            vec![format!("{} — Score 1 (len 3)", WINDOW_TITLE)]
+        );
+    }
+}
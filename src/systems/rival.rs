@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use super::spawn::START_POSITION;
+use crate::components::{
+    Direction, Food, Portal, Position, Rival, RivalHead, Size, SnekHead, SnekSegment, Wall,
+};
+use crate::events::{GameOverEvent, Player, ResetGame};
+use crate::resources::{
+    ArenaSize, CellPadding, GameState, Materials, RivalSegments, SnekMoveTimer, TwoPlayer, WallMode,
+};
+
+/// Where player two's snake starts: across the arena from player one's, heading up as well.
+pub(crate) fn rival_start(arena: ArenaSize) -> Position {
+    let x = arena.width as i32 - 1 - START_POSITION.x;
+    // Only an arena exactly this wide would start both snakes on the same column.
+    let x = if x == START_POSITION.x { x + 1 } else { x };
+    Position {
+        x,
+        y: START_POSITION.y,
+    }
+}
+
+fn spawn_part(
+    commands: &mut Commands,
+    material: &Handle<ColorMaterial>,
+    size: f32,
+    position: Position,
+) -> Entity {
+    commands
+        .spawn(SpriteComponents {
+            material: material.clone(),
+            ..Default::default()
+        })
+        .with(Rival)
+        .with(position)
+        .with(Size::square(size));
+    commands
+        .current_entity()
+        .expect("spawn always sets the current entity")
+}
+
+/// Lays out player two's snake once the game is running, and again on every reset.
+pub(crate) fn rival_spawn(
+    mut commands: Commands,
+    (two_player, state, arena): (Res<TwoPlayer>, Res<GameState>, Res<ArenaSize>),
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    (reset_events, mut reset_reader, mut laid_out): (
+        Res<Events<ResetGame>>,
+        Local<EventReader<ResetGame>>,
+        Local<bool>,
+    ),
+    mut segments: ResMut<RivalSegments>,
+    parts: Query<With<Rival, Entity>>,
+) {
+    let reset = reset_reader.iter(&reset_events).next().is_some();
+    let starting = !*laid_out && *state == GameState::Running;
+    if two_player.0.is_none() || !(reset || starting) {
+        return;
+    }
+    *laid_out = true;
+    for entity in parts.iter() {
+        commands.despawn(entity);
+    }
+    let head = rival_start(*arena);
+    let head_entity = spawn_part(
+        &mut commands,
+        &materials.rival_head_material,
+        padding.head,
+        head,
+    );
+    commands.with(RivalHead {
+        direction: Direction::Up,
+        turn: None,
+    });
+    let tail = Position {
+        x: head.x,
+        y: head.y - 1,
+    };
+    let tail_entity = spawn_part(
+        &mut commands,
+        &materials.rival_segment_material,
+        padding.segment,
+        tail,
+    );
+    *segments = RivalSegments {
+        entities: vec![head_entity, tail_entity],
+        last_tail: None,
+    };
+}
+
+/// Steers player two's snake and moves it on each tick, right after player one's, then ends
+/// the round for whichever of them ran into something.
+///
+/// It only goes through the edge under [`WallMode::Wrap`]; any other mode is a solid edge to
+/// it. Both snakes have moved by the time it checks, so a tail cell left this tick is free for
+/// either head.
+pub(crate) fn rival_movement(
+    keyboard_input: Res<Input<KeyCode>>,
+    (two_player, snek_timer, arena, wall_mode): (
+        Res<TwoPlayer>,
+        Res<SnekMoveTimer>,
+        Res<ArenaSize>,
+        Res<WallMode>,
+    ),
+    mut segments: ResMut<RivalSegments>,
+    mut game_over_events: ResMut<Events<GameOverEvent>>,
+    mut heads: Query<&mut RivalHead>,
+    mut positions: Query<With<Rival, &mut Position>>,
+    player_heads: Query<With<SnekHead, &Position>>,
+    player_segments: Query<With<SnekSegment, &Position>>,
+    walls: Query<With<Wall, &Position>>,
+    portals: Query<(&Portal, &Position)>,
+) {
+    let keys = match &two_player.0 {
+        Some(keys) => keys,
+        None => return,
+    };
+    for (key, dir) in keys.keys() {
+        if keyboard_input.just_pressed(key) {
+            for mut head in heads.iter_mut() {
+                if dir != head.direction.opposite() {
+                    head.turn = Some(dir);
+                }
+            }
+        }
+    }
+    if !snek_timer.finished {
+        return;
+    }
+    let mut heading = None;
+    for mut head in heads.iter_mut() {
+        if let Some(turn) = head.turn.take() {
+            head.direction = turn;
+        }
+        heading = Some(head.direction);
+    }
+    let old: Vec<Position> = segments
+        .entities
+        .iter()
+        .filter_map(|entity| positions.get_mut(*entity).ok().map(|pos| *pos))
+        .collect();
+    // Not there yet the frame it was laid out in.
+    let dir = match heading {
+        Some(dir) if !old.is_empty() && old.len() == segments.entities.len() => dir,
+        _ => return,
+    };
+    let mut next = old[0].step(dir);
+    if !arena.contains(next) && *wall_mode == WallMode::Wrap {
+        next = arena.wrap(next);
+    }
+    if let Some((portal, _)) = portals.iter().find(|(_, pos)| **pos == next) {
+        next = portal.link;
+    }
+    // Every part moves into the cell of the one ahead of it.
+    let moved: Vec<Position> = std::iter::once(next)
+        .chain(old[..old.len() - 1].iter().copied())
+        .collect();
+    for (entity, pos) in segments.entities.iter().zip(moved.iter()) {
+        if let Ok(mut part) = positions.get_mut(*entity) {
+            *part = *pos;
+        }
+    }
+    segments.last_tail = old.last().copied();
+
+    let rival_crashed = !arena.contains(next)
+        || walls.iter().any(|wall| *wall == next)
+        || moved[1..].contains(&next)
+        || player_heads
+            .iter()
+            .chain(player_segments.iter())
+            .any(|pos| *pos == next);
+    let player_crashed = player_heads.iter().any(|head| moved.contains(head));
+    if rival_crashed {
+        game_over_events.send(GameOverEvent(Player::Two));
+    }
+    if player_crashed {
+        game_over_events.send(GameOverEvent(Player::One));
+    }
+}
+
+/// Eats any food under player two's head, growing its snake by one where the tail just left.
+pub(crate) fn rival_eating(
+    mut commands: Commands,
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    mut segments: ResMut<RivalSegments>,
+    heads: Query<With<RivalHead, &Position>>,
+    food: Query<With<Food, (Entity, &Position)>>,
+) {
+    for head in heads.iter() {
+        for (entity, _) in food.iter().filter(|(_, pos)| *pos == head) {
+            commands.despawn(entity);
+            if let Some(tail) = segments.last_tail.take() {
+                let part = spawn_part(
+                    &mut commands,
+                    &materials.rival_segment_material,
+                    padding.segment,
+                    tail,
+                );
+                segments.entities.push(part);
+            }
+        }
+    }
+}
@@ -0,0 +1,1486 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy::render::texture::TextureFormat;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::movement::gameplay_delta;
+use crate::components::{
+    Backdrop, BeatMarker, BoostBar, Border, Coin, Direction, Enemy, FogTile, Food, FoodCluster,
+    FoodKind, GridLine, HintMarker, Layer, LengthText, LevelText, LivesText, MainCamera,
+    OneWayMarker, PaintTile, PeekCell, Portal, Position, Rival, RunTimeText, ScoreText, SeamDash,
+    Size, SnekHead, SnekSegment, Spark, SpawnedAt, StarvationBar, Wall, WallBreakText, WrapText,
+};
+use crate::events::ResetGame;
+use crate::resources::{
+    ArenaSize, BackgroundImage, BodySpacing, BonusFoodConfig, Canvas, CellPadding, Checkerboard,
+    CheckerboardShades, CoinDrops, DoubleScoreConfig, EnabledFoodKinds, EnemySpawns,
+    ExplosiveConfig, FogOfWar, FoodChain, FoodRng, FoodSeed, FoodSpawn, FoodSpawnTimer, GameState,
+    GridLines, InvincibleConfig, LastTailPosition, Level, Lives, LivesLeft, LoadedReplay,
+    MagnetConfig, Materials, MaxFood, MegaFoodConfig, OneWayCells, PaintMode, PaintPalette, Paused,
+    PhaseConfig, PoisonConfig, Portals, ReplayRecorder, ReverseConfig, RunTime, ScatterInterval,
+    SlowConfig, SnekMoveTimer, SnekPath, SnekSegments, SplitConfig, Theme, TongueConfig, TronMode,
+    WallBreakConfig, WrapPeek, WrapSeams,
+};
+use crate::{
+    HUD_BAR_HEIGHT, HUD_FONT_PATH, PEEK_CELL_SIZE, PEEK_RADIUS, SPARK_COUNT, SPARK_SECS,
+    SPARK_SPEED,
+};
+
+pub(crate) fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    theme: Res<Theme>,
+) {
+    // Bevy requires a specific ordering to the params when registering systems.
+    // Commands → Resources → Components/Queries.
+    // If you get a mysterious compile-time error after messing with a system, check your order.
+    commands
+        .spawn(Camera2dComponents::default())
+        .with(MainCamera);
+    commands.spawn(UiCameraComponents::default());
+    let font = asset_server.load(HUD_FONT_PATH);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with(WallBreakText);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with(RunTimeText);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                left: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with(LivesText);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                left: Val::Px(10.0),
+                top: Val::Px(40.0),
+                ..Default::default()
+            },
+        ))
+        .with(ScoreText);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                left: Val::Px(10.0),
+                top: Val::Px(70.0),
+                ..Default::default()
+            },
+        ))
+        .with(LengthText);
+    commands
+        .spawn(hud_text(
+            font.clone(),
+            Rect {
+                right: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with(LevelText);
+    commands
+        .spawn(hud_text(
+            font,
+            Rect {
+                right: Val::Px(10.0),
+                top: Val::Px(40.0),
+                ..Default::default()
+            },
+        ))
+        .with(WrapText);
+    commands
+        .spawn(hud_bar(
+            materials.add(Color::rgb(0.2, 0.6, 1.0).into()),
+            Rect {
+                left: Val::Px(10.0),
+                bottom: Val::Px(40.0),
+                ..Default::default()
+            },
+        ))
+        .with(BoostBar);
+    commands
+        .spawn(hud_bar(
+            materials.add(Color::rgb(0.8, 0.6, 0.1).into()),
+            Rect {
+                left: Val::Px(10.0),
+                bottom: Val::Px(55.0),
+                ..Default::default()
+            },
+        ))
+        .with(StarvationBar);
+    commands
+        .spawn(NodeComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(50.0),
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                size: bevy::math::Size::new(Val::Px(20.0), Val::Px(20.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::WHITE.into()),
+            // `beat_marker` shows it while turns register.
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(BeatMarker);
+    let circle = textures.add(circle_texture());
+    let arrow = textures.add(arrow_texture());
+    let colors = theme.colors();
+    commands.insert_resource(Materials {
+        head_color: colors.head,
+        head_material: materials.add(colors.head.into()),
+        segment_color: colors.segment,
+        segment_material: materials.add(colors.segment.into()),
+        food_material: materials.add(colors.food.into()),
+        ghost_material: materials.add(Color::rgba(0.4, 0.2, 0.0, 0.3).into()),
+        enemy_material: materials.add(Color::rgb(0.6, 0.0, 0.6).into()),
+        magnet_food_material: materials.add(Color::rgb(0.7, 0.7, 0.75).into()),
+        border_material: materials.add(Color::rgb(0.2, 0.2, 0.2).into()),
+        border_warning_material: materials.add(Color::rgb(0.8, 0.1, 0.1).into()),
+        wall_break_food_material: materials.add(Color::rgb(0.9, 0.5, 0.1).into()),
+        poison_food_material: materials.add(Color::rgb(0.3, 0.8, 0.1).into()),
+        reverse_food_material: materials.add(Color::rgb(0.1, 0.8, 0.8).into()),
+        invincible_food_material: materials.add(Color::rgb(1.0, 0.85, 0.1).into()),
+        double_score_food_material: materials.add(Color::rgb(0.9, 0.3, 0.9).into()),
+        phase_food_material: materials.add(Color::rgba(0.6, 0.6, 1.0, 0.6).into()),
+        split_food_material: materials.add(Color::rgb(0.5, 0.5, 0.5).into()),
+        explosive_food_material: materials.add(Color::rgb(1.0, 0.45, 0.0).into()),
+        bonus_food_material: materials.add(Color::rgb(1.0, 0.84, 0.0).into()),
+        slow_food_material: materials.add(Color::rgb(0.2, 0.4, 1.0).into()),
+        mega_food_material: materials.add(Color::rgb(0.6, 0.0, 0.1).into()),
+        wall_material: materials.add(Color::rgb(0.0, 0.1, 0.2).into()),
+        fog_material: materials.add(Color::rgb(0.02, 0.02, 0.02).into()),
+        tongue_material: materials.add(TongueConfig::default().color.into()),
+        aura_material: materials.add(ColorMaterial::modulated_texture(
+            circle.clone(),
+            Color::rgba(0.9, 0.3, 0.9, 0.35),
+        )),
+        one_way_material: materials.add(ColorMaterial::modulated_texture(
+            arrow.clone(),
+            Color::rgba(0.8, 0.8, 0.8, 0.5),
+        )),
+        portal_material: materials.add(ColorMaterial::modulated_texture(
+            circle.clone(),
+            Color::rgba(0.3, 0.8, 0.9, 0.6),
+        )),
+        segment_arrow_material: materials.add(ColorMaterial::modulated_texture(
+            arrow,
+            Color::rgba(1.0, 1.0, 1.0, 0.3),
+        )),
+        coin_material: materials.add(ColorMaterial::modulated_texture(
+            circle.clone(),
+            Color::rgb(1.0, 0.8, 0.2),
+        )),
+        peek_material: materials.add(Color::rgba(0.15, 0.15, 0.15, 0.8).into()),
+        hint_material: materials.add(Color::rgba(1.0, 1.0, 1.0, 0.25).into()),
+        gate_material: materials.add(Color::rgb(0.5, 0.35, 0.1).into()),
+        key_material: materials.add(Color::rgb(0.95, 0.95, 0.6).into()),
+        door_material: materials.add(Color::rgb(0.35, 0.15, 0.45).into()),
+        rival_head_material: materials.add(Color::rgb(0.1, 0.4, 0.1).into()),
+        rival_segment_material: materials.add(Color::rgb(0.2, 0.5, 0.2).into()),
+        circle_texture: circle,
+    });
+}
+
+/// A white disc filling the texture, with a one pixel soft edge.
+fn circle_texture() -> Texture {
+    const SIDE: usize = 64;
+    let radius = SIDE as f32 / 2.;
+    let mut data = vec![0u8; SIDE * SIDE * 4];
+    for y in 0..SIDE {
+        for x in 0..SIDE {
+            let (dx, dy) = (x as f32 + 0.5 - radius, y as f32 + 0.5 - radius);
+            let coverage = (radius - (dx * dx + dy * dy).sqrt()).clamp(0., 1.);
+            let i = (y * SIDE + x) * 4;
+            data[i..i + 3].copy_from_slice(&[255; 3]);
+            data[i + 3] = (coverage * 255.) as u8;
+        }
+    }
+    Texture::new(
+        Vec2::new(SIDE as f32, SIDE as f32),
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// A white, right-pointing triangle on a transparent background.
+fn arrow_texture() -> Texture {
+    const SIDE: usize = 16;
+    let mut data = vec![0u8; SIDE * SIDE * 4];
+    for y in 0..SIDE {
+        for x in 4..12 {
+            let half_height = (12 - x) as f32 / 2.;
+            if (y as f32 + 0.5 - SIDE as f32 / 2.).abs() <= half_height {
+                let i = (y * SIDE + x) * 4;
+                data[i..i + 4].copy_from_slice(&[255; 4]);
+            }
+        }
+    }
+    Texture::new(
+        Vec2::new(SIDE as f32, SIDE as f32),
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// An initially empty line of HUD text pinned to the window at `position`.
+pub(crate) fn hud_text(font: Handle<Font>, position: Rect<Val>) -> TextComponents {
+    TextComponents {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position,
+            ..Default::default()
+        },
+        text: Text {
+            value: String::new(),
+            font,
+            style: TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        },
+        ..Default::default()
+    }
+}
+
+/// An initially empty meter bar pinned to the window at `position`; set its width to fill it.
+pub(crate) fn hud_bar(material: Handle<ColorMaterial>, position: Rect<Val>) -> NodeComponents {
+    NodeComponents {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position,
+            size: bevy::math::Size::new(Val::Px(0.0), Val::Px(HUD_BAR_HEIGHT)),
+            ..Default::default()
+        },
+        material,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn spawn_background(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    background: Res<BackgroundImage>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let path = match &background.0 {
+        Some(path) => path,
+        None => return,
+    };
+    let texture: Handle<Texture> = asset_server.load(path.as_str());
+    commands
+        .spawn(SpriteComponents {
+            material: materials.add(ColorMaterial::texture(texture.clone())),
+            // Nothing shows until the image is in and `background_layout` sizes it.
+            sprite: Sprite::new(Vec2::zero()),
+            transform: Transform::from_translation(Vec3::new(0., 0., Layer::BACKDROP.0)),
+            ..Default::default()
+        })
+        .with(Backdrop(texture));
+}
+
+pub(crate) fn spawn_checkerboard(
+    mut commands: Commands,
+    checkerboard: Res<Checkerboard>,
+    shades: Res<CheckerboardShades>,
+    arena: Res<ArenaSize>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !checkerboard.0 {
+        return;
+    }
+    let shades = [
+        materials.add(shades.0.into()),
+        materials.add(shades.1.into()),
+    ];
+    for pos in arena.cells() {
+        commands
+            .spawn(SpriteComponents {
+                material: shades[((pos.x + pos.y) % 2) as usize].clone(),
+                ..Default::default()
+            })
+            .with(pos)
+            .with(Size::square(1.0))
+            .with(Layer::BACKGROUND);
+    }
+}
+
+/// One line between every two neighbouring rows and columns, laid out by `grid_layout`.
+pub(crate) fn spawn_grid_lines(
+    mut commands: Commands,
+    grid: Res<GridLines>,
+    arena: Res<ArenaSize>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let color = match grid.0 {
+        Some(color) => color,
+        None => return,
+    };
+    let material = materials.add(color.into());
+    for &(vertical, cells) in &[(true, arena.width), (false, arena.height)] {
+        for index in 1..cells {
+            commands
+                .spawn(SpriteComponents {
+                    material: material.clone(),
+                    ..Default::default()
+                })
+                .with(GridLine { vertical, index });
+        }
+    }
+}
+
+pub(crate) fn spawn_borders(mut commands: Commands, materials: Res<Materials>) {
+    for side in &[
+        Direction::Left,
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+    ] {
+        commands
+            .spawn(SpriteComponents {
+                material: materials.border_material.clone(),
+                ..Default::default()
+            })
+            .with(Border(*side));
+    }
+}
+
+pub(crate) fn spawn_wall(
+    commands: &mut Commands,
+    materials: &Materials,
+    padding: &CellPadding,
+    position: Position,
+) {
+    commands
+        .spawn(SpriteComponents {
+            material: materials.wall_material.clone(),
+            ..Default::default()
+        })
+        .with(Wall)
+        .with(position)
+        .with(Size::square(padding.segment));
+}
+
+/// Sends a ring of sparks flying out of `position`, for exploding food.
+pub(crate) fn spawn_sparks(
+    commands: &mut Commands,
+    materials: &Materials,
+    padding: &CellPadding,
+    position: Position,
+) {
+    for i in 0..SPARK_COUNT {
+        let angle = i as f32 / SPARK_COUNT as f32 * std::f32::consts::TAU;
+        commands
+            .spawn(SpriteComponents {
+                material: materials.explosive_food_material.clone(),
+                ..Default::default()
+            })
+            .with(Spark {
+                heading: Vec2::new(angle.cos(), angle.sin()) * SPARK_SPEED,
+                timer: Timer::from_seconds(SPARK_SECS, false),
+            })
+            .with(position)
+            .with(Size::square(padding.food * 0.4))
+            .with(Layer::OVERLAY);
+    }
+}
+
+/// In [`TronMode`], walls off every cell the tail moves out of.
+pub(crate) fn tron_trail(
+    mut commands: Commands,
+    snek_timer: Res<SnekMoveTimer>,
+    tron: Res<TronMode>,
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    last_tail_position: Res<LastTailPosition>,
+    walls: Query<With<Wall, &Position>>,
+) {
+    if !tron.0 || !snek_timer.finished {
+        return;
+    }
+    if let Some(vacated) = last_tail_position.0 {
+        // A freshly grown tail still sits on the wall it vacated last tick.
+        if walls.iter().all(|wall| *wall != vacated) {
+            spawn_wall(&mut commands, &materials, &padding, vacated);
+        }
+    }
+}
+
+/// In [`PaintMode`], colors in every cell the head visits, and clears them all on `C` or a
+/// reset.
+pub(crate) fn paint_trail(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    snek_timer: Res<SnekMoveTimer>,
+    (paint, palette, mut canvas): (Res<PaintMode>, Res<PaintPalette>, ResMut<Canvas>),
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    (mut brushes, mut strokes): (Local<Vec<Handle<ColorMaterial>>>, Local<usize>),
+    (reset_events, mut reset_reader): (Res<Events<ResetGame>>, Local<EventReader<ResetGame>>),
+    heads: Query<With<SnekHead, &Position>>,
+    tiles: Query<With<PaintTile, Entity>>,
+) {
+    if !paint.0 {
+        return;
+    }
+    let reset = reset_reader.iter(&reset_events).next().is_some();
+    if reset || keyboard_input.just_pressed(KeyCode::C) {
+        for entity in tiles.iter() {
+            commands.despawn(entity);
+        }
+        canvas.0.clear();
+        *strokes = 0;
+        return;
+    }
+    if !snek_timer.finished || palette.0.is_empty() {
+        return;
+    }
+    if brushes.is_empty() {
+        *brushes = palette
+            .0
+            .iter()
+            .map(|color| materials.add((*color).into()))
+            .collect();
+    }
+    for pos in heads.iter() {
+        if !canvas.0.insert(*pos) {
+            continue;
+        }
+        commands
+            .spawn(SpriteComponents {
+                material: brushes[*strokes % brushes.len()].clone(),
+                ..Default::default()
+            })
+            .with(PaintTile)
+            .with(*pos)
+            .with(Size::square(1.0))
+            .with(Layer::PAINT);
+        *strokes += 1;
+    }
+}
+
+pub(crate) fn spawn_fog(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    fog: Res<FogOfWar>,
+    arena: Res<ArenaSize>,
+) {
+    if fog.0.is_none() {
+        return;
+    }
+    for pos in arena.cells() {
+        commands
+            .spawn(SpriteComponents {
+                material: materials.fog_material.clone(),
+                ..Default::default()
+            })
+            .with(FogTile)
+            .with(pos)
+            .with(Size::square(1.0))
+            .with(Layer::FOG);
+    }
+}
+
+/// Lays out the [`WrapPeek`] inset in the top right corner, hidden until `wrap_peek` needs it.
+pub(crate) fn spawn_wrap_peek(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    peek: Res<WrapPeek>,
+) {
+    if !peek.0 {
+        return;
+    }
+    for dx in -PEEK_RADIUS..=PEEK_RADIUS {
+        for dy in -PEEK_RADIUS..=PEEK_RADIUS {
+            commands
+                .spawn(NodeComponents {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            right: Val::Px(10.0 + (PEEK_RADIUS - dx) as f32 * PEEK_CELL_SIZE),
+                            top: Val::Px(70.0 + (PEEK_RADIUS - dy) as f32 * PEEK_CELL_SIZE),
+                            ..Default::default()
+                        },
+                        // Leave a pixel between cells.
+                        size: bevy::math::Size::new(
+                            Val::Px(PEEK_CELL_SIZE - 1.0),
+                            Val::Px(PEEK_CELL_SIZE - 1.0),
+                        ),
+                        ..Default::default()
+                    },
+                    material: materials.peek_material.clone(),
+                    draw: Draw {
+                        is_visible: false,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(PeekCell { dx, dy });
+        }
+    }
+}
+
+/// The [`MoveHint`] highlight, hidden until `move_hint` has a cell for it.
+pub(crate) fn spawn_move_hint(mut commands: Commands, materials: Res<Materials>) {
+    commands
+        .spawn(SpriteComponents {
+            material: materials.hint_material.clone(),
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(HintMarker)
+        .with(Position { x: 0, y: 0 })
+        .with(Size::square(1.0))
+        .with(Layer::MARKINGS);
+}
+
+/// One dash per cell along every edge, plus one to scroll in from the end, all hidden until
+/// `wrap_seams` lays them out.
+pub(crate) fn spawn_wrap_seams(
+    mut commands: Commands,
+    seams: Res<WrapSeams>,
+    arena: Res<ArenaSize>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let style = match &seams.0 {
+        Some(style) => style,
+        None => return,
+    };
+    let material = materials.add(style.color.into());
+    for &(side, cells) in &[
+        (Direction::Left, arena.height),
+        (Direction::Up, arena.width),
+        (Direction::Right, arena.height),
+        (Direction::Down, arena.width),
+    ] {
+        for index in 0..=cells {
+            commands
+                .spawn(SpriteComponents {
+                    material: material.clone(),
+                    draw: Draw {
+                        is_visible: false,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(SeamDash { side, index });
+        }
+    }
+}
+
+pub(crate) fn spawn_one_way_cells(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    one_way: Res<OneWayCells>,
+) {
+    for cell in one_way.0.iter() {
+        commands
+            .spawn(SpriteComponents {
+                material: materials.one_way_material.clone(),
+                // `position_translation` only moves sprites, so the rotation sticks.
+                transform: Transform::from_rotation(Quat::from_rotation_z(cell.allowed.angle())),
+                ..Default::default()
+            })
+            .with(OneWayMarker)
+            .with(cell.position)
+            .with(Size::square(1.0))
+            .with(Layer::MARKINGS);
+    }
+}
+
+/// Marks both cells of the [`Portals`] pair, each linked to the other. They are there for the
+/// whole session, like the one-way cells, so a reset leaves them be.
+pub(crate) fn spawn_portals(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    portals: Res<Portals>,
+) {
+    if let Some((a, b)) = portals.0 {
+        for (position, link) in [(a, b), (b, a)].iter().copied() {
+            commands
+                .spawn(SpriteComponents {
+                    material: materials.portal_material.clone(),
+                    ..Default::default()
+                })
+                .with(Portal { link })
+                .with(position)
+                .with(Size::square(1.0))
+                .with(Layer::MARKINGS);
+        }
+    }
+}
+
+/// Sets up the first run. Unless it starts out running, as headless, its snake only spawns
+/// once the title menu starts the game.
+pub(crate) fn game_setup(
+    commands: Commands,
+    (materials, padding, spacing): (Res<Materials>, Res<CellPadding>, Res<BodySpacing>),
+    state: Res<GameState>,
+    lives: Res<Lives>,
+    mut lives_left: ResMut<LivesLeft>,
+    (chain, mut level): (Res<FoodChain>, ResMut<Level>),
+    (food_seed, mut food_rng): (Res<FoodSeed>, ResMut<FoodRng>),
+    (mut path, segments): (ResMut<SnekPath>, ResMut<SnekSegments>),
+) {
+    lives_left.0 = lives.0;
+    *food_rng = FoodRng::new(*food_seed);
+    if let Some(config) = &chain.0 {
+        *level = Level::new(1, config);
+    }
+    if *state == GameState::Running {
+        spawn_initial_snake(
+            commands, &materials, &padding, &spacing, &mut path, segments,
+        );
+    }
+}
+
+/// Picks a random cell of the arena that is not in `occupied`.
+pub(crate) fn random_free_cell(
+    arena: ArenaSize,
+    occupied: &HashSet<Position>,
+    rng: &mut impl Rng,
+) -> Option<Position> {
+    let free: Vec<Position> = arena
+        .cells()
+        .filter(|pos| !occupied.contains(pos))
+        .collect();
+    random_cell(&free, rng)
+}
+
+/// Picks a food cell from the whole arena first, and only among the free cells if that one
+/// is taken. The n-th roll of a [`FoodSeed`] so lands on the same cell for every player,
+/// wherever their snake happens to be.
+pub(crate) fn seeded_free_cell(
+    arena: ArenaSize,
+    occupied: &HashSet<Position>,
+    rng: &mut impl Rng,
+) -> Option<Position> {
+    let cells: Vec<Position> = arena.cells().collect();
+    random_cell(&cells, rng)
+        .filter(|pos| !occupied.contains(pos))
+        .or_else(|| random_free_cell(arena, occupied, rng))
+}
+
+/// Picks a free cell at most `radius` cells away from `center` on either axis.
+pub(crate) fn random_free_cell_near(
+    arena: ArenaSize,
+    occupied: &HashSet<Position>,
+    center: Position,
+    radius: u32,
+    rng: &mut impl Rng,
+) -> Option<Position> {
+    let radius = radius as i32;
+    let free: Vec<Position> = (center.x - radius..=center.x + radius)
+        .flat_map(|x| (center.y - radius..=center.y + radius).map(move |y| Position { x, y }))
+        .filter(|pos| arena.contains(*pos) && !occupied.contains(pos))
+        .collect();
+    random_cell(&free, rng)
+}
+
+pub(crate) fn random_cell(free: &[Position], rng: &mut impl Rng) -> Option<Position> {
+    if free.is_empty() {
+        return None;
+    }
+    Some(free[rng.gen_range(0, free.len())])
+}
+
+pub(crate) fn enemy_spawner(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    padding: Res<CellPadding>,
+    spawns: Res<EnemySpawns>,
+    arena: Res<ArenaSize>,
+    enemies: Query<&Enemy>,
+    heads: Query<With<SnekHead, &Position>>,
+    segments: Query<With<SnekSegment, &Position>>,
+    food: Query<With<Food, &Position>>,
+) {
+    if spawns.0.is_empty() || enemies.iter().next().is_some() {
+        return;
+    }
+    let mut occupied: HashSet<Position> = heads
+        .iter()
+        .chain(segments.iter())
+        .chain(food.iter())
+        .copied()
+        .collect();
+    for pattern in spawns.0.iter() {
+        if let Some(pos) = random_free_cell(*arena, &occupied, &mut rand::thread_rng()) {
+            occupied.insert(pos);
+            commands
+                .spawn(SpriteComponents {
+                    material: materials.enemy_material.clone(),
+                    ..Default::default()
+                })
+                .with(Enemy { pattern: *pattern })
+                .with(pos)
+                .with(Size::square(padding.food));
+        }
+    }
+}
+
+/// Where the head of every fresh snake starts out.
+pub(crate) const START_POSITION: Position = Position { x: 3, y: 3 };
+
+pub(crate) fn spawn_initial_snake(
+    mut commands: Commands,
+    materials: &Res<Materials>,
+    padding: &CellPadding,
+    spacing: &BodySpacing,
+    path: &mut SnekPath,
+    mut segments: ResMut<SnekSegments>,
+) {
+    let head = START_POSITION;
+    let spacing = spacing.0.max(1) as i32;
+    // Pretend the snake came straight up, far enough back for the tail to grow into, see
+    // `snek_movement`.
+    path.0 = (0..3 * spacing)
+        .map(|dy| Position {
+            x: head.x,
+            y: head.y - dy,
+        })
+        .collect();
+    let first_segment = spawn_segment(
+        &mut commands,
+        &materials.segment_material,
+        padding,
+        path.0[spacing as usize],
+    );
+    segments.0 = vec![first_segment];
+
+    commands
+        .spawn(SpriteComponents {
+            material: materials.head_material.clone(),
+            sprite: Sprite::new(Vec2::new(10.0, 10.0)),
+            ..Default::default()
+        })
+        .with(SnekHead {
+            direction: Direction::Up,
+            turns: VecDeque::new(),
+            ticks_since_turn: u32::MAX,
+        })
+        .with(head)
+        .with(Size::square(padding.head));
+}
+
+pub(crate) fn food_spawner(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    padding: Res<CellPadding>,
+    (magnet_config, wall_break_config, poison_config, reverse_config, invincible_config): (
+        Res<MagnetConfig>,
+        Res<WallBreakConfig>,
+        Res<PoisonConfig>,
+        Res<ReverseConfig>,
+        Res<InvincibleConfig>,
+    ),
+    (double_score_config, phase_config, split_config, explosive_config): (
+        Res<DoubleScoreConfig>,
+        Res<PhaseConfig>,
+        Res<SplitConfig>,
+        Res<ExplosiveConfig>,
+    ),
+    (bonus_config, slow_config, mega_config): (
+        Res<BonusFoodConfig>,
+        Res<SlowConfig>,
+        Res<MegaFoodConfig>,
+    ),
+    (food_spawn, max_food, last_tail_position, arena): (
+        Res<FoodSpawn>,
+        Res<MaxFood>,
+        Res<LastTailPosition>,
+        Res<ArenaSize>,
+    ),
+    (time, run_time, snek_timer, mut recorder, replay): (
+        Res<Time>,
+        Res<RunTime>,
+        Res<SnekMoveTimer>,
+        ResMut<ReplayRecorder>,
+        Res<LoadedReplay>,
+    ),
+    (paused, paint, one_way, portals, enabled, mut food_rng): (
+        Res<Paused>,
+        Res<PaintMode>,
+        Res<OneWayCells>,
+        Res<Portals>,
+        Res<EnabledFoodKinds>,
+        ResMut<FoodRng>,
+    ),
+    mut timer: ResMut<FoodSpawnTimer>,
+    (mut previous, mut clusters, mut owed): (Local<Option<Position>>, Local<u32>, Local<bool>),
+    heads: Query<With<SnekHead, &Position>>,
+    segments: Query<With<SnekSegment, &Position>>,
+    enemies: Query<With<Enemy, &Position>>,
+    food: Query<With<Food, (&Position, &FoodKind)>>,
+    walls: Query<With<Wall, &Position>>,
+    rivals: Query<With<Rival, &Position>>,
+) {
+    if paused.0 || paint.0 {
+        return;
+    }
+    let occupied = || -> HashSet<Position> {
+        heads
+            .iter()
+            .chain(segments.iter())
+            .chain(rivals.iter())
+            .chain(enemies.iter())
+            .chain(food.iter().map(|(pos, _)| pos))
+            .chain(walls.iter())
+            .copied()
+            .chain(one_way.positions())
+            .chain(portals.positions())
+            .collect()
+    };
+    if let Some(kinds) = &enabled.0 {
+        let mut missing: Vec<FoodKind> = kinds
+            .iter()
+            .copied()
+            .filter(|kind| food.iter().all(|(_, live)| live != kind))
+            .collect();
+        // Sets have no order of their own; keep the spawn order stable.
+        missing.sort();
+        let mut occupied = occupied();
+        for kind in missing {
+            if let Some(position) = seeded_free_cell(*arena, &occupied, &mut food_rng.next_spawn())
+            {
+                occupied.insert(position);
+                spawn_food(
+                    &mut commands,
+                    &materials,
+                    &padding,
+                    kind,
+                    position,
+                    &run_time,
+                );
+            }
+        }
+        return;
+    }
+    timer.0.tick(gameplay_delta(&time));
+    *owed |= timer.0.finished;
+    // Food waits for the snake's next move, so a replay can spawn it on the same tick as
+    // when it was recorded, however long its frames take.
+    if !snek_timer.finished {
+        return;
+    }
+    let due = match &replay.0 {
+        Some(log) => log
+            .food
+            .get(recorder.food.len())
+            .is_some_and(|tick| *tick <= recorder.tick),
+        None => std::mem::take(&mut *owed),
+    };
+    // A slow player would otherwise come back to a board full of uneaten food.
+    if due && food.iter().count() < max_food.0 as usize {
+        let tick = recorder.tick;
+        recorder.food.push(tick);
+        let mut rng = food_rng.next_spawn();
+        let mut roll = rng.gen::<f32>();
+        let mut kind = FoodKind::Normal;
+        for &(candidate, chance) in &[
+            (FoodKind::Magnet, magnet_config.spawn_chance),
+            (FoodKind::WallBreak, wall_break_config.spawn_chance),
+            (FoodKind::Poison, poison_config.spawn_chance),
+            (FoodKind::Reverse, reverse_config.spawn_chance),
+            (FoodKind::Invincible, invincible_config.spawn_chance),
+            (FoodKind::DoubleScore, double_score_config.spawn_chance),
+            (FoodKind::Phase, phase_config.spawn_chance),
+            (FoodKind::Split, split_config.spawn_chance),
+            (FoodKind::Explosive, explosive_config.spawn_chance),
+            (FoodKind::Bonus, bonus_config.spawn_chance),
+            (FoodKind::Slow, slow_config.spawn_chance),
+            (
+                FoodKind::Mega {
+                    growth: mega_config.growth,
+                },
+                mega_config.spawn_chance,
+            ),
+        ] {
+            if roll < chance {
+                kind = candidate;
+                break;
+            }
+            roll -= chance;
+        }
+        if let FoodSpawn::Cluster { size, .. } = *food_spawn {
+            if let Some(cells) = random_cluster(*arena, &occupied(), size, &mut rng) {
+                *clusters += 1;
+                for (i, position) in cells.into_iter().enumerate() {
+                    let kind = if i == 0 { kind } else { FoodKind::Normal };
+                    spawn_food(
+                        &mut commands,
+                        &materials,
+                        &padding,
+                        kind,
+                        position,
+                        &run_time,
+                    );
+                    commands.with(FoodCluster(*clusters));
+                }
+            }
+            return;
+        }
+        let preferred = match (*food_spawn, *previous) {
+            (FoodSpawn::NearPrevious { radius }, Some(center)) => {
+                random_free_cell_near(*arena, &occupied(), center, radius, &mut rng)
+            }
+            // The tail is only known once the snake has moved, and may have grown back into it.
+            (FoodSpawn::AtTail, _) => last_tail_position
+                .0
+                .filter(|tail| !occupied().contains(tail)),
+            _ => None,
+        };
+        // Taken cells include walls and one-way cells too: food on a one-way cell could only
+        // be eaten from one side and food in a wall not at all. With the arena full, nothing
+        // spawns until a cell frees up.
+        let position = match preferred.or_else(|| seeded_free_cell(*arena, &occupied(), &mut rng)) {
+            Some(position) => position,
+            None => return,
+        };
+        *previous = Some(position);
+        spawn_food(
+            &mut commands,
+            &materials,
+            &padding,
+            kind,
+            position,
+            &run_time,
+        );
+    }
+}
+
+/// Picks `size` connected free cells, growing outward from a random start.
+///
+/// Every free cell is tried as the start before giving up, so this only fails when no
+/// connected patch of free cells is big enough.
+fn random_cluster(
+    arena: ArenaSize,
+    occupied: &HashSet<Position>,
+    size: u32,
+    rng: &mut impl Rng,
+) -> Option<Vec<Position>> {
+    let mut starts: Vec<Position> = arena
+        .cells()
+        .filter(|pos| !occupied.contains(pos))
+        .collect();
+    starts.shuffle(rng);
+    let size = size.max(1) as usize;
+    for start in starts {
+        let mut cells = vec![start];
+        while cells.len() < size {
+            let frontier: Vec<Position> = cells
+                .iter()
+                .flat_map(|cell| {
+                    [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                        .iter()
+                        .map(move |(dx, dy)| Position {
+                            x: cell.x + dx,
+                            y: cell.y + dy,
+                        })
+                })
+                .filter(|pos| {
+                    arena.contains(*pos) && !occupied.contains(pos) && !cells.contains(pos)
+                })
+                .collect();
+            match random_cell(&frontier, rng) {
+                Some(next) => cells.push(next),
+                None => break,
+            }
+        }
+        if cells.len() == size {
+            return Some(cells);
+        }
+    }
+    None
+}
+
+pub(crate) fn spawn_food(
+    commands: &mut Commands,
+    materials: &Materials,
+    padding: &CellPadding,
+    kind: FoodKind,
+    position: Position,
+    run_time: &RunTime,
+) {
+    commands
+        .spawn(SpriteComponents {
+            material: materials.food(kind).clone(),
+            ..Default::default()
+        })
+        .with(Food)
+        .with(kind)
+        .with(SpawnedAt(run_time.0))
+        .with(position)
+        .with(Size::square(padding.food));
+}
+
+/// Takes away bonus food left uneaten for longer than [`BonusFoodConfig::lifetime`].
+pub(crate) fn bonus_food_lifetime(
+    mut commands: Commands,
+    config: Res<BonusFoodConfig>,
+    run_time: Res<RunTime>,
+    food: Query<With<Food, (Entity, &FoodKind, &SpawnedAt)>>,
+) {
+    for (entity, kind, spawned_at) in food.iter() {
+        let age = run_time.0.checked_sub(spawned_at.0).unwrap_or_default();
+        if *kind == FoodKind::Bonus && age >= config.lifetime {
+            commands.despawn(entity);
+        }
+    }
+}
+
+pub(crate) fn food_scatter(
+    snek_timer: Res<SnekMoveTimer>,
+    interval: Res<ScatterInterval>,
+    (one_way, portals): (Res<OneWayCells>, Res<Portals>),
+    arena: Res<ArenaSize>,
+    mut food_rng: ResMut<FoodRng>,
+    mut ticks: Local<u32>,
+    heads: Query<With<SnekHead, &Position>>,
+    segments: Query<With<SnekSegment, &Position>>,
+    enemies: Query<With<Enemy, &Position>>,
+    walls: Query<With<Wall, &Position>>,
+    mut food: Query<With<Food, &mut Position>>,
+) {
+    let interval = match interval.0 {
+        Some(interval) if interval > 0 => interval,
+        _ => return,
+    };
+    if !snek_timer.finished {
+        return;
+    }
+    *ticks += 1;
+    if *ticks < interval {
+        return;
+    }
+    *ticks = 0;
+    let mut occupied: HashSet<Position> = heads
+        .iter()
+        .chain(segments.iter())
+        .chain(enemies.iter())
+        .chain(walls.iter())
+        .copied()
+        .chain(one_way.positions())
+        .chain(portals.positions())
+        .collect();
+    for mut pos in food.iter_mut() {
+        if let Some(free) = seeded_free_cell(*arena, &occupied, &mut food_rng.next_spawn()) {
+            *pos = free;
+        }
+        occupied.insert(*pos);
+    }
+}
+
+pub(crate) fn coin_drop(
+    mut commands: Commands,
+    snek_timer: Res<SnekMoveTimer>,
+    coin_drops: Res<CoinDrops>,
+    (materials, padding): (Res<Materials>, Res<CellPadding>),
+    last_tail_position: Res<LastTailPosition>,
+) {
+    let config = match &coin_drops.0 {
+        Some(config) => config,
+        None => return,
+    };
+    if !snek_timer.finished {
+        return;
+    }
+    let position = match last_tail_position.0 {
+        Some(position) => position,
+        None => return,
+    };
+    if rand::thread_rng().gen::<f32>() >= config.chance {
+        return;
+    }
+    commands
+        .spawn(SpriteComponents {
+            material: materials.coin_material.clone(),
+            ..Default::default()
+        })
+        .with(Coin {
+            value: config.value,
+            lifetime: Timer::new(config.lifetime, false),
+        })
+        .with(position)
+        .with(Size::square(padding.food * 0.6));
+}
+
+pub(crate) fn spawn_segment(
+    commands: &mut Commands,
+    material: &Handle<ColorMaterial>,
+    padding: &CellPadding,
+    position: Position,
+) -> Entity {
+    commands
+        .spawn(SpriteComponents {
+            material: material.clone(),
+            ..SpriteComponents::default()
+        })
+        .with(SnekSegment)
+        .with(position)
+        .with(Size::square(padding.segment));
+    commands
+        .current_entity()
+        .expect("spawn always sets the current entity")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+    use rand::RngCore;
+
+    use super::{random_cluster, random_free_cell, seeded_free_cell};
+    use crate::components::{FoodKind, PaintTile, Position};
+    use crate::resources::{
+        ArenaSize, Canvas, EnabledFoodKinds, FixedStep, FoodSeed, FoodSpawn, FoodSpawnTimer,
+        LastTailPosition, LoadedReplay, MaxFood, PaintMode, Paused, ReplayLog, ReplayRecorder,
+        ScatterInterval, Score, WallMode,
+    };
+    use crate::testing::{
+        food, place_food, press_key, quiet_app, release_key, run_tick, run_ticks,
+    };
+    use crate::DailyChallenge;
+
+    /// The food on the board after each of `ticks` moves, with `setup` applied on top of a
+    /// wrapping arena that spawns food every 0.3s.
+    fn food_by_tick(ticks: usize, setup: impl FnOnce(&mut Resources)) -> (Vec<Vec<Position>>, App) {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(MaxFood(10));
+            resources.insert(FoodSpawnTimer(Timer::from_seconds(0.3, true)));
+            setup(resources);
+        });
+        let history = (0..ticks)
+            .map(|_| {
+                run_tick(&mut app);
+                let mut cells: Vec<Position> = food(&app).into_iter().map(|(pos, _)| pos).collect();
+                cells.sort_by_key(|pos| (pos.x, pos.y));
+                cells
+            })
+            .collect();
+        (history, app)
+    }
+
+    #[test]
+    fn replays_spawn_food_after_the_recorded_ticks() {
+        let (recorded, app) = food_by_tick(12, |resources| {
+            resources.insert(FoodSeed(Some(7)));
+        });
+        let recorder = app.resources.get::<ReplayRecorder>().unwrap();
+        assert!(!recorder.food.is_empty());
+        let log = ReplayLog {
+            seed: 7,
+            turns: recorder.turns.clone(),
+            food: recorder.food.clone(),
+        };
+
+        // Slower frames that would put the food timer on other ticks.
+        let (replayed, _) = food_by_tick(12, |resources| {
+            resources.insert(FoodSeed(Some(log.seed)));
+            resources.insert(LoadedReplay(Some(log)));
+            resources.insert(FixedStep(Some(Duration::from_millis(100))));
+        });
+        assert_eq!(recorded, replayed);
+    }
+
+    /// An `Rng` that only ever comes up with the ends of its range, starting at `.0`.
+    struct Extremes(usize);
+
+    impl RngCore for Extremes {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let values = [u64::MAX, 0, u64::MAX - 1, 1];
+            self.0 += 1;
+            values[self.0 % values.len()]
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            let byte = self.next_u64() as u8;
+            dest.iter_mut().for_each(|b| *b = byte);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn extreme_rolls_still_land_food_in_the_arena() {
+        let arenas = [
+            ArenaSize::default(),
+            ArenaSize {
+                width: 4,
+                height: 5,
+            },
+            ArenaSize {
+                width: 17,
+                height: 9,
+            },
+        ];
+        for &arena in &arenas {
+            let cells: HashSet<Position> = arena.cells().collect();
+            // With the corners taken, the ends of the free list sit right next to them.
+            let occupied: HashSet<Position> = cells
+                .iter()
+                .copied()
+                .filter(|pos| {
+                    (pos.x == 0 || pos.x == arena.width as i32 - 1)
+                        && (pos.y == 0 || pos.y == arena.height as i32 - 1)
+                })
+                .collect();
+            for start in 0..4 {
+                let cell = random_free_cell(arena, &occupied, &mut Extremes(start)).unwrap();
+                assert!(cells.contains(&cell) && !occupied.contains(&cell));
+                let cell = seeded_free_cell(arena, &occupied, &mut Extremes(start)).unwrap();
+                assert!(cells.contains(&cell) && !occupied.contains(&cell));
+                let cluster = random_cluster(arena, &occupied, 3, &mut Extremes(start)).unwrap();
+                assert_eq!(cluster.len(), 3);
+                assert!(cluster
+                    .iter()
+                    .all(|cell| cells.contains(cell) && !occupied.contains(cell)));
+            }
+        }
+    }
+
+    #[test]
+    fn scattered_food_moves_every_other_tick() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(ScatterInterval(Some(2)));
+        });
+        place_food(&mut app, FoodKind::Normal, Position { x: 0, y: 0 });
+        place_food(&mut app, FoodKind::Normal, Position { x: 9, y: 9 });
+        let cells = |app: &App| {
+            let mut cells: Vec<Position> = food(app).into_iter().map(|(pos, _)| pos).collect();
+            cells.sort_by_key(|pos| (pos.x, pos.y));
+            cells
+        };
+        let mut before = cells(&app);
+        for tick in 1..=6 {
+            run_tick(&mut app);
+            let after = cells(&app);
+            assert_eq!(after.len(), 2);
+            assert_eq!(after != before, tick % 2 == 0, "on tick {}", tick);
+            before = after;
+        }
+    }
+
+    #[test]
+    fn trap_food_lands_where_the_tail_just_was() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(MaxFood(1));
+            resources.insert(FoodSpawn::AtTail);
+            resources.insert(FoodSpawnTimer(Timer::from_seconds(0.3, true)));
+        });
+        run_tick(&mut app);
+        assert!(food(&app).is_empty());
+        // The food timer runs out between the first and second move.
+        run_tick(&mut app);
+        let tail = app.resources.get::<LastTailPosition>().unwrap().0;
+        assert_eq!(tail, Some(Position { x: 3, y: 3 }));
+        let cells: Vec<Position> = food(&app).into_iter().map(|(pos, _)| pos).collect();
+        assert_eq!(cells, vec![tail.unwrap()]);
+    }
+
+    #[test]
+    fn enabled_kinds_keep_one_food_each_on_the_board() {
+        let mut app = quiet_app(|resources| {
+            let kinds = [FoodKind::Normal, FoodKind::Poison]
+                .iter()
+                .copied()
+                .collect();
+            resources.insert(EnabledFoodKinds(Some(kinds)));
+        });
+        let kinds = |app: &App| {
+            let mut kinds: Vec<FoodKind> = food(app).into_iter().map(|(_, kind)| kind).collect();
+            kinds.sort();
+            kinds
+        };
+        app.update();
+        assert_eq!(kinds(&app), vec![FoodKind::Normal, FoodKind::Poison]);
+
+        // Put the normal food right in front of the head to have it eaten.
+        let normal = app
+            .world
+            .query::<(Entity, &FoodKind)>()
+            .find(|(_, kind)| **kind == FoodKind::Normal)
+            .map(|(entity, _)| entity)
+            .unwrap();
+        *app.world.get_mut::<Position>(normal).unwrap() = Position { x: 3, y: 4 };
+        run_tick(&mut app);
+        assert_eq!(app.resources.get::<Score>().unwrap().0, 1);
+        app.update();
+        assert_eq!(kinds(&app), vec![FoodKind::Normal, FoodKind::Poison]);
+        assert!(food(&app)
+            .iter()
+            .all(|(pos, _)| *pos != Position { x: 3, y: 4 }));
+    }
+
+    #[test]
+    fn a_daily_seed_places_the_same_food_however_the_snake_moves() {
+        // A day on which none of the three snakes gets in the way of a food roll.
+        let seed = DailyChallenge::for_day(20_001).seed();
+        let food_after_turning = |turn: Option<KeyCode>| {
+            let mut app = quiet_app(|resources| {
+                resources.insert(seed);
+                resources.insert(WallMode::Wrap);
+                resources.insert(MaxFood(10));
+                resources.insert(FoodSpawnTimer(Timer::from_seconds(0.3, true)));
+            });
+            if let Some(key) = turn {
+                press_key(&mut app, key);
+            }
+            run_ticks(&mut app, 10);
+            let mut cells: Vec<Position> = food(&app).into_iter().map(|(pos, _)| pos).collect();
+            cells.sort_by_key(|pos| (pos.x, pos.y));
+            cells
+        };
+        let straight = food_after_turning(None);
+        assert_eq!(straight.len(), 8);
+        assert_eq!(food_after_turning(Some(KeyCode::Left)), straight);
+        assert_eq!(food_after_turning(Some(KeyCode::Right)), straight);
+    }
+
+    #[test]
+    fn painted_cells_are_kept_until_the_canvas_is_cleared() {
+        let mut app = quiet_app(|resources| resources.insert(PaintMode(true)));
+        let painted = |app: &App| {
+            let mut cells: Vec<Position> = app
+                .resources
+                .get::<Canvas>()
+                .unwrap()
+                .0
+                .iter()
+                .copied()
+                .collect();
+            cells.sort_by_key(|pos| pos.y);
+            (cells, app.world.query::<&PaintTile>().count())
+        };
+        run_ticks(&mut app, 3);
+        app.update();
+        let cell = |y| Position { x: 3, y };
+        assert_eq!(painted(&app), (vec![cell(4), cell(5), cell(6)], 3));
+
+        press_key(&mut app, KeyCode::C);
+        app.update();
+        release_key(&mut app, KeyCode::C);
+        app.update();
+        assert_eq!(painted(&app), (vec![], 0));
+    }
+
+    #[test]
+    fn unpausing_releases_no_backlog_of_food() {
+        let mut app = quiet_app(|resources| {
+            resources.insert(WallMode::Wrap);
+            resources.insert(MaxFood(10));
+            resources.insert(FoodSpawnTimer(Timer::from_seconds(1.0, true)));
+        });
+        let tap_pause = |app: &mut App| {
+            press_key(app, KeyCode::Space);
+            app.update();
+            release_key(app, KeyCode::Space);
+            app.update();
+        };
+        while food(&app).is_empty() {
+            run_tick(&mut app);
+        }
+        // Right after a spawn, the next one is three moves off.
+        tap_pause(&mut app);
+        assert!(app.resources.get::<Paused>().unwrap().0);
+        // Long enough for several spawns, were the timer still running.
+        for _ in 0..180 {
+            app.update();
+        }
+        assert_eq!(food(&app).len(), 1);
+
+        tap_pause(&mut app);
+        assert!(!app.resources.get::<Paused>().unwrap().0);
+        run_ticks(&mut app, 2);
+        assert_eq!(food(&app).len(), 1);
+    }
+
+    #[test]
+    fn trail_food_lands_within_the_radius_of_the_last() {
+        let (history, _) = food_by_tick(20, |resources| {
+            resources.insert(MaxFood(8));
+            resources.insert(FoodSpawn::NearPrevious { radius: 2 });
+        });
+        let mut spawned = Vec::new();
+        let mut before: HashSet<Position> = HashSet::new();
+        for cells in history {
+            let after: HashSet<Position> = cells.into_iter().collect();
+            let new: Vec<Position> = after.difference(&before).copied().collect();
+            assert!(new.len() <= 1);
+            spawned.extend(new);
+            before = after;
+        }
+        assert!(spawned.len() >= 5);
+        for pair in spawned.windows(2) {
+            let (last, next) = (pair[0], pair[1]);
+            assert!((next.x - last.x).abs() <= 2 && (next.y - last.y).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn a_cluster_lands_as_one_connected_group() {
+        let (history, _) = food_by_tick(6, |resources| {
+            resources.insert(MaxFood(1));
+            resources.insert(FoodSpawn::Cluster { size: 3, bonus: 5 });
+        });
+        let cluster = history.into_iter().find(|cells| !cells.is_empty()).unwrap();
+        assert_eq!(cluster.len(), 3);
+        // Everything can be reached from the first cell through its neighbors.
+        let mut reached = vec![cluster[0]];
+        let mut i = 0;
+        while i < reached.len() {
+            let cell = reached[i];
+            for &next in &cluster {
+                let touching = (next.x - cell.x).abs() + (next.y - cell.y).abs() == 1;
+                if touching && !reached.contains(&next) {
+                    reached.push(next);
+                }
+            }
+            i += 1;
+        }
+        assert_eq!(reached.len(), cluster.len());
+    }
+}
@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+
+use super::spawn::hud_text;
+use crate::components::TutorialText;
+use crate::events::ResetGame;
+use crate::resources::{GameState, SaveFiles, Tutorial, TutorialShown};
+use crate::{HUD_FONT_PATH, TUTORIAL_PATH};
+
+/// Starts in the tutorial unless it was already shown on an earlier launch.
+pub(crate) fn spawn_tutorial(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    shown: Res<TutorialShown>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    tutorial.0 = !shown.0;
+    commands
+        .spawn(hud_text(
+            asset_server.load(HUD_FONT_PATH),
+            Rect {
+                left: Val::Px(10.0),
+                top: Val::Px(70.0),
+                ..Default::default()
+            },
+        ))
+        .with(TutorialText);
+}
+
+/// Runs the practice round: `F1` brings it back, `Enter` leaves it for a fresh real game.
+///
+/// Dying while practicing only starts the practice over, see `game_over`.
+pub(crate) fn tutorial(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<GameState>,
+    (mut tutorial, mut shown, save_files): (
+        ResMut<Tutorial>,
+        ResMut<TutorialShown>,
+        Res<SaveFiles>,
+    ),
+    mut reset_events: ResMut<Events<ResetGame>>,
+    mut texts: Query<With<TutorialText, &mut Text>>,
+) {
+    // On the title menu `Enter` only starts the game; the practice round, if any, begins then.
+    let running = *state == GameState::Running;
+    if running && !tutorial.0 && keyboard_input.just_pressed(KeyCode::F1) {
+        tutorial.0 = true;
+        reset_events.send(ResetGame);
+    } else if running && tutorial.0 && keyboard_input.just_pressed(KeyCode::Return) {
+        tutorial.0 = false;
+        reset_events.send(ResetGame);
+        if !shown.0 {
+            shown.0 = true;
+            if save_files.0 {
+                if let Err(e) = shown.save(TUTORIAL_PATH) {
+                    eprintln!("failed to save tutorial marker to {}: {}", TUTORIAL_PATH, e);
+                }
+            }
+        }
+    }
+    for mut text in texts.iter_mut() {
+        text.value = if running && tutorial.0 {
+            "Practice: arrow keys or WASD steer, eat food to grow. Enter starts the game."
+                .to_string()
+        } else {
+            String::new()
+        };
+    }
+}
@@ -0,0 +1,234 @@
+//! Helpers for driving the game headlessly from tests.
+//!
+//! A test builds an [`App`] with [`SnekPlugin`](crate::SnekPlugin), queues key presses with
+//! [`press_key`] and [`release_key`], and then calls `app.update()` to have `snek_movement`
+//! and friends see them. On [`build_headless_app`](crate::build_headless_app), [`start`] and
+//! [`run_tick`] step it one move of the snake at a time.
+
+use bevy::input::{keyboard::KeyboardInput, ElementState};
+use bevy::prelude::*;
+
+#[cfg(test)]
+use crate::components::{Direction, Food, FoodKind, Position, SnekHead, Wall};
+use crate::resources::SnekMoveTimer;
+#[cfg(test)]
+use crate::resources::{CellPadding, FoodSeed, Materials, MaxFood, RunTime, SnekSegments};
+#[cfg(test)]
+use crate::systems::{spawn_food, spawn_wall};
+
+/// Frames [`run_tick`] waits for a move before giving up, a few seconds' worth.
+const MAX_FRAMES_PER_TICK: usize = 600;
+
+/// Takes the app out of `builder` and runs its startup systems, ready for its first update.
+pub fn start(builder: AppBuilder) -> App {
+    let mut app = builder.app;
+    app.initialize();
+    app
+}
+
+/// Updates `app` until the snake has made its next move, and returns how many frames it took.
+///
+/// Panics when no move comes, like while the game is paused.
+pub fn run_tick(app: &mut App) -> usize {
+    run_tick_watching(app, |_| ())
+}
+
+/// Like [`run_tick`], calling `watch` on `app` after every frame.
+fn run_tick_watching(app: &mut App, mut watch: impl FnMut(&App)) -> usize {
+    for frame in 1..=MAX_FRAMES_PER_TICK {
+        app.update();
+        watch(app);
+        let timer = app
+            .resources
+            .get::<SnekMoveTimer>()
+            .expect("SnekPlugin is added");
+        if timer.0.finished {
+            return frame;
+        }
+    }
+    panic!("the snake did not move in {} frames", MAX_FRAMES_PER_TICK);
+}
+
+/// Runs `ticks` moves of the snake, see [`run_tick`].
+#[cfg(test)]
+pub(crate) fn run_ticks(app: &mut App, ticks: usize) {
+    for _ in 0..ticks {
+        run_tick(app);
+    }
+}
+
+/// Like [`run_tick`], returning how many `E` events were sent on the way.
+#[cfg(test)]
+pub(crate) fn run_tick_counting<E: Send + Sync + 'static>(app: &mut App) -> usize {
+    let mut reader = app
+        .resources
+        .get::<Events<E>>()
+        .expect("the event is added")
+        .get_reader_current();
+    let mut sent = 0;
+    run_tick_watching(app, |app| {
+        let events = app.resources.get::<Events<E>>().unwrap();
+        sent += reader.iter(&events).count();
+    });
+    sent
+}
+
+/// A started [`build_headless_app`](crate::build_headless_app) with a fixed [`FoodSeed`]
+/// and no food of its own, after `setup` has had its say on the resources.
+#[cfg(test)]
+pub(crate) fn quiet_app(setup: impl FnOnce(&mut Resources)) -> App {
+    let mut builder = crate::build_headless_app();
+    builder
+        .add_resource(MaxFood(0))
+        .add_resource(FoodSeed(Some(1)));
+    setup(builder.resources_mut());
+    start(builder)
+}
+
+/// Runs the commands `spawn` queues right away, as a system's would be at the end of a stage.
+#[cfg(test)]
+pub(crate) fn spawn_now(app: &mut App, spawn: impl FnOnce(&mut Commands, &Resources)) {
+    let mut commands = Commands::default();
+    commands.set_entity_reserver(app.world.get_entity_reserver());
+    spawn(&mut commands, &app.resources);
+    commands.apply(&mut app.world, &mut app.resources);
+}
+
+/// Puts a piece of `kind` food on `position`.
+#[cfg(test)]
+pub(crate) fn place_food(app: &mut App, kind: FoodKind, position: Position) {
+    spawn_now(app, |commands, resources| {
+        spawn_food(
+            commands,
+            &resources.get::<Materials>().unwrap(),
+            &resources.get::<CellPadding>().unwrap(),
+            kind,
+            position,
+            &resources.get::<RunTime>().unwrap(),
+        )
+    });
+}
+
+/// Puts a wall on `position`.
+#[cfg(test)]
+pub(crate) fn place_wall(app: &mut App, position: Position) {
+    spawn_now(app, |commands, resources| {
+        spawn_wall(
+            commands,
+            &resources.get::<Materials>().unwrap(),
+            &resources.get::<CellPadding>().unwrap(),
+            position,
+        )
+    });
+}
+
+/// Where the player's head is and where it is heading.
+#[cfg(test)]
+pub(crate) fn head(app: &App) -> (Position, Direction) {
+    app.world
+        .query::<(&SnekHead, &Position)>()
+        .map(|(head, pos)| (*pos, head.direction))
+        .next()
+        .expect("the snake is on the board")
+}
+
+/// The cells of the player's body behind the head, nearest first.
+#[cfg(test)]
+pub(crate) fn body(app: &App) -> Vec<Position> {
+    let segments = app.resources.get::<SnekSegments>().unwrap();
+    segments
+        .0
+        .iter()
+        .map(|segment| *app.world.get::<Position>(*segment).unwrap())
+        .collect()
+}
+
+/// Every piece of food on the board, in no particular order.
+#[cfg(test)]
+pub(crate) fn food(app: &App) -> Vec<(Position, FoodKind)> {
+    app.world
+        .query::<(&Food, &Position, &FoodKind)>()
+        .map(|(_, pos, kind)| (*pos, *kind))
+        .collect()
+}
+
+/// Every wall on the board, in no particular order.
+#[cfg(test)]
+pub(crate) fn walls(app: &App) -> Vec<Position> {
+    app.world
+        .query::<(&Wall, &Position)>()
+        .map(|(_, pos)| *pos)
+        .collect()
+}
+
+/// Presses `key` for the next `app.update()`, so it reads as both pressed and just pressed.
+///
+/// With the input plugin loaded the press goes through a [`KeyboardInput`] event, which
+/// keeps `just_pressed` correct across updates. Without it the [`Input<KeyCode>`] resource
+/// is set directly and stays just pressed until the test releases the key.
+pub fn press_key(app: &mut App, key: KeyCode) {
+    send_key(app, key, ElementState::Pressed);
+}
+
+/// Releases `key` for the next `app.update()`.
+pub fn release_key(app: &mut App, key: KeyCode) {
+    send_key(app, key, ElementState::Released);
+}
+
+fn send_key(app: &mut App, key: KeyCode, state: ElementState) {
+    if let Some(mut events) = app.resources.get_mut::<Events<KeyboardInput>>() {
+        events.send(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(key),
+            state,
+        });
+        return;
+    }
+    if app.resources.get::<Input<KeyCode>>().is_none() {
+        app.resources.insert(Input::<KeyCode>::default());
+    }
+    let mut input = app
+        .resources
+        .get_mut::<Input<KeyCode>>()
+        .expect("input was just inserted");
+    match state {
+        ElementState::Pressed => input.press(key),
+        ElementState::Released => {
+            input.release(key);
+            input.reset(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_headless_app;
+
+    #[test]
+    fn keys_read_as_pressed_until_released() {
+        let mut app = build_headless_app().app;
+        app.initialize();
+
+        press_key(&mut app, KeyCode::Up);
+        app.update();
+        {
+            let input = app.resources.get::<Input<KeyCode>>().unwrap();
+            assert!(input.just_pressed(KeyCode::Up));
+            assert!(input.pressed(KeyCode::Up));
+        }
+
+        app.update();
+        {
+            let input = app.resources.get::<Input<KeyCode>>().unwrap();
+            assert!(!input.just_pressed(KeyCode::Up));
+            assert!(input.pressed(KeyCode::Up));
+        }
+
+        release_key(&mut app, KeyCode::Up);
+        app.update();
+        let input = app.resources.get::<Input<KeyCode>>().unwrap();
+        assert!(input.just_released(KeyCode::Up));
+        assert!(!input.pressed(KeyCode::Up));
+    }
+}